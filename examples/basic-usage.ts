@@ -14,7 +14,7 @@ import { AnchorProvider, Wallet, BN } from "@coral-xyz/anchor";
 import PayGuardClient, { AIArbitrator } from "../sdk/src";
 
 // Configuration
-const PROGRAM_ID = new PublicKey("PayGUARD1111111111111111111111111111111111111");
+const PROGRAM_ID = new PublicKey("PayGUARD11111111111111111111111111111111111");
 const USDC_MINT = new PublicKey("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"); // Mainnet USDC
 
 async function main() {
@@ -66,7 +66,7 @@ async function main() {
 
   // Step 4: Client approves milestone (happy path)
   console.log("4. Client approves milestone 1...");
-  // client.approveMilestone(contractId, 0, freelancer, escrowVault, freelancerTokenAccount)
+  // client.approveMilestone(contractId, 0, freelancer, escrowVault)
   console.log("   [Simulated] 300 USDC released to freelancer\n");
 
   // Step 5: Dispute scenario
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+declare_id!("CaLLBackMock1111111111111111111111111111111");
+
+/// Stand-in for a marketplace program that wants to hear about completed
+/// PayGuard contracts. Only used by the integration tests.
+#[program]
+pub mod callback_mock {
+    use super::*;
+
+    /// Log the completion notice so tests can assert on it
+    pub fn on_payguard_completion(ctx: Context<OnPayguardCompletion>, notice: CompletionNotice) -> Result<()> {
+        msg!(
+            "PayGuard contract {} completed: total={} released={} refunded={} (signed by {})",
+            notice.contract_id,
+            notice.total_amount,
+            notice.released_amount,
+            notice.refunded_amount,
+            ctx.accounts.contract.key()
+        );
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct OnPayguardCompletion<'info> {
+    /// PayGuard contract PDA, signing the notification
+    pub contract: Signer<'info>,
+}
+
+/// Mirrors `payguard::CompletionNotice`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompletionNotice {
+    pub contract_id: u64,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub refunded_amount: u64,
+}
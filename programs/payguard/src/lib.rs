@@ -1,60 +1,405 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_lang::solana_program::{ed25519_program, sysvar};
+use anchor_lang::system_program;
+use anchor_spl::token;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{
+    instruction as transfer_fee_ix, TransferFeeAmount, TransferFeeConfig,
+};
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::{Account as Token2022Account, Mint as Token2022Mint};
+use anchor_spl::token_interface::{self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked};
+use pyth_sdk_solana::{load_price_feed_from_account_info, Price};
 
-declare_id!("PayGUARD1111111111111111111111111111111111111");
+declare_id!("PayGUARD11111111111111111111111111111111111");
+
+/// Basis point denominator (100%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Anchor-style instruction name invoked on a completion callback program
+pub const COMPLETION_CALLBACK_IX_NAME: &[u8] = b"global:on_payguard_completion";
+
+/// Highest platform fee a contract may charge on releases (10%)
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Milestones a contract account is created with room for
+pub const MAX_INITIAL_MILESTONES: usize = 10;
+
+/// Most milestones a contract can grow to, one bit each in `milestone_status_bitmap`
+pub const MAX_MILESTONES: usize = 32;
+
+/// Maximum deliverable artifacts attached to one milestone submission
+pub const MAX_PROOF_HASHES: usize = 5;
+
+/// Maximum parties whose approval can be required to cancel a contract
+pub const MAX_CANCEL_APPROVERS: usize = 3;
+
+/// Maximum arbitrators on a contract's dispute panel
+pub const MAX_ARBITRATORS: usize = 5;
+
+/// Appeals allowed per dispute; a decision remade after the last one can only be settled
+pub const MAX_APPEALS: u8 = 1;
+
+/// Maximum payment mints on the config's whitelist
+pub const MAX_ALLOWED_MINTS: usize = 16;
+
+/// Resolved disputes kept in a contract's history; the oldest is dropped first
+pub const MAX_DISPUTE_LOG: usize = 8;
+
+/// Fixed-point scale of `Milestone::display_rate`
+pub const DISPLAY_RATE_SCALE: u64 = 1_000_000;
+
+/// Reputation of a newly registered arbitrator, in basis points
+pub const INITIAL_REPUTATION_BPS: u16 = 10_000;
+
+/// Reputation regained by resolving a dispute within the SLA
+pub const SLA_MET_REPUTATION_BPS: u16 = 100;
+
+/// Reputation lost by resolving a dispute after the SLA
+pub const SLA_MISSED_REPUTATION_BPS: u16 = 1_000;
+
+/// How long a dispute must sit unresolved before governance may step in, until configured
+pub const DEFAULT_GOVERNANCE_STALENESS_SECS: i64 = 30 * SECONDS_PER_DAY;
+
+/// Domain prefix of the message a client signs to approve a milestone off-chain,
+/// followed by the program id, the contract id (u64 LE), the milestone index, its
+/// revision count and its submission time (i64 LE)
+pub const APPROVAL_MESSAGE_PREFIX: &[u8] = b"payguard:approve_milestone:";
+
+/// Byte offset of `Contract::description_hash` in account data
+/// (discriminator, id, client, freelancer, token_mint, arbitrator)
+pub const DESCRIPTION_HASH_OFFSET: usize = 8 + 8 + 32 * 4;
+
+/// Byte offset of `Contract::milestone_status_bitmap` in account data
+pub const MILESTONE_BITMAP_OFFSET: usize = DESCRIPTION_HASH_OFFSET + 32;
+
+/// Number of `MilestoneStatus` variants, the length of `ContractSummary::status_counts`
+pub const MILESTONE_STATUS_COUNT: usize = 13;
+
+/// Build the `DecisionPayout` for a payout instruction from its accounts
+///
+/// Every payout instruction names its accounts alike, so only how the
+/// freelancer's and restitution token accounts are passed varies.
+macro_rules! decision_payout {
+    ($ctx:ident, freelancer: $freelancer:expr, restitution: $restitution:expr $(,)?) => {
+        DecisionPayout {
+            config: &$ctx.accounts.config,
+            token: TokenCpi::new(&$ctx.accounts.token_program, &$ctx.accounts.token_mint),
+            bonus_mint: $ctx.accounts.bonus_mint.as_ref(),
+            escrow_vault: &$ctx.accounts.escrow_vault,
+            freelancer_token_account: $freelancer,
+            client_token_account: &$ctx.accounts.client_token_account,
+            client: &$ctx.accounts.client,
+            bonus_vault: $ctx.accounts.bonus_vault.as_ref(),
+            freelancer_bonus_token_account: $ctx.accounts.freelancer_bonus_token_account.as_ref(),
+            fee_treasury_token_account: $ctx.accounts.fee_treasury_token_account.as_ref(),
+            restitution_token_account: $restitution,
+            remaining_accounts: $ctx.remaining_accounts,
+        }
+    };
+}
 
 #[program]
 pub mod payguard {
     use super::*;
 
     /// Create a new escrow contract between client and freelancer
+    ///
+    /// Passing no milestones creates a `Draft` for the freelancer to scope with
+    /// `propose_milestones`. The escrow vault is created alongside as a PDA of
+    /// the contract id, owned by the contract.
     pub fn create_contract(
         ctx: Context<CreateContract>,
         contract_id: u64,
         total_amount: u64,
-        milestones: Vec<Milestone>,
+        milestones: Vec<MilestoneInput>,
         description_hash: [u8; 32],
+        terms: ContractTerms,
     ) -> Result<()> {
+        let new = NewContract {
+            id: contract_id,
+            client: ctx.accounts.client.key(),
+            freelancer: ctx.accounts.freelancer.key(),
+            // Arbitrator is set to the PayGuard oracle by default
+            // In production, this would be a multisig or DAO-controlled address
+            arbitrator: ctx.accounts.arbitrator.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            escrow_vault: ctx.accounts.escrow_vault.key(),
+            total_amount,
+            milestones: new_milestones(milestones),
+            description_hash,
+            terms,
+        };
+        init_contract(
+            &mut ctx.accounts.contract,
+            new,
+            &ctx.accounts.config,
+            &ctx.accounts.client_kyc,
+            &ctx.accounts.freelancer_kyc,
+            ctx.bumps.contract,
+        )
+    }
+
+    /// Propose the milestone breakdown for a draft contract (freelancer)
+    ///
+    /// May be called again to revise the proposal until the client accepts.
+    pub fn propose_milestones(ctx: Context<ProposeMilestones>, milestones: Vec<MilestoneInput>) -> Result<()> {
+        let mut milestones = new_milestones(milestones);
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Draft, PayGuardError::ContractNotDraft);
+        if contract.terms.amounts_in_bps {
+            normalize_bps_amounts(&mut milestones, contract.total_amount)?;
+        }
+        validate_milestones(
+            &milestones,
+            contract.total_amount,
+            &ctx.accounts.config,
+            &contract.terms,
+            MAX_INITIAL_MILESTONES,
+        )?;
+        
+        contract.bonus_total = checked_sum(milestones.iter().map(|m| m.bonus_amount))?;
+        contract.milestones = milestones;
+        
+        Ok(())
+    }
+
+    /// Accept the proposed milestones, locking them and activating the contract (client)
+    pub fn accept_milestones(ctx: Context<AcceptMilestones>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Draft, PayGuardError::ContractNotDraft);
+        require!(!contract.milestones.is_empty(), PayGuardError::InvalidMilestones);
+        if contract.terms.require_kyc {
+            let now = Clock::get()?.unix_timestamp;
+            check_kyc(&ctx.accounts.client_kyc, &ctx.accounts.config, now)?;
+            check_kyc(&ctx.accounts.freelancer_kyc, &ctx.accounts.config, now)?;
+        }
+        
+        contract.status = ContractStatus::Active;
+        
+        Ok(())
+    }
+
+    /// Accept the contract's terms and milestones, activating it (freelancer)
+    ///
+    /// Until then the client can neither fund nor start work on the contract.
+    pub fn accept_contract(ctx: Context<AcceptContract>) -> Result<()> {
         let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Pending, PayGuardError::ContractNotPending);
         
-        require!(milestones.len() > 0 && milestones.len() <= 10, PayGuardError::InvalidMilestones);
+        contract.status = ContractStatus::Active;
         
-        let total_milestone_amount: u64 = milestones.iter().map(|m| m.amount).sum();
-        require!(total_milestone_amount == total_amount, PayGuardError::AmountMismatch);
+        Ok(())
+    }
+
+    /// Replace the milestones and total of a contract not yet funded (client + freelancer)
+    ///
+    /// Both parties sign so scope only changes by agreement; once money has moved
+    /// the milestones are fixed.
+    pub fn amend_contract(
+        ctx: Context<AmendContract>,
+        milestones: Vec<MilestoneInput>,
+        total_amount: u64,
+    ) -> Result<()> {
+        let mut milestones = new_milestones(milestones);
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(contract.status, ContractStatus::Active | ContractStatus::Pending),
+            PayGuardError::ContractNotActive
+        );
+        require!(contract.funded_amount == 0, PayGuardError::CannotAmendFunded);
+        if contract.terms.amounts_in_bps {
+            normalize_bps_amounts(&mut milestones, total_amount)?;
+        }
+        // A grown account keeps its space, so it may be amended back up to its current size
+        let capacity = MAX_INITIAL_MILESTONES.max(contract.milestones.len());
+        validate_milestones(&milestones, total_amount, &ctx.accounts.config, &contract.terms, capacity)?;
         
-        contract.id = contract_id;
-        contract.client = ctx.accounts.client.key();
-        contract.freelancer = ctx.accounts.freelancer.key();
-        contract.token_mint = ctx.accounts.token_mint.key();
-        // Arbitrator is set to the PayGuard oracle by default
-        // In production, this would be a multisig or DAO-controlled address
-        contract.arbitrator = ctx.accounts.arbitrator.key();
         contract.total_amount = total_amount;
-        contract.released_amount = 0;
+        contract.bonus_total = checked_sum(milestones.iter().map(|m| m.bonus_amount))?;
         contract.milestones = milestones;
-        contract.description_hash = description_hash;
-        contract.status = ContractStatus::Active;
-        contract.created_at = Clock::get()?.unix_timestamp;
-        contract.bump = ctx.bumps.contract;
+        contract.refresh_status_bitmap();
+        
+        Ok(())
+    }
+
+    /// Append milestones to a contract not yet funded, growing its account (client + freelancer)
+    ///
+    /// The account is reallocated to fit, with the client paying the extra rent,
+    /// up to `MAX_MILESTONES` in all. The total grows by the added amounts.
+    pub fn add_milestones(ctx: Context<AddMilestones>, milestones: Vec<MilestoneInput>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(contract.status, ContractStatus::Active | ContractStatus::Pending),
+            PayGuardError::ContractNotActive
+        );
+        require!(contract.funded_amount == 0, PayGuardError::CannotAmendFunded);
+        // Basis-point amounts are shares of a fixed total, which appending would change
+        require!(
+            !milestones.is_empty() && !contract.terms.amounts_in_bps,
+            PayGuardError::InvalidMilestones
+        );
+        
+        let added = checked_sum(milestones.iter().map(|m| m.amount))?;
+        let total_amount = contract.total_amount.checked_add(added).ok_or(PayGuardError::Overflow)?;
+        let mut all = contract.milestones.clone();
+        all.extend(new_milestones(milestones));
+        validate_milestones(&all, total_amount, &ctx.accounts.config, &contract.terms, MAX_MILESTONES)?;
+        
+        contract.total_amount = total_amount;
+        contract.bonus_total = checked_sum(all.iter().map(|m| m.bonus_amount))?;
+        contract.milestones = all;
+        contract.refresh_status_bitmap();
         
         Ok(())
     }
 
     /// Fund the escrow with tokens
+    ///
+    /// The initial funding may cover only part of the total; the rest follows
+    /// with `top_up_escrow`. `amount` may also exceed `total_amount` so that mints
+    /// charging a transfer fee still deliver the full contract value; what
+    /// matters is what the vault receives, which may not exceed `funding_cap`.
     pub fn fund_escrow(ctx: Context<FundEscrow>, amount: u64) -> Result<()> {
         let contract = &ctx.accounts.contract;
         require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
-        require!(amount == contract.total_amount, PayGuardError::AmountMismatch);
+        require!(contract.funded_amount == 0, PayGuardError::AlreadyFunded);
+        require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+        require!(contract.terms.sponsor.is_none(), PayGuardError::SponsorOnly);
+        require!(amount > 0, PayGuardError::AmountMismatch);
+        
+        let accounts = &mut *ctx.accounts;
+        let received = deposit_to_vault(
+            &accounts.config,
+            TokenCpi::new(&accounts.token_program, &accounts.token_mint),
+            &accounts.funder_token_account,
+            &accounts.funder,
+            &mut accounts.escrow_vault,
+            amount,
+        )?;
+        let cap = funding_cap(&accounts.token_mint, &accounts.contract)?;
+        require!(received <= cap, PayGuardError::OverFunded);
+        ctx.accounts.contract.funded_amount = received;
+        
+        emit!(EscrowFunded {
+            contract_id: ctx.accounts.contract.id,
+            funder: ctx.accounts.funder.key(),
+            amount: received,
+            funded_amount: received,
+            funded_at: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Add funds to a partially funded escrow
+    ///
+    /// Cumulative funding may never exceed `funding_cap`.
+    pub fn top_up_escrow(ctx: Context<FundEscrow>, amount: u64) -> Result<()> {
+        let contract = &ctx.accounts.contract;
+        // Disputed contracts may still need topping up to pay a decision out
+        require!(
+            matches!(contract.status, ContractStatus::Active | ContractStatus::Disputed),
+            PayGuardError::ContractNotActive
+        );
+        require!(contract.funded_amount > 0, PayGuardError::EscrowNotFunded);
+        require!(contract.terms.sponsor.is_none(), PayGuardError::SponsorOnly);
+        require!(amount > 0, PayGuardError::AmountMismatch);
+        
+        let accounts = &mut *ctx.accounts;
+        let received = deposit_to_vault(
+            &accounts.config,
+            TokenCpi::new(&accounts.token_program, &accounts.token_mint),
+            &accounts.funder_token_account,
+            &accounts.funder,
+            &mut accounts.escrow_vault,
+            amount,
+        )?;
+        let cap = funding_cap(&accounts.token_mint, &accounts.contract)?;
+        let contract = &mut ctx.accounts.contract;
+        let funded = contract.funded_amount.checked_add(received).ok_or(PayGuardError::Overflow)?;
+        require!(funded <= cap, PayGuardError::OverFunded);
+        contract.funded_amount = funded;
+        
+        emit!(EscrowFunded {
+            contract_id: contract.id,
+            funder: ctx.accounts.funder.key(),
+            amount: received,
+            funded_amount: funded,
+            funded_at: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Fund or top up the escrow from the contract's sponsor
+    ///
+    /// Sponsored contracts are funded only this way, and cancellation refunds
+    /// the sponsor rather than the client.
+    pub fn fund_escrow_sponsored(ctx: Context<FundEscrowSponsored>, amount: u64) -> Result<()> {
+        let contract = &ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(amount > 0, PayGuardError::AmountMismatch);
+        
+        let accounts = &mut *ctx.accounts;
+        let received = deposit_to_vault(
+            &accounts.config,
+            TokenCpi::new(&accounts.token_program, &accounts.token_mint),
+            &accounts.sponsor_token_account,
+            &accounts.sponsor,
+            &mut accounts.escrow_vault,
+            amount,
+        )?;
+        let cap = funding_cap(&accounts.token_mint, &accounts.contract)?;
+        let contract = &mut ctx.accounts.contract;
+        let funded = contract.funded_amount.checked_add(received).ok_or(PayGuardError::Overflow)?;
+        require!(funded <= cap, PayGuardError::OverFunded);
+        contract.funded_amount = funded;
+        
+        emit!(EscrowFunded {
+            contract_id: contract.id,
+            funder: ctx.accounts.sponsor.key(),
+            amount: received,
+            funded_amount: contract.funded_amount,
+            funded_at: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Fund a SOL-escrow contract with lamports (client)
+    ///
+    /// May be called repeatedly; cumulative funding may never exceed `total_amount`.
+    pub fn fund_escrow_sol(ctx: Context<FundEscrowSol>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(contract.escrows_sol(), PayGuardError::NotSolEscrow);
+        require!(amount > 0, PayGuardError::AmountMismatch);
+        
+        let funded = contract.funded_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+        require!(funded <= contract.total_amount, PayGuardError::OverFunded);
         
-        // Transfer tokens from client to escrow vault
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.client_token_account.to_account_info(),
-            to: ctx.accounts.escrow_vault.to_account_info(),
-            authority: ctx.accounts.client.to_account_info(),
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.client.to_account_info(),
+            to: ctx.accounts.sol_vault.to_account_info(),
         };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, amount)?;
+        contract.funded_amount = funded;
+        
+        emit!(EscrowFunded {
+            contract_id: contract.id,
+            funder: ctx.accounts.client.key(),
+            amount,
+            funded_amount: funded,
+            funded_at: Clock::get()?.unix_timestamp,
+        });
         
         Ok(())
     }
@@ -63,324 +408,4712 @@ pub mod payguard {
     pub fn submit_milestone(
         ctx: Context<SubmitMilestone>,
         milestone_index: u8,
-        proof_hash: [u8; 32],
+        proof_hashes: Vec<[u8; 32]>,
+        format: Option<DeliverableFormat>,
     ) -> Result<()> {
+        require!(!proof_hashes.is_empty(), PayGuardError::MissingProof);
+        require!(proof_hashes.len() <= MAX_PROOF_HASHES, PayGuardError::TooManyProofs);
+        
         let contract = &mut ctx.accounts.contract;
+        require!(contract.status != ContractStatus::Disputed, PayGuardError::ContractDisputed);
         require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(contract.funded_amount > 0, PayGuardError::EscrowNotFunded);
         require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        require!(!contract.is_paused(Clock::get()?.unix_timestamp), PayGuardError::ContractPaused);
+        // Ordered contracts take work one milestone at a time; revoked
+        // milestones drop out of the order
+        if contract.terms.ordered {
+            let previous = contract.milestones[..milestone_index as usize]
+                .iter()
+                .rev()
+                .find(|m| m.status != MilestoneStatus::Cancelled);
+            require!(
+                previous.map_or(true, |m| matches!(m.status, MilestoneStatus::Approved | MilestoneStatus::Resolved)),
+                PayGuardError::PreviousMilestoneIncomplete
+            );
+        }
         
+        // Funded value not yet settled by earlier milestones
+        let available = contract.funded_amount.saturating_sub(contract.released_amount);
+        let contract_id = contract.id;
+        let resubmit_window = contract.terms.resubmit_window;
         let milestone = &mut contract.milestones[milestone_index as usize];
-        require!(milestone.status == MilestoneStatus::Pending, PayGuardError::MilestoneNotPending);
+        match (milestone.status.clone(), milestone.rejected_at) {
+            (MilestoneStatus::Pending, _) => {}
+            // A rejection may be answered with a new submission while the window is open
+            (MilestoneStatus::Rejected, Some(rejected_at)) if resubmit_window > 0 => require!(
+                Clock::get()?.unix_timestamp
+                    <= rejected_at.checked_add(resubmit_window).ok_or(PayGuardError::Overflow)?,
+                PayGuardError::ResubmitWindowClosed
+            ),
+            // Rejections upheld by a dispute, or made without a window, stay open to revision
+            (MilestoneStatus::Rejected, _) => {}
+            _ => return err!(PayGuardError::MilestoneNotPending),
+        }
+        if milestone.status == MilestoneStatus::Rejected {
+            require!(
+                milestone.max_revisions == 0 || milestone.revision_count < milestone.max_revisions,
+                PayGuardError::RevisionLimitReached
+            );
+            // Unlimited revisions stop counting at 255 rather than overflow
+            milestone.revision_count = milestone.revision_count.saturating_add(1);
+        }
+        // A committed format must be matched exactly by the submission
+        require!(
+            milestone.expected_format.is_none() || format == milestone.expected_format,
+            PayGuardError::FormatMismatch
+        );
         
+        let now = Clock::get()?.unix_timestamp;
         milestone.status = MilestoneStatus::Submitted;
-        milestone.proof_hash = Some(proof_hash);
-        milestone.submitted_at = Some(Clock::get()?.unix_timestamp);
+        milestone.proof_hashes = proof_hashes;
+        milestone.submitted_at = Some(now);
+        milestone.rejected_at = None;
+        // Late work is still accepted; the flag lets penalties and UIs account for it
+        milestone.late = milestone.due_date.is_some_and(|due_date| now > due_date);
+        
+        emit!(MilestoneSubmitted {
+            contract_id,
+            milestone_index,
+            freelancer: ctx.accounts.freelancer.key(),
+            submitted_at: now,
+            late: milestone.late,
+        });
+        
+        // Prompt the client to top up before this milestone can be approved
+        if milestone.amount > available {
+            emit!(LowEscrowWarning {
+                contract_id,
+                milestone_index,
+                shortfall: milestone.amount - available,
+            });
+        }
         
         Ok(())
     }
 
-    /// Approve milestone and release funds (client)
-    pub fn approve_milestone(ctx: Context<ApproveMilestone>, milestone_index: u8) -> Result<()> {
+    /// Approve milestone and credit its payout for `withdraw_funds` (client)
+    pub fn approve_milestone<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveMilestone<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let payout = decision_payout!(
+            ctx,
+            freelancer: None,
+            restitution: None,
+        );
+        release_milestone(&payout, &mut ctx.accounts.contract, milestone_index)
+    }
+
+    /// Approve several submitted milestones at once (client)
+    ///
+    /// Each index is approved as `approve_milestone` would, and the contract
+    /// is checked for completion once at the end. Any index that is out of
+    /// range, repeated or not `Submitted` reverts the whole batch.
+    pub fn approve_milestones<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveMilestone<'info>>,
+        milestone_indices: Vec<u8>,
+    ) -> Result<()> {
+        require!(!milestone_indices.is_empty(), PayGuardError::InvalidMilestoneIndex);
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: None,
+            restitution: None,
+        );
         let contract = &mut ctx.accounts.contract;
-        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        for milestone_index in milestone_indices {
+            approve_submitted(&payout, contract, milestone_index)?;
+        }
+        complete_if_released(&payout, contract)
+    }
+
+    /// Approve a milestone on the client's behalf from an off-chain signature (relayer)
+    ///
+    /// The transaction must carry an Ed25519 program instruction, immediately
+    /// before this one, verifying the client's signature over
+    /// `approval_message(contract, milestone_index)`. The message names the
+    /// submission it approves, so it cannot be replayed against a later one. The
+    /// relayer pays the fees.
+    pub fn approve_milestone_signed<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveMilestoneSigned<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        verify_client_approval(
+            &ctx.accounts.instructions,
+            &ctx.accounts.contract.client,
+            &approval_message(&ctx.accounts.contract, milestone_index)?,
+        )?;
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: None,
+            restitution: None,
+        );
+        release_milestone(&payout, &mut ctx.accounts.contract, milestone_index)
+    }
+
+    /// Release a submission the client left unreviewed past `terms.review_window` (anyone)
+    ///
+    /// Pays out exactly as `approve_milestone` would, to the contract's own
+    /// token accounts.
+    pub fn claim_timeout_release<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimTimeoutRelease<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let contract = &ctx.accounts.contract;
         require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let review_window = contract.terms.review_window;
+        require!(review_window > 0, PayGuardError::NoReviewWindow);
+        let milestone = &contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Submitted, PayGuardError::MilestoneNotSubmitted);
+        let review_ends_at = milestone
+            .submitted_at
+            .ok_or(PayGuardError::MilestoneNotSubmitted)?
+            .checked_add(review_window)
+            .ok_or(PayGuardError::Overflow)?;
+        require!(Clock::get()?.unix_timestamp > review_ends_at, PayGuardError::ReviewWindowNotElapsed);
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: None,
+            restitution: None,
+        );
+        release_milestone(&payout, &mut ctx.accounts.contract, milestone_index)
+    }
+
+    /// Approve the final milestone and roll the relationship into a renewal (client)
+    ///
+    /// The renewal keeps the parties and terms, takes `milestones` as its
+    /// schedule and is escrowed in full from the client into its associated
+    /// token vault. A `terms.deadline` keeps its length, counted from the renewal.
+    /// A funded bonus vault must already be empty, as `reclaim_bonus` needs the
+    /// old contract to return what is left in it. The approval must complete
+    /// the old contract, which is closed in the same transaction.
+    pub fn complete_and_renew<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompleteAndRenew<'info>>,
+        milestone_index: u8,
+        renewal_id: u64,
+        milestones: Vec<MilestoneInput>,
+        description_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(!ctx.accounts.contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+        let mut terms = ctx.accounts.contract.terms.clone();
+        require!(terms.sponsor.is_none(), PayGuardError::SponsorOnly);
+        require!(!milestones.is_empty(), PayGuardError::InvalidMilestones);
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: Some(&ctx.accounts.freelancer_token_account),
+            restitution: None,
+        );
+        release_milestone(&payout, &mut ctx.accounts.contract, milestone_index)?;
+        
+        // Renewing is the client's sign-off, so a contract awaiting one qualifies
+        require!(
+            matches!(
+                ctx.accounts.contract.status,
+                ContractStatus::Completed | ContractStatus::PendingFinalization
+            ),
+            PayGuardError::RenewalRequiresCompletion
+        );
+        require!(ctx.accounts.contract.fees_owed == 0, PayGuardError::ContractNotClosable);
+        // Unpaid bonus tokens could no longer be reclaimed once the contract closes
+        if ctx.accounts.contract.bonus_vault != Pubkey::default() {
+            require!(
+                ctx.accounts
+                    .bonus_vault
+                    .as_ref()
+                    .is_some_and(|v| v.key() == ctx.accounts.contract.bonus_vault && v.amount == 0),
+                PayGuardError::BonusUnreclaimed
+            );
+        }
+        
+        // The old contract closes here, so whatever it still owes the freelancer is paid now
+        let owed = ctx.accounts.contract.approved_unclaimed;
+        if owed > 0 {
+            ctx.accounts.contract.approved_unclaimed = 0;
+            let token = TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint);
+            transfer_from_vault(
+                token,
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.freelancer_token_account,
+                &ctx.accounts.contract,
+                owed,
+            )?;
+            close_drained_vault(token, &ctx.accounts.escrow_vault, &ctx.accounts.client, &ctx.accounts.contract)?;
+        }
+        
+        let contract = &ctx.accounts.contract;
+        
+        // The deadline is absolute, so the renewal gets the same term from its own start
+        if terms.deadline != 0 {
+            let term = terms.deadline.checked_sub(contract.created_at).ok_or(PayGuardError::Overflow)?;
+            terms.deadline = Clock::get()?.unix_timestamp.checked_add(term).ok_or(PayGuardError::Overflow)?;
+        }
+        
+        let total_amount = checked_sum(milestones.iter().map(|m| m.amount))?;
+        let new = NewContract {
+            id: renewal_id,
+            client: contract.client,
+            freelancer: contract.freelancer,
+            arbitrator: contract.arbitrator,
+            token_mint: contract.token_mint,
+            escrow_vault: ctx.accounts.renewal_vault.key(),
+            total_amount,
+            milestones: new_milestones(milestones),
+            description_hash,
+            terms,
+        };
+        init_contract(
+            &mut ctx.accounts.renewal,
+            new,
+            &ctx.accounts.config,
+            &ctx.accounts.client_kyc,
+            &ctx.accounts.freelancer_kyc,
+            ctx.bumps.renewal,
+        )?;
+        
+        let accounts = &mut *ctx.accounts;
+        let received = deposit_to_vault(
+            &accounts.config,
+            TokenCpi::new(&accounts.token_program, &accounts.token_mint),
+            &accounts.client_token_account,
+            &accounts.client,
+            &mut accounts.renewal_vault,
+            total_amount,
+        )?;
+        // The renewal is escrowed up front but, like any new contract, stays
+        // `Pending` until the freelancer accepts its milestones
+        accounts.renewal.funded_amount = received;
+        
+        emit!(EscrowFunded {
+            contract_id: accounts.renewal.id,
+            funder: accounts.client.key(),
+            amount: received,
+            funded_amount: received,
+            funded_at: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Approve a milestone on a SOL-escrow contract, paying the freelancer in
+    /// `token_mint` through the contract's swap program (client + freelancer)
+    ///
+    /// `swap_data` is the swap program's instruction and its accounts follow in
+    /// `remaining_accounts`, as an aggregator route supplies them. The swap must
+    /// spend exactly the milestone amount from the SOL vault and deliver at least
+    /// `min_amount_out`; the freelancer signs to accept the route and that floor.
+    pub fn approve_milestone_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveMilestoneSwap<'info>>,
+        milestone_index: u8,
+        min_amount_out: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        check_approvable(&ctx.accounts.config, contract, milestone_index)?;
         
         let milestone = &mut contract.milestones[milestone_index as usize];
-        require!(milestone.status == MilestoneStatus::Submitted, PayGuardError::MilestoneNotSubmitted);
+        let amount = milestone.amount;
+        milestone.status = MilestoneStatus::Approved;
+        contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+        contract.refresh_status_bitmap();
+        
+        let sol_vault = &ctx.accounts.sol_vault;
+        let vault_before = sol_vault.lamports();
+        let received_before = ctx.accounts.freelancer_token_account.amount;
+        
+        // Only the SOL vault gains a signature; the route's other accounts pass through as given
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|a| AccountMeta {
+                pubkey: a.key(),
+                is_signer: a.is_signer || a.key() == sol_vault.key(),
+                is_writable: a.is_writable,
+            })
+            .collect();
+        let ix = Instruction {
+            program_id: ctx.accounts.swap_program.key(),
+            accounts,
+            data: swap_data,
+        };
+        let mut infos = ctx.remaining_accounts.to_vec();
+        infos.push(ctx.accounts.swap_program.to_account_info());
+        
+        let contract_key = contract.key();
+        let seeds: &[&[u8]] = &[b"sol_vault", contract_key.as_ref(), &[ctx.bumps.sol_vault]];
+        invoke_signed(&ix, &infos, &[seeds])?;
+        
+        require!(
+            vault_before.checked_sub(sol_vault.lamports()) == Some(amount),
+            PayGuardError::SwapAmountMismatch
+        );
+        ctx.accounts.freelancer_token_account.reload()?;
+        require!(
+            ctx.accounts.freelancer_token_account.amount.saturating_sub(received_before) >= min_amount_out,
+            PayGuardError::SlippageExceeded
+        );
+        
+        emit!(MilestoneApproved {
+            contract_id: contract.id,
+            milestone_index,
+            amount,
+            fee: 0,
+            payout: ctx.accounts.freelancer_token_account.amount - received_before,
+            approved_at: Clock::get()?.unix_timestamp,
+        });
+        
+        if contract.is_fully_released() {
+            finish_contract(contract, ctx.remaining_accounts)?;
+        }
+        
+        Ok(())
+    }
+
+    /// Approve a milestone on a native SOL contract, paying its lamports to the freelancer (client)
+    pub fn approve_milestone_native<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveMilestoneNative<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.terms.is_native, PayGuardError::NotSolEscrow);
+        check_approvable(&ctx.accounts.config, contract, milestone_index)?;
         
+        let milestone = &mut contract.milestones[milestone_index as usize];
         let amount = milestone.amount;
         milestone.status = MilestoneStatus::Approved;
-        contract.released_amount += amount;
-        
-        // Transfer from escrow to freelancer
-        let seeds = &[
-            b"contract",
-            &contract.id.to_le_bytes(),
-            &[contract.bump],
-        ];
-        let signer = &[&seeds[..]];
-        
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.escrow_vault.to_account_info(),
-            to: ctx.accounts.freelancer_token_account.to_account_info(),
-            authority: ctx.accounts.contract.to_account_info(),
+        contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+        contract.refresh_status_bitmap();
+        
+        // The vault must end up empty or still rent-exempt
+        let remaining = ctx.accounts.sol_vault.lamports().saturating_sub(amount);
+        require!(
+            remaining == 0 || remaining >= Rent::get()?.minimum_balance(0),
+            PayGuardError::SolVaultBelowRent
+        );
+        
+        let contract_key = contract.key();
+        let seeds: &[&[u8]] = &[b"sol_vault", contract_key.as_ref(), &[ctx.bumps.sol_vault]];
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.sol_vault.to_account_info(),
+            to: ctx.accounts.freelancer.to_account_info(),
         };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, amount)?;
+        let signer_seeds = &[seeds];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        system_program::transfer(cpi_ctx, amount)?;
         
-        // Check if all milestones completed
-        if contract.released_amount == contract.total_amount {
-            contract.status = ContractStatus::Completed;
+        emit!(MilestoneApproved {
+            contract_id: contract.id,
+            milestone_index,
+            amount,
+            fee: 0,
+            payout: amount,
+            approved_at: Clock::get()?.unix_timestamp,
+        });
+        
+        if contract.is_fully_released() {
+            finish_contract(contract, ctx.remaining_accounts)?;
         }
         
         Ok(())
     }
 
-    /// Raise dispute on a milestone
-    pub fn raise_dispute(
-        ctx: Context<RaiseDispute>,
+    /// Claim a held payout once its clawback window has closed (freelancer)
+    pub fn claim_release<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleHeldRelease<'info>>,
         milestone_index: u8,
-        reason_hash: [u8; 32],
     ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.contract.freelancer,
+            PayGuardError::Unauthorized
+        );
         let contract = &mut ctx.accounts.contract;
-        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
         
         let milestone = &mut contract.milestones[milestone_index as usize];
-        require!(milestone.status == MilestoneStatus::Submitted, PayGuardError::MilestoneNotSubmitted);
+        let claimable_at = milestone.claimable_at.ok_or(PayGuardError::NoHeldPayout)?;
+        require!(Clock::get()?.unix_timestamp >= claimable_at, PayGuardError::ClawbackWindowOpen);
         
-        milestone.status = MilestoneStatus::Disputed;
-        milestone.dispute_reason = Some(reason_hash);
+        let held = milestone.held_payout;
+        milestone.held_payout = 0;
+        milestone.claimable_at = None;
+        contract.held_total = contract.held_total.checked_sub(held).ok_or(PayGuardError::Overflow)?;
+        
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.freelancer_token_account,
+            contract,
+            held,
+        )?;
+        
+        if contract.is_fully_released() {
+            complete_contract(
+                TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.client_token_account,
+                &ctx.accounts.client,
+                contract,
+                ctx.remaining_accounts,
+            )?;
+        }
         
         Ok(())
     }
 
-    /// Resolve dispute with AI arbitration result (oracle/authority)
-    pub fn resolve_dispute(
-        ctx: Context<ResolveDispute>,
+    /// Claim the vested part of a vesting milestone's payout (freelancer)
+    ///
+    /// The payout vests linearly over `vesting_secs` from approval; each claim
+    /// transfers what has vested since the last one.
+    pub fn claim_vested<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleHeldRelease<'info>>,
         milestone_index: u8,
-        decision: DisputeDecision,
-        arbitration_proof: [u8; 32],
     ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.contract.freelancer,
+            PayGuardError::Unauthorized
+        );
         let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
         let milestone = &mut contract.milestones[milestone_index as usize];
-        require!(milestone.status == MilestoneStatus::Disputed, PayGuardError::MilestoneNotDisputed);
+        let vested = milestone
+            .vested_unclaimed(Clock::get()?.unix_timestamp)
+            .ok_or(PayGuardError::NoHeldPayout)?;
+        require!(vested > 0, PayGuardError::NothingToWithdraw);
         
-        milestone.arbitration_proof = Some(arbitration_proof);
+        milestone.vested_claimed = milestone.vested_claimed.checked_add(vested).ok_or(PayGuardError::Overflow)?;
+        contract.held_total = contract.held_total.checked_sub(vested).ok_or(PayGuardError::Overflow)?;
         
-        match decision {
-            DisputeDecision::FavorFreelancer => {
-                milestone.status = MilestoneStatus::Approved;
-                contract.released_amount += milestone.amount;
-                
-                // Release to freelancer
-                let seeds = &[
-                    b"contract",
-                    &contract.id.to_le_bytes(),
-                    &[contract.bump],
-                ];
-                let signer = &[&seeds[..]];
-                
-                let cpi_accounts = Transfer {
-                    from: ctx.accounts.escrow_vault.to_account_info(),
-                    to: ctx.accounts.freelancer_token_account.to_account_info(),
-                    authority: ctx.accounts.contract.to_account_info(),
-                };
-                let cpi_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    cpi_accounts,
-                    signer
-                );
-                token::transfer(cpi_ctx, milestone.amount)?;
-            }
-            DisputeDecision::FavorClient => {
-                milestone.status = MilestoneStatus::Rejected;
-                // Funds stay in escrow for resubmission or refund
-            }
-            DisputeDecision::Split(freelancer_pct) => {
-                let freelancer_amount = (milestone.amount as u128 * freelancer_pct as u128 / 100) as u64;
-                let client_amount = milestone.amount - freelancer_amount;
-                
-                milestone.status = MilestoneStatus::Resolved;
-                contract.released_amount += freelancer_amount;
-                
-                // Transfer split amounts
-                let seeds = &[
-                    b"contract",
-                    &contract.id.to_le_bytes(),
-                    &[contract.bump],
-                ];
-                let signer = &[&seeds[..]];
-                
-                // To freelancer
-                let cpi_accounts = Transfer {
-                    from: ctx.accounts.escrow_vault.to_account_info(),
-                    to: ctx.accounts.freelancer_token_account.to_account_info(),
-                    authority: ctx.accounts.contract.to_account_info(),
-                };
-                let cpi_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    cpi_accounts,
-                    signer
-                );
-                token::transfer(cpi_ctx, freelancer_amount)?;
-                
-                // To client
-                let cpi_accounts = Transfer {
-                    from: ctx.accounts.escrow_vault.to_account_info(),
-                    to: ctx.accounts.client_token_account.to_account_info(),
-                    authority: ctx.accounts.contract.to_account_info(),
-                };
-                let cpi_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    cpi_accounts,
-                    signer
-                );
-                token::transfer(cpi_ctx, client_amount)?;
-            }
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.freelancer_token_account,
+            contract,
+            vested,
+        )?;
+        
+        if contract.is_fully_released() {
+            complete_contract(
+                TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.client_token_account,
+                &ctx.accounts.client,
+                contract,
+                ctx.remaining_accounts,
+            )?;
         }
         
-        // Check completion
-        if contract.released_amount == contract.total_amount {
-            contract.status = ContractStatus::Completed;
+        Ok(())
+    }
+
+    /// Withdraw every approved payout not yet claimed (freelancer)
+    ///
+    /// Approvals only credit `approved_unclaimed`; the tokens stay in the vault
+    /// until the freelancer pulls them here, including after a cancellation.
+    /// Once a completed contract is paid out its emptied vault is closed.
+    pub fn withdraw_funds(ctx: Context<WithdrawFunds>) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        let contract = &mut ctx.accounts.contract;
+        let amount = contract.approved_unclaimed;
+        require!(amount > 0, PayGuardError::NothingToWithdraw);
+        
+        contract.approved_unclaimed = 0;
+        
+        let token = TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint);
+        transfer_from_vault(
+            token,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.freelancer_token_account,
+            contract,
+            amount,
+        )?;
+        
+        let finished = matches!(
+            contract.status,
+            ContractStatus::Completed | ContractStatus::PendingFinalization
+        );
+        if finished && token::accessor::amount(&ctx.accounts.escrow_vault.to_account_info())? == 0 {
+            close_drained_vault(token, &ctx.accounts.escrow_vault, &ctx.accounts.client, contract)?;
         }
         
+        emit!(FundsWithdrawn {
+            contract_id: contract.id,
+            freelancer: ctx.accounts.freelancer.key(),
+            amount,
+            withdrawn_at: Clock::get()?.unix_timestamp,
+        });
+        
         Ok(())
     }
 
-    /// Cancel contract and refund (mutual agreement or timeout)
-    pub fn cancel_contract(ctx: Context<CancelContract>) -> Result<()> {
+    /// Reverse a held release within its clawback window (client + arbitrator)
+    ///
+    /// The held payout returns to the client; a platform fee already paid on the
+    /// release is not refunded.
+    pub fn clawback_release<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleHeldRelease<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.contract.arbitrator,
+            PayGuardError::Unauthorized
+        );
+        require!(ctx.accounts.client.is_signer, PayGuardError::Unauthorized);
         let contract = &mut ctx.accounts.contract;
-        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
         
-        let refund_amount = contract.total_amount - contract.released_amount;
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        let claimable_at = milestone.claimable_at.ok_or(PayGuardError::NoHeldPayout)?;
+        require!(Clock::get()?.unix_timestamp < claimable_at, PayGuardError::ClawbackWindowClosed);
         
-        if refund_amount > 0 {
-            let seeds = &[
-                b"contract",
-                &contract.id.to_le_bytes(),
-                &[contract.bump],
-            ];
-            let signer = &[&seeds[..]];
-            
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.escrow_vault.to_account_info(),
-                to: ctx.accounts.client_token_account.to_account_info(),
-                authority: ctx.accounts.contract.to_account_info(),
-            };
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                cpi_accounts,
-                signer
-            );
-            token::transfer(cpi_ctx, refund_amount)?;
+        let held = milestone.held_payout;
+        milestone.status = MilestoneStatus::ClawedBack;
+        milestone.held_payout = 0;
+        milestone.claimable_at = None;
+        milestone.client_share = milestone.client_share.checked_add(held).ok_or(PayGuardError::Overflow)?;
+        contract.held_total = contract.held_total.checked_sub(held).ok_or(PayGuardError::Overflow)?;
+        contract.refunded_amount = contract.refunded_amount.checked_add(held).ok_or(PayGuardError::Overflow)?;
+        
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+            held,
+        )?;
+        
+        if contract.is_fully_released() {
+            complete_contract(
+                TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.client_token_account,
+                &ctx.accounts.client,
+                contract,
+                ctx.remaining_accounts,
+            )?;
         }
         
-        contract.status = ContractStatus::Cancelled;
+        Ok(())
+    }
+
+    /// Sign off on a fully paid contract created with `terms.require_sign_off` (client)
+    ///
+    /// Completes the contract and only then notifies the completion callback.
+    pub fn finalize_contract<'info>(ctx: Context<'_, '_, '_, 'info, FinalizeContract<'info>>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            contract.status == ContractStatus::PendingFinalization,
+            PayGuardError::NotPendingFinalization
+        );
+        
+        contract.status = ContractStatus::Completed;
+        emit!(ContractFinalized {
+            contract_id: contract.id,
+            client: contract.client,
+            finalized_at: Clock::get()?.unix_timestamp,
+        });
+        emit_completed(contract)?;
+        
+        notify_completion(contract, ctx.remaining_accounts)
+    }
+
+    /// Tip the freelancer on top of an approved milestone (client)
+    ///
+    /// Tips come straight from the client and are fee-exempt unless
+    /// `terms.fee_on_tips` is set.
+    pub fn add_tip(ctx: Context<AddTip>, milestone_index: u8, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        require!(amount > 0, PayGuardError::AmountMismatch);
+        
+        let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        require!(
+            contract.milestones[milestone_index as usize].status == MilestoneStatus::Approved,
+            PayGuardError::MilestoneNotApproved
+        );
+        
+        let (fee, net) = if contract.terms.fee_on_tips {
+            contract.take_fee(amount)
+        } else {
+            (0, amount)
+        };
+        contract.milestones[milestone_index as usize].tip_amount += net;
+        
+        let token = TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint);
+        transfer_tokens(
+            token,
+            &ctx.accounts.client_token_account,
+            &ctx.accounts.freelancer_token_account,
+            &ctx.accounts.client,
+            net,
+        )?;
+        
+        if fee > 0 {
+            let treasury = ctx
+                .accounts
+                .fee_treasury_token_account
+                .as_ref()
+                .ok_or(PayGuardError::FeeAccountMissing)?;
+            transfer_tokens(token, &ctx.accounts.client_token_account, treasury, &ctx.accounts.client, fee)?;
+        }
         
         Ok(())
     }
-}
 
-// ============ ACCOUNTS ============
+    /// Flag a pending milestone whose due date has passed (anyone)
+    ///
+    /// Only for contracts with `terms.flag_missed_deadlines`. The milestone cannot
+    /// be submitted until the freelancer calls `explain_missed_deadline`.
+    pub fn flag_missed_deadline(ctx: Context<FlagMissedDeadline>, milestone_index: u8) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(contract.terms.flag_missed_deadlines, PayGuardError::DeadlineFlaggingDisabled);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Pending, PayGuardError::MilestoneNotPending);
+        let due_date = milestone.due_date.ok_or(PayGuardError::DeadlineNotMissed)?;
+        require!(Clock::get()?.unix_timestamp > due_date, PayGuardError::DeadlineNotMissed);
+        
+        milestone.status = MilestoneStatus::Flagged;
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(contract_id: u64)]
-pub struct CreateContract<'info> {
-    #[account(
-        init,
-        payer = client,
-        space = 8 + Contract::INIT_SPACE,
-        seeds = [b"contract", &contract_id.to_le_bytes()],
-        bump
-    )]
-    pub contract: Account<'info, Contract>,
-    
-    #[account(mut)]
-    pub client: Signer<'info>,
-    
-    /// CHECK: Freelancer pubkey, validated by business logic
-    pub freelancer: AccountInfo<'info>,
+    /// Explain a missed deadline, returning the flagged milestone to pending (freelancer)
+    pub fn explain_missed_deadline(
+        ctx: Context<SubmitMilestone>,
+        milestone_index: u8,
+        explanation_hash: [u8; 32],
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Flagged, PayGuardError::MilestoneNotFlagged);
+        
+        milestone.status = MilestoneStatus::Pending;
+        milestone.deadline_explanation = Some(explanation_hash);
+        
+        Ok(())
+    }
+
+    /// Reject a submitted milestone (client)
+    ///
+    /// Funds stay in escrow and `feedback_hash` records what needs revising. The
+    /// freelancer may resubmit, or escalate the rejection to a dispute within
+    /// `terms.rejection_contest_window`; after that it stands.
+    pub fn reject_milestone(
+        ctx: Context<RejectMilestone>,
+        milestone_index: u8,
+        feedback_hash: [u8; 32],
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Submitted, PayGuardError::MilestoneNotSubmitted);
+        
+        milestone.status = MilestoneStatus::Rejected;
+        milestone.rejected_at = Some(Clock::get()?.unix_timestamp);
+        milestone.feedback_hash = Some(feedback_hash);
+        
+        Ok(())
+    }
+
+    /// Refund a rejected milestone the freelancer did not resubmit in time (anyone)
+    ///
+    /// Runs once both the resubmission and contest windows have passed. Only
+    /// the funded part of the milestone is in the vault to return.
+    pub fn expire_rejected_milestone<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExpireRejectedMilestone<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let resubmit_window = contract.terms.resubmit_window;
+        require!(resubmit_window > 0, PayGuardError::NoResubmitWindow);
+        let window = resubmit_window.max(contract.terms.rejection_contest_window);
+        
+        let available = contract.funded_amount.saturating_sub(contract.released_amount);
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        let rejected_at = match (milestone.status.clone(), milestone.rejected_at) {
+            (MilestoneStatus::Rejected, Some(rejected_at)) => rejected_at,
+            _ => return err!(PayGuardError::MilestoneNotRejected),
+        };
+        require!(
+            Clock::get()?.unix_timestamp > rejected_at.checked_add(window).ok_or(PayGuardError::Overflow)?,
+            PayGuardError::ResubmitWindowOpen
+        );
+        
+        let amount = milestone.amount;
+        milestone.status = MilestoneStatus::Refunded;
+        milestone.client_share = amount;
+        contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+        contract.refunded_amount += amount;
+        contract.refresh_status_bitmap();
+        
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+            amount.min(available),
+        )?;
+        
+        if contract.is_fully_released() {
+            complete_contract(
+                TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.client_token_account,
+                &ctx.accounts.client,
+                contract,
+                ctx.remaining_accounts,
+            )?;
+        }
+        
+        Ok(())
+    }
+
+    /// Take back a milestone whose rejection a dispute upheld (client)
+    ///
+    /// Ends the freelancer's chance to revise it. Only the funded part of the
+    /// milestone is in the vault to return.
+    pub fn claim_rejected_refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimRejectedRefund<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
+        let available = contract.funded_amount.saturating_sub(contract.released_amount);
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        // Rejections by the client alone carry `rejected_at` and are refunded
+        // through `expire_rejected_milestone` once their windows pass
+        require!(
+            milestone.status == MilestoneStatus::Rejected && milestone.rejected_at.is_none(),
+            PayGuardError::MilestoneNotRejected
+        );
+        
+        let amount = milestone.amount;
+        milestone.status = MilestoneStatus::Refunded;
+        milestone.client_share = amount;
+        contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+        contract.refunded_amount += amount;
+        contract.refresh_status_bitmap();
+        
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+            amount.min(available),
+        )?;
+        
+        if contract.is_fully_released() {
+            complete_contract(
+                TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.client_token_account,
+                &ctx.accounts.client,
+                contract,
+                ctx.remaining_accounts,
+            )?;
+        }
+        
+        Ok(())
+    }
+
+    /// Raise dispute on a milestone
+    ///
+    /// Either party may dispute a submission; the freelancer may also contest a
+    /// client rejection while its contest window is open.
+    pub fn raise_dispute(
+        ctx: Context<RaiseDispute>,
+        milestone_index: u8,
+        reason_hash: [u8; 32],
+        dispute_type: DisputeType,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(contract.status, ContractStatus::Active | ContractStatus::Disputed),
+            PayGuardError::ContractNotActive
+        );
+        // Decisions pay out of the token vault, so a SOL escrow cannot be disputed
+        require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+        require!(contract.funded_amount > 0, PayGuardError::EscrowNotFunded);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
+        // Technical disputes go to the expert arbitrator, or to a vote of the
+        // panel when the terms name one; scope disputes to the client's
+        // designated manager
+        let resolver = match dispute_type {
+            DisputeType::Technical if !contract.terms.arbitrators.is_empty() => None,
+            DisputeType::Technical => Some(contract.arbitrator),
+            DisputeType::Scope => Some(contract.terms.scope_resolver.ok_or(PayGuardError::NoScopeResolver)?),
+        };
+        
+        let contest_window = contract.terms.rejection_contest_window;
+        let resolution_window = contract.terms.dispute_resolution_window;
+        let is_freelancer = ctx.accounts.authority.key() == contract.freelancer;
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        match (milestone.status.clone(), milestone.rejected_at) {
+            (MilestoneStatus::Submitted, _) => {}
+            (MilestoneStatus::Rejected, Some(rejected_at)) => {
+                require!(is_freelancer, PayGuardError::Unauthorized);
+                require!(
+                    Clock::get()?.unix_timestamp
+                        <= rejected_at.checked_add(contest_window).ok_or(PayGuardError::Overflow)?,
+                    PayGuardError::ContestWindowClosed
+                );
+            }
+            _ => return err!(PayGuardError::MilestoneNotSubmitted),
+        }
+        
+        milestone.status = MilestoneStatus::Disputed;
+        milestone.dispute_reason = Some(reason_hash);
+        milestone.dispute_type = Some(dispute_type);
+        milestone.dispute_resolver = resolver;
+        milestone.disputed_by = Some(ctx.accounts.authority.key());
+        milestone.dispute_raised_by = Some(ctx.accounts.authority.key());
+        milestone.dispute_bond = 0;
+        milestone.arbitrator_fee_paid = false;
+        milestone.appeal_count = 0;
+        milestone.open_dispute(Clock::get()?.unix_timestamp, resolution_window)?;
+        contract.refresh_dispute_status();
+        
+        emit!(DisputeRaised {
+            contract_id: contract.id,
+            milestone_index,
+            raised_by: ctx.accounts.authority.key(),
+            dispute_type,
+            reason_hash,
+            raised_at: Clock::get()?.unix_timestamp,
+        });
+        
+        // The bond waits in the vault until the decision settles who keeps it
+        if contract.terms.dispute_bond > 0 {
+            let bond = contract.terms.dispute_bond;
+            let accounts = &mut *ctx.accounts;
+            let (Some(raiser_token_account), Some(escrow_vault), Some(token_mint), Some(token_program)) = (
+                accounts.raiser_token_account.as_ref(),
+                accounts.escrow_vault.as_mut(),
+                accounts.token_mint.as_ref(),
+                accounts.token_program.as_ref(),
+            ) else {
+                return err!(PayGuardError::DisputeBondAccountsMissing);
+            };
+            let received = deposit_to_vault(
+                &accounts.config,
+                TokenCpi::new(token_program, token_mint),
+                raiser_token_account,
+                &accounts.authority,
+                escrow_vault,
+                bond,
+            )?;
+            accounts.contract.milestones[milestone_index as usize].dispute_bond = received;
+        }
+        
+        Ok(())
+    }
+
+    /// Resolve dispute with AI arbitration result (oracle/authority)
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+        milestone_index: u8,
+        decision: DisputeDecision,
+        arbitration_proof: [u8; 32],
+        rationale_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        // Arbitrators must document their reasoning for accountability and appeals
+        require!(rationale_hash != [0u8; 32], PayGuardError::MissingRationale);
+        // Checked up front too, as a deferred decision only executes at settlement
+        if let DisputeDecision::Split(freelancer_pct) = decision {
+            require!(freelancer_pct <= 100, PayGuardError::InvalidSplit);
+        }
+        
+        let contract = &mut ctx.accounts.contract;
+        require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+        require!(contract.funded_amount > 0, PayGuardError::EscrowNotFunded);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let finality_window = contract.terms.resolution_finality_window;
+        let threshold = contract.terms.arbitration_threshold;
+        let panel_seat = contract.terms.arbitrators.iter().position(|a| *a == ctx.accounts.arbitrator.key());
+        let contract_id = contract.id;
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Disputed, PayGuardError::MilestoneNotDisputed);
+        match milestone.dispute_resolver {
+            Some(resolver) => require!(resolver == ctx.accounts.arbitrator.key(), PayGuardError::Unauthorized),
+            // A panel's decision only executes once enough members agree on it
+            None => {
+                let seat = panel_seat.ok_or(PayGuardError::Unauthorized)?;
+                let matching_votes = milestone.cast_vote(seat as u8, decision.clone());
+                emit!(DisputeVoteCast {
+                    contract_id,
+                    milestone_index,
+                    arbitrator: ctx.accounts.arbitrator.key(),
+                    decision: decision.clone(),
+                    matching_votes,
+                    cast_at: Clock::get()?.unix_timestamp,
+                });
+                if matching_votes < threshold {
+                    return Ok(());
+                }
+            }
+        }
+        
+        milestone.arbitration_proof = Some(arbitration_proof);
+        milestone.rationale_hash = Some(rationale_hash);
+        
+        // Registered resolvers are scored on how long the dispute stayed open
+        if let Some(disputed_at) = milestone.disputed_at {
+            record_resolution(
+                &ctx.accounts.arbitrator_profile,
+                Clock::get()?.unix_timestamp - disputed_at,
+                ctx.accounts.config.arbitrator_sla_secs,
+            )?;
+        }
+        
+        let now = Clock::get()?.unix_timestamp;
+        contract.log_dispute(milestone_index, decision.clone(), now);
+        emit!(DisputeResolved {
+            contract_id: contract.id,
+            milestone_index,
+            resolver: ctx.accounts.arbitrator.key(),
+            decision: decision.clone(),
+            arbitration_proof: Some(arbitration_proof),
+            resolved_at: now,
+        });
+        
+        // The resolver is paid whichever way the decision goes, before any
+        // finality window defers the payout
+        pay_arbitrator_fee(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            ctx.accounts.arbitrator_token_account.as_ref(),
+            contract,
+            milestone_index,
+        )?;
+        
+        // With a finality window the payout waits in the vault, where an
+        // appeal can still intercept it
+        if finality_window > 0 {
+            let milestone = &mut contract.milestones[milestone_index as usize];
+            milestone.status = MilestoneStatus::ResolutionPending;
+            milestone.pending_decision = Some(decision);
+            milestone.resolved_at = Some(now);
+            contract.refresh_dispute_status();
+            return Ok(());
+        }
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: Some(&ctx.accounts.freelancer_token_account),
+            restitution: ctx.accounts.restitution_token_account.as_ref(),
+        );
+        execute_decision(&payout, &mut ctx.accounts.contract, milestone_index, decision)
+    }
+
+    /// Resolve a stuck dispute in place of its resolver (governance)
+    ///
+    /// The backstop for unreachable arbitrators: only disputes open for the
+    /// config's minimum staleness qualify, and the decision pays out at once.
+    pub fn governance_resolve<'info>(
+        ctx: Context<'_, '_, '_, 'info, GovernanceResolve<'info>>,
+        milestone_index: u8,
+        decision: DisputeDecision,
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        if let DisputeDecision::Split(freelancer_pct) = decision {
+            require!(freelancer_pct <= 100, PayGuardError::InvalidSplit);
+        }
+        let min_staleness = ctx.accounts.config.governance_min_staleness;
+        let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let milestone = &contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Disputed, PayGuardError::MilestoneNotDisputed);
+        let stale_at = milestone
+            .disputed_at
+            .unwrap_or_default()
+            .checked_add(min_staleness)
+            .ok_or(PayGuardError::Overflow)?;
+        require!(Clock::get()?.unix_timestamp >= stale_at, PayGuardError::DisputeNotStale);
+        let now = Clock::get()?.unix_timestamp;
+        contract.log_dispute(milestone_index, decision.clone(), now);
+        emit!(DisputeResolved {
+            contract_id: contract.id,
+            milestone_index,
+            resolver: ctx.accounts.governance.key(),
+            decision: decision.clone(),
+            arbitration_proof: None,
+            resolved_at: now,
+        });
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: Some(&ctx.accounts.freelancer_token_account),
+            restitution: ctx.accounts.restitution_token_account.as_ref(),
+        );
+        execute_decision(&payout, &mut ctx.accounts.contract, milestone_index, decision)
+    }
+
+    /// Pay out a resolution once its finality window has passed without appeal
+    ///
+    /// Anyone may crank settlement; funds only ever go to the contract parties.
+    pub fn settle_resolution<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleResolution<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let finality_window = contract.terms.resolution_finality_window;
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(
+            milestone.status == MilestoneStatus::ResolutionPending,
+            PayGuardError::NoPendingResolution
+        );
+        
+        let final_at = milestone
+            .resolved_at
+            .unwrap_or_default()
+            .checked_add(finality_window)
+            .ok_or(PayGuardError::Overflow)?;
+        require!(Clock::get()?.unix_timestamp >= final_at, PayGuardError::FinalityWindowOpen);
+        let decision = milestone.pending_decision.take().ok_or(PayGuardError::NoPendingResolution)?;
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: Some(&ctx.accounts.freelancer_token_account),
+            restitution: ctx.accounts.restitution_token_account.as_ref(),
+        );
+        execute_decision(&payout, &mut ctx.accounts.contract, milestone_index, decision)
+    }
+
+    /// Appeal a pending resolution within its finality window (client or freelancer)
+    ///
+    /// The payout is frozen. When the terms name a `senior_arbitrator` the
+    /// appellant stakes `appeal_bond` and the milestone goes to them as
+    /// `Appealed`; otherwise the dispute reopens for the resolver. A dispute
+    /// may be appealed `MAX_APPEALS` times.
+    pub fn appeal_resolution(ctx: Context<AppealResolution>, milestone_index: u8) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let finality_window = contract.terms.resolution_finality_window;
+        let resolution_window = contract.terms.dispute_resolution_window;
+        let to_senior = contract.terms.senior_arbitrator.is_some();
+        let appeal_bond = contract.terms.appeal_bond;
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(
+            milestone.status == MilestoneStatus::ResolutionPending,
+            PayGuardError::NoPendingResolution
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let final_at = milestone
+            .resolved_at
+            .unwrap_or_default()
+            .checked_add(finality_window)
+            .ok_or(PayGuardError::Overflow)?;
+        require!(now < final_at, PayGuardError::FinalityWindowClosed);
+        // Appeals are free without a senior arbitrator, so they must run out
+        require!(milestone.appeal_count < MAX_APPEALS, PayGuardError::AppealLimitReached);
+        
+        milestone.appeal_count += 1;
+        milestone.pending_decision = None;
+        milestone.resolved_at = None;
+        milestone.disputed_by = Some(ctx.accounts.authority.key());
+        if to_senior {
+            milestone.status = MilestoneStatus::Appealed;
+            milestone.appealed_by = Some(ctx.accounts.authority.key());
+        } else {
+            milestone.status = MilestoneStatus::Disputed;
+            milestone.open_dispute(now, resolution_window)?;
+        }
+        contract.refresh_dispute_status();
+        
+        // The appeal bond waits in the vault with any dispute bond until the
+        // senior arbitrator decides who keeps it
+        if to_senior {
+            let accounts = &mut *ctx.accounts;
+            let (Some(appellant_token_account), Some(escrow_vault), Some(token_mint), Some(token_program)) = (
+                accounts.appellant_token_account.as_ref(),
+                accounts.escrow_vault.as_mut(),
+                accounts.token_mint.as_ref(),
+                accounts.token_program.as_ref(),
+            ) else {
+                return err!(PayGuardError::DisputeBondAccountsMissing);
+            };
+            let received = deposit_to_vault(
+                &accounts.config,
+                TokenCpi::new(token_program, token_mint),
+                appellant_token_account,
+                &accounts.authority,
+                escrow_vault,
+                appeal_bond,
+            )?;
+            accounts.contract.milestones[milestone_index as usize].appeal_bond = received;
+        }
+        
+        Ok(())
+    }
+
+    /// Decide an appealed resolution (senior arbitrator)
+    ///
+    /// The decision is final and pays out at once, settling the dispute and
+    /// appeal bonds with it.
+    pub fn resolve_appeal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveAppeal<'info>>,
+        milestone_index: u8,
+        decision: DisputeDecision,
+        rationale_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(rationale_hash != [0u8; 32], PayGuardError::MissingRationale);
+        let contract = &mut ctx.accounts.contract;
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Appealed, PayGuardError::MilestoneNotAppealed);
+        milestone.rationale_hash = Some(rationale_hash);
+        
+        let now = Clock::get()?.unix_timestamp;
+        contract.log_dispute(milestone_index, decision.clone(), now);
+        emit!(DisputeResolved {
+            contract_id: contract.id,
+            milestone_index,
+            resolver: ctx.accounts.senior_arbitrator.key(),
+            decision: decision.clone(),
+            arbitration_proof: None,
+            resolved_at: now,
+        });
+        
+        let payout = decision_payout!(
+            ctx,
+            freelancer: Some(&ctx.accounts.freelancer_token_account),
+            restitution: ctx.accounts.restitution_token_account.as_ref(),
+        );
+        execute_decision(&payout, &mut ctx.accounts.contract, milestone_index, decision)
+    }
+
+    /// Ask to pause the contract for `duration_secs` (client or freelancer)
+    ///
+    /// Takes effect once the other party calls `approve_pause`.
+    pub fn request_pause(ctx: Context<PauseContract>, duration_secs: i64) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(duration_secs > 0, PayGuardError::InvalidPauseDuration);
+        require!(!contract.is_paused(Clock::get()?.unix_timestamp), PayGuardError::ContractPaused);
+        
+        contract.pause_requested_by = Some(ctx.accounts.authority.key());
+        contract.pause_duration = duration_secs;
+        
+        Ok(())
+    }
+
+    /// Agree to the other party's pause request, starting the pause
+    ///
+    /// Submission deadlines of milestones not yet submitted, and the contract's
+    /// `terms.deadline` when set, move back by the pause duration.
+    pub fn approve_pause(ctx: Context<PauseContract>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        let requested_by = contract.pause_requested_by.ok_or(PayGuardError::NoPauseRequest)?;
+        require!(requested_by != ctx.accounts.authority.key(), PayGuardError::Unauthorized);
+        
+        let now = Clock::get()?.unix_timestamp;
+        require!(!contract.is_paused(now), PayGuardError::ContractPaused);
+        
+        let duration = contract.pause_duration;
+        contract.paused_until = now.checked_add(duration).ok_or(PayGuardError::Overflow)?;
+        contract.pause_requested_by = None;
+        contract.pause_duration = 0;
+        for milestone in contract.milestones.iter_mut() {
+            if milestone.status == MilestoneStatus::Pending {
+                if let Some(due_date) = milestone.due_date.as_mut() {
+                    *due_date = due_date.checked_add(duration).ok_or(PayGuardError::Overflow)?;
+                }
+            }
+        }
+        // The pause must not run the clock down on `cancel_expired`
+        if contract.terms.deadline != 0 {
+            contract.terms.deadline = contract.terms.deadline.checked_add(duration).ok_or(PayGuardError::Overflow)?;
+        }
+        
+        Ok(())
+    }
+
+    /// Approve cancelling a contract that requires multiple signers (cancel approver)
+    pub fn approve_cancellation(ctx: Context<ApproveCancellation>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(
+                contract.status,
+                ContractStatus::Active | ContractStatus::Draft | ContractStatus::Pending
+            ),
+            PayGuardError::ContractNotActive
+        );
+        
+        let approver = ctx.accounts.approver.key();
+        require!(contract.terms.cancel_approvers.contains(&approver), PayGuardError::Unauthorized);
+        if !contract.cancel_approvals.contains(&approver) {
+            contract.cancel_approvals.push(approver);
+        }
+        
+        Ok(())
+    }
+
+    /// Cancel contract and refund by mutual agreement (client + freelancer)
+    ///
+    /// The freelancer need not sign before accepting the contract. When the
+    /// terms set a `cancel_threshold`, that many cancel approvers must have
+    /// called `approve_cancellation` first.
+    pub fn cancel_contract(ctx: Context<CancelContract>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(
+                contract.status,
+                ContractStatus::Active | ContractStatus::Draft | ContractStatus::Pending
+            ),
+            PayGuardError::ContractNotActive
+        );
+        // Work may be underway once the contract is active
+        require!(
+            contract.status != ContractStatus::Active || ctx.accounts.freelancer.is_signer,
+            PayGuardError::Unauthorized
+        );
+        require!(
+            contract.cancel_approvals.len() >= contract.terms.cancel_threshold as usize,
+            PayGuardError::CancelApprovalsRequired
+        );
+        
+        cancel_and_refund(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+        )
+    }
+
+    /// Cancel an `Active` contract whose `terms.deadline` has passed (anyone)
+    ///
+    /// Refunds the escrow as `cancel_contract` would, so a contract neither
+    /// party will move forward does not lock its funds forever. SOL escrows
+    /// use `cancel_expired_sol`.
+    pub fn cancel_expired(ctx: Context<CancelExpired>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+        require!(contract.is_expired(Clock::get()?.unix_timestamp), PayGuardError::DeadlineNotReached);
+        
+        cancel_and_refund(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+        )
+    }
+
+    /// Walk away from an `Active` contract, refunding the escrow (freelancer)
+    ///
+    /// The freelancer keeps only what was already approved, which remains
+    /// claimable with `withdraw_funds`. SOL escrows use `freelancer_withdraw_sol`.
+    pub fn freelancer_withdraw(ctx: Context<FreelancerWithdraw>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+        
+        cancel_and_refund(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+        )
+    }
+
+    /// Cancel a SOL-escrow contract and refund the SOL vault (client + freelancer)
+    ///
+    /// Signing follows `cancel_contract`: the freelancer need not sign before
+    /// accepting the contract.
+    pub fn cancel_contract_sol(ctx: Context<CancelContractSol>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(
+                contract.status,
+                ContractStatus::Active | ContractStatus::Draft | ContractStatus::Pending
+            ),
+            PayGuardError::ContractNotActive
+        );
+        // Work may be underway once the contract is active
+        require!(
+            contract.status != ContractStatus::Active || ctx.accounts.freelancer.is_signer,
+            PayGuardError::Unauthorized
+        );
+        require!(
+            contract.cancel_approvals.len() >= contract.terms.cancel_threshold as usize,
+            PayGuardError::CancelApprovalsRequired
+        );
+        
+        cancel_and_refund_sol(
+            &ctx.accounts.sol_vault,
+            ctx.bumps.sol_vault,
+            &ctx.accounts.client,
+            &ctx.accounts.system_program,
+            contract,
+        )
+    }
+
+    /// Walk away from an `Active` SOL-escrow contract, refunding the SOL vault (freelancer)
+    ///
+    /// The SOL counterpart of `freelancer_withdraw`. SOL releases are paid out
+    /// on approval, so everything left in the vault goes back to the client.
+    pub fn freelancer_withdraw_sol(ctx: Context<FreelancerWithdrawSol>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(contract.escrows_sol(), PayGuardError::NotSolEscrow);
+        
+        cancel_and_refund_sol(
+            &ctx.accounts.sol_vault,
+            ctx.bumps.sol_vault,
+            &ctx.accounts.client,
+            &ctx.accounts.system_program,
+            contract,
+        )
+    }
+
+    /// Cancel an `Active` SOL-escrow contract whose `terms.deadline` has passed (anyone)
+    ///
+    /// The SOL counterpart of `cancel_expired`, refunding the SOL vault to the client.
+    pub fn cancel_expired_sol(ctx: Context<CancelExpiredSol>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!(contract.escrows_sol(), PayGuardError::NotSolEscrow);
+        require!(contract.is_expired(Clock::get()?.unix_timestamp), PayGuardError::DeadlineNotReached);
+        
+        cancel_and_refund_sol(
+            &ctx.accounts.sol_vault,
+            ctx.bumps.sol_vault,
+            &ctx.accounts.client,
+            &ctx.accounts.system_program,
+            contract,
+        )
+    }
+
+    /// Revoke a milestone that is no longer needed before it is submitted
+    ///
+    /// Requires both parties. The milestone slot is kept for history; its amount
+    /// leaves the contract total and is refunded if the escrow is already funded.
+    pub fn revoke_milestone<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevokeMilestone<'info>>,
+        milestone_index: u8,
+    ) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+        require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+        
+        let milestone = &mut contract.milestones[milestone_index as usize];
+        require!(milestone.status == MilestoneStatus::Pending, PayGuardError::MilestoneNotPending);
+        
+        let amount = milestone.amount;
+        let bonus = milestone.bonus_amount;
+        milestone.status = MilestoneStatus::Cancelled;
+        require!(contract.total_amount > amount, PayGuardError::CannotRevokeLastMilestone);
+        
+        contract.total_amount = contract.total_amount.checked_sub(amount).ok_or(PayGuardError::Overflow)?;
+        contract.bonus_total = contract.bonus_total.checked_sub(bonus).ok_or(PayGuardError::Overflow)?;
+        contract.refresh_status_bitmap();
+        
+        // Refund whatever funding the smaller total no longer needs, up to the
+        // milestone amount; a partially funded escrow may owe nothing back
+        let refund = contract.funded_amount.saturating_sub(contract.total_amount).min(amount);
+        contract.funded_amount = contract.funded_amount.checked_sub(refund).ok_or(PayGuardError::Overflow)?;
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.client_token_account,
+            contract,
+            refund,
+        )?;
+        
+        // Revoking the last open milestone may complete the contract
+        if contract.is_fully_released() {
+            complete_contract(
+                TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                &ctx.accounts.escrow_vault,
+                &ctx.accounts.client_token_account,
+                &ctx.accounts.client,
+                contract,
+                ctx.remaining_accounts,
+            )?;
+        }
+        
+        Ok(())
+    }
+
+    /// Return the indices of all milestones in `status` (read-only)
+    pub fn milestones_with_status(ctx: Context<ViewContract>, status: MilestoneStatus) -> Result<Vec<u8>> {
+        let indices: Vec<u8> = ctx
+            .accounts
+            .contract
+            .milestones
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.status == status)
+            .map(|(i, _)| i as u8)
+            .collect();
+        
+        msg!("Milestones in {:?}: {:?}", status, indices);
+        Ok(indices)
+    }
+
+    /// Estimate the freelancer's net from each milestone (read-only)
+    ///
+    /// Late penalties are those already incurred, so milestones not yet
+    /// submitted are assumed on time. The estimate is also logged.
+    pub fn estimate_net_payout(ctx: Context<ViewContract>) -> Result<Vec<PayoutEstimate>> {
+        let contract = &ctx.accounts.contract;
+        let estimates: Vec<PayoutEstimate> = contract
+            .milestones
+            .iter()
+            .map(|m| contract.estimate_payout(m))
+            .collect();
+        
+        for (i, e) in estimates.iter().enumerate() {
+            msg!(
+                "Milestone {}: net {} (payout {} + retainage {}) after fee {} and penalty {}, bonus {}",
+                i,
+                e.payout + e.retainage,
+                e.payout,
+                e.retainage,
+                e.fee,
+                e.late_penalty,
+                e.bonus
+            );
+        }
+        Ok(estimates)
+    }
+
+    /// Summarize a contract's balance, milestone states and funding (read-only)
+    pub fn contract_summary(ctx: Context<ViewContract>) -> Result<ContractSummary> {
+        let contract = &ctx.accounts.contract;
+        let mut status_counts = [0u8; MILESTONE_STATUS_COUNT];
+        for m in &contract.milestones {
+            status_counts[m.status.clone() as usize] += 1;
+        }
+        
+        let summary = ContractSummary {
+            remaining: contract.total_amount.saturating_sub(contract.released_amount),
+            status_counts,
+            fully_funded: contract.funded_amount >= contract.total_amount,
+        };
+        msg!(
+            "Remaining {}, milestones by status {:?}, fully funded {}",
+            summary.remaining,
+            summary.status_counts,
+            summary.fully_funded
+        );
+        Ok(summary)
+    }
+
+    /// Fund the secondary bonus vault with the full bonus total (client)
+    ///
+    /// A contract has one bonus vault, so it can only be funded once.
+    pub fn fund_bonus(ctx: Context<FundBonus>, amount: u64) -> Result<()> {
+        let contract = &ctx.accounts.contract;
+        require!(
+            matches!(contract.status, ContractStatus::Active | ContractStatus::Disputed),
+            PayGuardError::ContractNotActive
+        );
+        require!(contract.bonus_vault == Pubkey::default(), PayGuardError::BonusAlreadyFunded);
+        require!(contract.terms.bonus_mint == Some(ctx.accounts.bonus_vault.mint), PayGuardError::InvalidBonus);
+        require!(ctx.accounts.bonus_vault.owner == contract.key(), PayGuardError::InvalidVault);
+        require!(amount == contract.bonus_total, PayGuardError::AmountMismatch);
+        
+        deposit_to_vault(
+            &ctx.accounts.config,
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.bonus_mint),
+            &ctx.accounts.client_bonus_token_account,
+            &ctx.accounts.client,
+            &mut ctx.accounts.bonus_vault,
+            amount,
+        )?;
+        
+        ctx.accounts.contract.bonus_vault = ctx.accounts.bonus_vault.key();
+        
+        Ok(())
+    }
+
+    /// Return unpaid bonus tokens to the client once the contract is finished
+    pub fn reclaim_bonus(ctx: Context<ReclaimBonus>) -> Result<()> {
+        let contract = &ctx.accounts.contract;
+        require!(contract.is_closeable(), PayGuardError::ContractNotClosable);
+        
+        transfer_from_vault(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.bonus_mint),
+            &ctx.accounts.bonus_vault,
+            &ctx.accounts.client_bonus_token_account,
+            contract,
+            ctx.accounts.bonus_vault.amount,
+        )
+    }
+
+    /// Close a finished contract and its vault, returning the rent (client)
+    ///
+    /// The contract must be `Completed` or `Cancelled` with every released token
+    /// accounted for by a settled milestone and nothing left for the freelancer to
+    /// withdraw. The vault must be the contract's own and, when still open, empty;
+    /// a completed contract closes its own once nothing is left unclaimed.
+    pub fn close_contract(ctx: Context<CloseContract>) -> Result<()> {
+        let contract = &ctx.accounts.contract;
+        contract.check_closeable()?;
+        
+        let escrow_vault = &ctx.accounts.escrow_vault;
+        require!(escrow_vault.key() == contract.escrow_vault, PayGuardError::InvalidVault);
+        if !escrow_vault.data_is_empty() {
+            let vault = TokenAccount::try_deserialize(&mut &escrow_vault.data.borrow()[..])?;
+            require!(vault.amount == 0, PayGuardError::ContractNotClosable);
+            close_vault(&ctx.accounts.token_program, escrow_vault, &ctx.accounts.client, contract)?;
+        }
+        
+        Ok(())
+    }
+
+    /// Close several finished contracts and their vaults in one transaction
+    ///
+    /// `remaining_accounts` holds one `[contract, client, escrow_vault]` triple per
+    /// contract. Each contract must pass the same checks as in `close_contract`,
+    /// with its own vault, closed or empty, in the vault slot; rent from both
+    /// accounts goes back to that contract's client.
+    pub fn close_contracts<'info>(ctx: Context<'_, '_, 'info, 'info, CloseContracts<'info>>) -> Result<()> {
+        let triples = ctx.remaining_accounts.chunks_exact(3);
+        require!(
+            !ctx.remaining_accounts.is_empty() && triples.remainder().is_empty(),
+            PayGuardError::InvalidRemainingAccounts
+        );
+        
+        for triple in triples {
+            let contract = Account::<Contract>::try_from(&triple[0])?;
+            let client = &triple[1];
+            let escrow_vault = &triple[2];
+            
+            require!(contract.client == client.key(), PayGuardError::Unauthorized);
+            contract.check_closeable()?;
+            require!(escrow_vault.key() == contract.escrow_vault, PayGuardError::InvalidVault);
+            
+            // Completed contracts close their vault once the freelancer is paid out
+            if !escrow_vault.data_is_empty() {
+                let vault = InterfaceAccount::<TokenAccount>::try_from(escrow_vault)?;
+                require!(vault.amount == 0, PayGuardError::ContractNotClosable);
+                close_vault(&ctx.accounts.token_program, &vault, client, &contract)?;
+            }
+            
+            contract.close(client.clone())?;
+        }
+        
+        Ok(())
+    }
+
+    /// Initialize the protocol config; the signer becomes the admin
+    pub fn initialize_config(ctx: Context<InitializeConfig>, timelock_secs: i64) -> Result<()> {
+        require!(timelock_secs >= 0, PayGuardError::InvalidTimelock);
+        
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.timelock_secs = timelock_secs;
+        config.min_milestones = 1;
+        config.fee_treasury = ctx.accounts.admin.key();
+        config.kyc_authority = ctx.accounts.admin.key();
+        config.arbitrator_sla_secs = 0;
+        config.restitution_treasury = ctx.accounts.admin.key();
+        config.governance = ctx.accounts.admin.key();
+        config.governance_min_staleness = DEFAULT_GOVERNANCE_STALENESS_SECS;
+        config.bump = ctx.bumps.config;
+        
+        Ok(())
+    }
+
+    /// Set the minimum number of milestones new contracts must have (admin)
+    pub fn set_min_milestones(ctx: Context<UpdateConfig>, min_milestones: u8) -> Result<()> {
+        require!(
+            (1..=MAX_INITIAL_MILESTONES as u8).contains(&min_milestones),
+            PayGuardError::InvalidMilestones
+        );
+        ctx.accounts.config.min_milestones = min_milestones;
+        
+        Ok(())
+    }
+
+    /// Collect fees in a protocol token instead of the payment token (admin)
+    ///
+    /// Applies to contracts created afterwards; `None` reverts to collecting
+    /// fees out of each release.
+    pub fn set_fee_token(ctx: Context<UpdateConfig>, fee_token: Option<FeeToken>) -> Result<()> {
+        ctx.accounts.config.fee_token = fee_token;
+        
+        Ok(())
+    }
+
+    /// Pay the fees owed on a contract in its fee token (client)
+    ///
+    /// The fee owed is valued in the payment token and converted at the
+    /// current oracle prices of both tokens.
+    pub fn collect_token_fee(ctx: Context<CollectTokenFee>) -> Result<()> {
+        require!(!ctx.accounts.config.paused, PayGuardError::ProgramPaused);
+        let contract = &ctx.accounts.contract;
+        let fee_token = contract.fee_token.clone().ok_or(PayGuardError::NoFeeToken)?;
+        require!(ctx.accounts.fee_mint.key() == fee_token.mint, PayGuardError::InvalidFeeAccount);
+        require!(
+            ctx.accounts.payment_price_feed.key() == fee_token.payment_price_feed
+                && ctx.accounts.fee_price_feed.key() == fee_token.fee_price_feed,
+            PayGuardError::InvalidPriceFeed
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let payment_price = oracle_price(&ctx.accounts.payment_price_feed, now, fee_token.max_price_age_secs)?;
+        let fee_price = oracle_price(&ctx.accounts.fee_price_feed, now, fee_token.max_price_age_secs)?;
+        
+        let owed = contract.fees_owed;
+        let amount = convert_fee(
+            owed,
+            &payment_price,
+            ctx.accounts.token_mint.decimals,
+            &fee_price,
+            ctx.accounts.fee_mint.decimals,
+        )?;
+        
+        transfer_tokens(
+            TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.fee_mint),
+            &ctx.accounts.client_fee_token_account,
+            &ctx.accounts.treasury_fee_token_account,
+            &ctx.accounts.client,
+            amount,
+        )?;
+        
+        let contract = &mut ctx.accounts.contract;
+        contract.fees_owed = 0;
+        contract.fees_collected += owed;
+        
+        Ok(())
+    }
+
+    /// Block an address from taking part in new contracts (admin)
+    pub fn block_address(ctx: Context<BlockAddress>, address: Pubkey) -> Result<()> {
+        let blocked = &mut ctx.accounts.blocked;
+        blocked.address = address;
+        blocked.blocked_at = Clock::get()?.unix_timestamp;
+        blocked.bump = ctx.bumps.blocked;
+        
+        Ok(())
+    }
+
+    /// Lift a block, returning the entry's rent to the admin (admin)
+    pub fn unblock_address(_ctx: Context<UnblockAddress>, _address: Pubkey) -> Result<()> {
+        Ok(())
+    }
+
+    /// Set the authority whose KYC attestations gate `require_kyc` contracts (admin)
+    ///
+    /// Attestations issued by a previous authority stop being accepted.
+    pub fn set_kyc_authority(ctx: Context<UpdateConfig>, kyc_authority: Pubkey) -> Result<()> {
+        ctx.accounts.config.kyc_authority = kyc_authority;
+        Ok(())
+    }
+
+    /// Set the time arbitrators have to resolve a dispute before losing reputation (admin)
+    ///
+    /// 0 disables SLA tracking; resolutions still count towards the registry.
+    pub fn set_arbitrator_sla(ctx: Context<UpdateConfig>, arbitrator_sla_secs: i64) -> Result<()> {
+        require!(arbitrator_sla_secs >= 0, PayGuardError::InvalidArbitratorSla);
+        ctx.accounts.config.arbitrator_sla_secs = arbitrator_sla_secs;
+        Ok(())
+    }
+
+    /// Set who receives forfeited milestones on contracts created from now on (admin)
+    pub fn set_restitution_treasury(ctx: Context<UpdateConfig>, restitution_treasury: Pubkey) -> Result<()> {
+        ctx.accounts.config.restitution_treasury = restitution_treasury;
+        Ok(())
+    }
+
+    /// Point platform fees at a new recipient (admin)
+    ///
+    /// Applies to contracts created from now on, and to `contract` if given. A
+    /// contract can only be moved off the recipient being replaced, so one paying
+    /// a recipient of its own is never redirected.
+    pub fn update_fee_recipient(ctx: Context<UpdateFeeRecipient>, new_recipient: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        if let Some(contract) = ctx.accounts.contract.as_mut() {
+            require!(contract.fee_treasury == config.fee_treasury, PayGuardError::Unauthorized);
+            contract.fee_treasury = new_recipient;
+        }
+        config.fee_treasury = new_recipient;
+        Ok(())
+    }
+
+    /// Halt or resume funding and releases program-wide (admin)
+    ///
+    /// Every deposit, approval and decision payout goes through a helper that checks
+    /// the flag, so relayed and timeout releases halt too, as do bonded disputes and
+    /// appeals. Bonus funding, tips, token fee collection, withdrawals and held or
+    /// vested claims check it themselves. Cancellations, clawbacks and refunds stay
+    /// open while paused so no funds are trapped.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+        Ok(())
+    }
+
+    /// Allow new contracts to pay in `mint` (admin)
+    ///
+    /// Once the whitelist has an entry, only whitelisted mints are accepted.
+    pub fn add_allowed_mint(ctx: Context<UpdateConfig>, mint: Pubkey) -> Result<()> {
+        let allowed_mints = &mut ctx.accounts.config.allowed_mints;
+        if !allowed_mints.contains(&mint) {
+            require!(allowed_mints.len() < MAX_ALLOWED_MINTS, PayGuardError::AllowedMintsFull);
+            allowed_mints.push(mint);
+        }
+        Ok(())
+    }
+
+    /// Take `mint` off the whitelist (admin)
+    ///
+    /// Existing contracts keep paying in it; removing the last entry allows any mint again.
+    pub fn remove_allowed_mint(ctx: Context<UpdateConfig>, mint: Pubkey) -> Result<()> {
+        let allowed_mints = &mut ctx.accounts.config.allowed_mints;
+        let index = allowed_mints
+            .iter()
+            .position(|m| *m == mint)
+            .ok_or(PayGuardError::MintNotAllowed)?;
+        allowed_mints.remove(index);
+        Ok(())
+    }
+
+    /// Withdraw platform fees from a treasury token account owned by the config (admin)
+    ///
+    /// Fees accrue in such accounts once `update_fee_recipient` points at the
+    /// config address, so only the admin can move them out.
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        let treasury = &ctx.accounts.treasury_token_account;
+        require!(amount > 0 && amount <= treasury.amount, PayGuardError::InvalidFeeWithdrawal);
+        
+        let seeds: &[&[u8]] = &[b"config", &[ctx.accounts.config.bump]];
+        let signer = &[seeds];
+        let cpi_accounts = TransferChecked {
+            from: treasury.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.config.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.token_mint.decimals)
+    }
+
+    /// Set the governance authority of last resort and how stale a dispute must be
+    /// before it may step in (admin)
+    pub fn set_governance(ctx: Context<UpdateConfig>, governance: Pubkey, min_staleness: i64) -> Result<()> {
+        require!(min_staleness > 0, PayGuardError::InvalidGovernanceStaleness);
+        let config = &mut ctx.accounts.config;
+        config.governance = governance;
+        config.governance_min_staleness = min_staleness;
+        Ok(())
+    }
+
+    /// Add an arbitrator to the registry at full reputation (admin)
+    pub fn register_arbitrator(ctx: Context<RegisterArbitrator>, arbitrator: Pubkey) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        profile.arbitrator = arbitrator;
+        profile.reputation_bps = INITIAL_REPUTATION_BPS;
+        profile.bump = ctx.bumps.profile;
+        Ok(())
+    }
+
+    /// Attest that `subject` passed KYC, valid until `expires_at` (KYC authority)
+    pub fn issue_kyc_attestation(ctx: Context<IssueKycAttestation>, subject: Pubkey, expires_at: i64) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.subject = subject;
+        attestation.authority = ctx.accounts.kyc_authority.key();
+        attestation.expires_at = expires_at;
+        attestation.bump = ctx.bumps.attestation;
+        Ok(())
+    }
+
+    /// Propose re-pointing a stuck contract to a new vault (admin)
+    ///
+    /// The migration can only be executed once the config timelock has elapsed.
+    pub fn propose_vault_migration(ctx: Context<ProposeVaultMigration>, new_vault: Pubkey) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.escrow_vault != new_vault, PayGuardError::InvalidVault);
+        
+        contract.pending_vault = Some(new_vault);
+        contract.vault_migration_eta = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.config.timelock_secs)
+            .ok_or(PayGuardError::Overflow)?;
+        
+        Ok(())
+    }
+
+    /// Execute a proposed vault migration after the timelock (admin)
+    ///
+    /// Any balance still transferable out of the old vault is moved across; a
+    /// closed or frozen old vault is simply abandoned.
+    pub fn migrate_vault(ctx: Context<MigrateVault>, new_vault: Pubkey) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(contract.pending_vault == Some(new_vault), PayGuardError::NoPendingMigration);
+        require!(
+            Clock::get()?.unix_timestamp >= contract.vault_migration_eta,
+            PayGuardError::TimelockNotElapsed
+        );
+        
+        let new_vault_account = &ctx.accounts.new_vault;
+        require!(new_vault_account.key() == new_vault, PayGuardError::InvalidVault);
+        require!(new_vault_account.owner == contract.key(), PayGuardError::InvalidVault);
+        require!(new_vault_account.mint == contract.token_mint, PayGuardError::InvalidVault);
+        
+        let old_vault = &ctx.accounts.old_vault;
+        require!(old_vault.key() == contract.escrow_vault, PayGuardError::InvalidVault);
+        
+        if old_vault.owner == &ctx.accounts.token_program.key() && !old_vault.data_is_empty() {
+            let old_state = TokenAccount::try_deserialize(&mut &old_vault.try_borrow_data()?[..])?;
+            if !old_state.is_frozen() {
+                transfer_from_vault(
+                    TokenCpi::new(&ctx.accounts.token_program, &ctx.accounts.token_mint),
+                    old_vault,
+                    new_vault_account,
+                    contract,
+                    old_state.amount,
+                )?;
+            }
+        }
+        
+        contract.escrow_vault = new_vault;
+        contract.pending_vault = None;
+        contract.vault_migration_eta = 0;
+        
+        Ok(())
+    }
+
+    /// Recompute a contract's accounting from its milestones and vault (admin or arbitrator)
+    ///
+    /// Safety valve for drift from bugs or transfers made around the program:
+    /// released and refunded totals are rebuilt from settled milestones, and
+    /// funding from what the vault actually holds. Contracts that gross up
+    /// transfer fees are refused: the fees their payouts carried out of the
+    /// vault are not recorded, so the vault cannot show what was funded.
+    pub fn reconcile_accounting(ctx: Context<ReconcileAccounting>) -> Result<()> {
+        let contract = &mut ctx.accounts.contract;
+        require!(
+            matches!(contract.status, ContractStatus::Active | ContractStatus::Disputed),
+            PayGuardError::ContractNotActive
+        );
+        require!(!contract.terms.gross_up_transfer_fees, PayGuardError::GrossUpNotReconcilable);
+        
+        let settled = contract
+            .milestones
+            .iter()
+            .filter(|m| {
+                matches!(
+                    m.status,
+                    MilestoneStatus::Approved
+                        | MilestoneStatus::Resolved
+                        | MilestoneStatus::ClawedBack
+                        | MilestoneStatus::Forfeited
+                        | MilestoneStatus::Refunded
+                )
+            });
+        let released = checked_sum(settled.clone().map(|m| m.amount))?;
+        let refunded = checked_sum(settled.map(|m| m.client_share))?;
+        
+        // Releases leave the vault except for retainage, held and unclaimed
+        // payouts, and dispute bonds were never funding
+        let retained = checked_sum(
+            [
+                contract.retainage_held,
+                contract.held_total,
+                contract.approved_unclaimed,
+                contract.bonds_held()?,
+            ]
+            .into_iter(),
+        )?;
+        let funded = ctx
+            .accounts
+            .escrow_vault
+            .amount
+            .checked_add(released)
+            .ok_or(PayGuardError::Overflow)?
+            .checked_sub(retained)
+            .ok_or(PayGuardError::VaultShortfall)?;
+        
+        emit!(AccountingReconciled {
+            contract_id: contract.id,
+            released_before: contract.released_amount,
+            released_after: released,
+            refunded_before: contract.refunded_amount,
+            refunded_after: refunded,
+            funded_before: contract.funded_amount,
+            funded_after: funded,
+        });
+        
+        contract.released_amount = released;
+        contract.refunded_amount = refunded;
+        contract.funded_amount = funded;
+        
+        Ok(())
+    }
+
+    /// Assert the contract's invariants, failing with the first one violated (anyone)
+    ///
+    /// Pass the escrow vault while it is open so its balance can be checked.
+    pub fn check_invariants(ctx: Context<CheckInvariants>) -> Result<()> {
+        let contract = &ctx.accounts.contract;
+        
+        require!(
+            contract.released_amount <= contract.total_amount,
+            PayGuardError::InvariantReleasedExceedsTotal
+        );
+        
+        // Corrupted amounts may not even add up, which is a violation too
+        // rather than an overflow
+        if contract.status != ContractStatus::Draft {
+            let milestone_total = checked_sum(
+                contract
+                    .milestones
+                    .iter()
+                    .filter(|m| m.status != MilestoneStatus::Cancelled)
+                    .map(|m| m.amount),
+            );
+            require!(
+                milestone_total.is_ok_and(|total| total == contract.total_amount),
+                PayGuardError::InvariantMilestoneSum
+            );
+        }
+        
+        // Releases leave the vault except for retainage, held and unclaimed payouts;
+        // anything extra is dust
+        if let Some(vault) = &ctx.accounts.escrow_vault {
+            let expected = contract.bonds_held().and_then(|bonds| {
+                checked_sum(
+                    [
+                        contract.funded_amount.saturating_sub(contract.released_amount),
+                        contract.retainage_held,
+                        contract.held_total,
+                        contract.approved_unclaimed,
+                        bonds,
+                    ]
+                    .into_iter(),
+                )
+            });
+            require!(
+                expected.is_ok_and(|expected| vault.amount >= expected),
+                PayGuardError::InvariantVaultBalance
+            );
+        }
+        
+        let all_settled = !contract.milestones.is_empty()
+            && contract.milestones.iter().all(|m| m.is_settled());
+        match contract.status {
+            ContractStatus::Completed | ContractStatus::PendingFinalization => {
+                require!(all_settled, PayGuardError::InvariantStatus)
+            }
+            ContractStatus::Active => require!(
+                (!all_settled || contract.held_total > 0) && !contract.has_open_dispute(),
+                PayGuardError::InvariantStatus
+            ),
+            ContractStatus::Disputed => require!(contract.has_open_dispute(), PayGuardError::InvariantStatus),
+            ContractStatus::Draft => require!(contract.funded_amount == 0, PayGuardError::InvariantStatus),
+            _ => {}
+        }
+        
+        let id_bytes = contract.id.to_le_bytes();
+        let derived = Pubkey::create_program_address(&[b"contract", &id_bytes, &[contract.bump]], &ID);
+        require!(derived == Ok(contract.key()), PayGuardError::InvariantBump);
+        
+        Ok(())
+    }
+
+    /// Route instructions missing from the IDL, which only test builds have
+    ///
+    /// Builds with the `test-mutators` feature take `corrupt_contract` here; see
+    /// `mutators`. Every other build rejects anything unknown, as Anchor would.
+    pub fn fallback<'info>(program_id: &Pubkey, accounts: &'info [AccountInfo<'info>], data: &[u8]) -> Result<()> {
+        mutators::dispatch(program_id, accounts, data)
+    }
+}
+
+// ============ HELPERS ============
+
+/// `corrupt_contract`, which overwrites contract state to exercise
+/// `check_invariants` and deadline handling in tests
+///
+/// Anchor generates dispatch and the IDL for every handler in `#[program]`
+/// whatever its `cfg`, so the mutator is reached through the fallback handler
+/// instead and only exists in builds with the `test-mutators` feature.
+#[cfg(feature = "test-mutators")]
+mod mutators {
+    use super::*;
+    use anchor_lang::error::ErrorCode;
+    use anchor_lang::solana_program::hash::hash;
+    
+    /// State overwritten by `corrupt_contract`
+    #[derive(AnchorSerialize, AnchorDeserialize)]
+    enum Corruption {
+        ReleasedAmount(u64),
+        MilestoneAmount { index: u8, amount: u64 },
+        FundedAmount(u64),
+        Status(ContractStatus),
+        Bump(u8),
+        /// Backdates a deadline, which creation only accepts in the future
+        DueDate { index: u8, due_date: i64 },
+    }
+    
+    /// Decode `corrupt_contract` as Anchor would encode it: the contract
+    /// account, then the instruction discriminator and a `Corruption`
+    pub fn dispatch<'info>(program_id: &Pubkey, accounts: &'info [AccountInfo<'info>], data: &[u8]) -> Result<()> {
+        let discriminator = &hash(b"global:corrupt_contract").to_bytes()[..8];
+        let mut args = data.strip_prefix(discriminator).ok_or(ErrorCode::InstructionFallbackNotFound)?;
+        let corruption = Corruption::deserialize(&mut args).map_err(|_| ErrorCode::InstructionDidNotDeserialize)?;
+        
+        let mut contract = Account::<Contract>::try_from(accounts.first().ok_or(ErrorCode::AccountNotEnoughKeys)?)?;
+        match corruption {
+            Corruption::ReleasedAmount(amount) => contract.released_amount = amount,
+            Corruption::MilestoneAmount { index, amount } => {
+                contract.milestones.get_mut(index as usize).ok_or(PayGuardError::InvalidMilestoneIndex)?.amount = amount
+            }
+            Corruption::FundedAmount(amount) => contract.funded_amount = amount,
+            Corruption::Status(status) => contract.status = status,
+            Corruption::Bump(bump) => contract.bump = bump,
+            Corruption::DueDate { index, due_date } => {
+                contract.milestones.get_mut(index as usize).ok_or(PayGuardError::InvalidMilestoneIndex)?.due_date =
+                    Some(due_date)
+            }
+        }
+        contract.exit(program_id)
+    }
+}
+
+/// Builds without the `test-mutators` feature carry no mutator
+#[cfg(not(feature = "test-mutators"))]
+mod mutators {
+    use super::*;
+    
+    pub fn dispatch<'info>(_program_id: &Pubkey, _accounts: &'info [AccountInfo<'info>], _data: &[u8]) -> Result<()> {
+        err!(anchor_lang::error::ErrorCode::InstructionFallbackNotFound)
+    }
+}
+
+/// Token program and mint a transfer goes through
+///
+/// Transfers are checked against the mint, which Token-2022 requires for mints
+/// with a transfer fee; classic SPL mints work the same way.
+#[derive(Clone, Copy)]
+struct TokenCpi<'a, 'info> {
+    program: &'a Interface<'info, TokenInterface>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+}
+
+impl<'a, 'info> TokenCpi<'a, 'info> {
+    fn new(program: &'a Interface<'info, TokenInterface>, mint: &'a InterfaceAccount<'info, Mint>) -> Self {
+        Self { program, mint }
+    }
+}
+
+/// Transfer tokens signed by their owner
+fn transfer_tokens<'info>(
+    token: TokenCpi<'_, 'info>,
+    from: &impl ToAccountInfo<'info>,
+    to: &impl ToAccountInfo<'info>,
+    authority: &impl ToAccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = TransferChecked {
+        from: from.to_account_info(),
+        mint: token.mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: authority.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(token.program.to_account_info(), cpi_accounts);
+    token_interface::transfer_checked(cpi_ctx, amount, token.mint.decimals)
+}
+
+/// Move `amount` from the client into an escrow or bonus vault, returning what
+/// the vault actually received net of any transfer fee; halted while paused
+fn deposit_to_vault<'info>(
+    config: &Config,
+    token: TokenCpi<'_, 'info>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    authority: &Signer<'info>,
+    escrow_vault: &mut InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+) -> Result<u64> {
+    require!(!config.paused, PayGuardError::ProgramPaused);
+    // A self-transfer would credit nothing while the contract records funding
+    require!(from.key() != escrow_vault.key(), PayGuardError::SameAccount);
+    let vault_before = escrow_vault.amount;
+    
+    transfer_tokens(token, from, &*escrow_vault, authority, amount)?;
+    
+    escrow_vault.reload()?;
+    Ok(escrow_vault.amount - vault_before)
+}
+
+/// Identity, schedule and terms of a contract being created
+struct NewContract {
+    id: u64,
+    client: Pubkey,
+    freelancer: Pubkey,
+    arbitrator: Pubkey,
+    token_mint: Pubkey,
+    escrow_vault: Pubkey,
+    total_amount: u64,
+    milestones: Vec<Milestone>,
+    description_hash: [u8; 32],
+    terms: ContractTerms,
+}
+
+/// Validate a new contract's terms and milestones and initialize its account;
+/// an empty schedule starts a draft
+fn init_contract(
+    contract: &mut Contract,
+    new: NewContract,
+    config: &Config,
+    client_kyc: &AccountInfo,
+    freelancer_kyc: &AccountInfo,
+    bump: u8,
+) -> Result<()> {
+    let NewContract {
+        id,
+        client,
+        freelancer,
+        arbitrator,
+        token_mint,
+        escrow_vault,
+        total_amount,
+        milestones,
+        description_hash,
+        terms,
+    } = new;
+    
+    // A party on both sides could approve their own milestones
+    require!(client != freelancer, PayGuardError::SelfDealing);
+    require!(
+        config.allowed_mints.is_empty() || config.allowed_mints.contains(&token_mint),
+        PayGuardError::MintNotAllowed
+    );
+    // Drafts have no milestones to check yet, but must still be worth something
+    require!(total_amount > 0, PayGuardError::ZeroMilestoneAmount);
+    require!(terms.retainage_bps as u64 <= BPS_DENOMINATOR, PayGuardError::InvalidRetainage);
+    require!(terms.fee_bps <= MAX_FEE_BPS, PayGuardError::InvalidFee);
+    require!(terms.arbitrator_fee_bps as u64 <= BPS_DENOMINATOR, PayGuardError::InvalidArbitratorFee);
+    require!(
+        terms.cancel_approvers.len() <= MAX_CANCEL_APPROVERS
+            && terms.cancel_threshold as usize <= terms.cancel_approvers.len(),
+        PayGuardError::InvalidCancelThreshold
+    );
+    // SOL escrows pay out in SOL or through a swap and carry no token-side extras
+    if terms.swap_program.is_some() || terms.is_native {
+        require!(
+            !(terms.swap_program.is_some() && terms.is_native)
+                && terms.retainage_bps == 0
+                && terms.fee_bps == 0
+                && terms.arbitrator_fee_bps == 0
+                && terms.bonus_mint.is_none()
+                && terms.clawback_window == 0
+                && terms.sponsor.is_none()
+                && terms.dispute_bond == 0
+                && terms.senior_arbitrator.is_none(),
+            PayGuardError::InvalidSwapTerms
+        );
+    }
+    let panel = &terms.arbitrators;
+    require!(
+        panel.len() <= MAX_ARBITRATORS
+            && panel.iter().enumerate().all(|(i, a)| !panel[..i].contains(a))
+            && if panel.is_empty() {
+                terms.arbitration_threshold == 0
+            } else {
+                (1..=panel.len()).contains(&(terms.arbitration_threshold as usize))
+            },
+        PayGuardError::ThresholdNotMet
+    );
+    // An appeal to the senior arbitrator must cost more than the dispute it escalates
+    require!(
+        match terms.senior_arbitrator {
+            Some(senior) => senior != client && senior != freelancer && terms.appeal_bond > terms.dispute_bond,
+            None => terms.appeal_bond == 0,
+        },
+        PayGuardError::InvalidAppealTerms
+    );
+    require!(terms.resubmit_window >= 0, PayGuardError::InvalidResubmitWindow);
+    require!(terms.review_window >= 0, PayGuardError::InvalidReviewWindow);
+    require!(terms.dispute_resolution_window >= 0, PayGuardError::InvalidResolutionWindow);
+    // A clawback returns the held payout only, so nothing may be withheld beside it
+    require!(
+        terms.clawback_window >= 0 && (terms.clawback_window == 0 || terms.retainage_bps == 0),
+        PayGuardError::InvalidClawbackTerms
+    );
+    if terms.require_kyc {
+        let now = Clock::get()?.unix_timestamp;
+        check_kyc(client_kyc, config, now)?;
+        check_kyc(freelancer_kyc, config, now)?;
+    }
+    
+    let mut milestones = milestones;
+    let is_draft = milestones.is_empty();
+    if !is_draft {
+        if terms.amounts_in_bps {
+            normalize_bps_amounts(&mut milestones, total_amount)?;
+        }
+        validate_milestones(&milestones, total_amount, config, &terms, MAX_INITIAL_MILESTONES)?;
+    }
+    let bonus_total = checked_sum(milestones.iter().map(|m| m.bonus_amount))?;
+    
+    contract.id = id;
+    contract.client = client;
+    contract.freelancer = freelancer;
+    contract.token_mint = token_mint;
+    contract.arbitrator = arbitrator;
+    contract.total_amount = total_amount;
+    contract.released_amount = 0;
+    contract.refunded_amount = 0;
+    contract.funded_amount = 0;
+    contract.escrow_vault = escrow_vault;
+    contract.pending_vault = None;
+    contract.vault_migration_eta = 0;
+    contract.retainage_held = 0;
+    contract.bonus_vault = Pubkey::default();
+    contract.bonus_total = bonus_total;
+    contract.bonus_released = 0;
+    contract.milestone_status_bitmap = 0;
+    contract.fee_treasury = config.fee_treasury;
+    contract.fee_token = config.fee_token.clone();
+    contract.fees_owed = 0;
+    contract.fees_collected = 0;
+    contract.cancel_approvals = Vec::new();
+    contract.pause_requested_by = None;
+    contract.pause_duration = 0;
+    contract.paused_until = 0;
+    contract.held_total = 0;
+    contract.approved_unclaimed = 0;
+    contract.restitution_treasury = config.restitution_treasury;
+    contract.terms = terms;
+    contract.milestones = milestones;
+    contract.description_hash = description_hash;
+    // Work only starts once the freelancer accepts with `accept_contract`
+    contract.status = if is_draft { ContractStatus::Draft } else { ContractStatus::Pending };
+    contract.created_at = Clock::get()?.unix_timestamp;
+    contract.bump = bump;
+    
+    emit!(ContractCreated {
+        contract_id: contract.id,
+        client: contract.client,
+        freelancer: contract.freelancer,
+        arbitrator: contract.arbitrator,
+        token_mint: contract.token_mint,
+        total_amount: contract.total_amount,
+        created_at: contract.created_at,
+    });
+    
+    Ok(())
+}
+
+/// Sum amounts, failing rather than wrapping past `u64::MAX`
+fn checked_sum(mut amounts: impl Iterator<Item = u64>) -> Result<u64> {
+    amounts
+        .try_fold(0u64, |sum, amount| sum.checked_add(amount))
+        .ok_or_else(|| error!(PayGuardError::Overflow))
+}
+
+/// Build pending milestones from the caller-authored fields of a schedule
+fn new_milestones(inputs: Vec<MilestoneInput>) -> Vec<Milestone> {
+    inputs.into_iter().map(Milestone::from).collect()
+}
+
+/// Turn milestone amounts given in basis points of `total_amount` into token
+/// amounts, flooring each and giving the remainder to the last milestone
+fn normalize_bps_amounts(milestones: &mut [Milestone], total_amount: u64) -> Result<()> {
+    let bps_total = checked_sum(milestones.iter().map(|m| m.amount))?;
+    require!(bps_total == BPS_DENOMINATOR, PayGuardError::InvalidMilestoneBps);
+    
+    let mut assigned = 0;
+    for m in milestones.iter_mut() {
+        m.amount = (total_amount as u128 * m.amount as u128 / BPS_DENOMINATOR as u128) as u64;
+        assigned += m.amount;
+    }
+    if let Some(last) = milestones.last_mut() {
+        last.amount += total_amount - assigned;
+    }
+    Ok(())
+}
+
+/// Validate a milestone schedule against the contract total and protocol config
+///
+/// `capacity` is the most milestones the contract account has room for.
+fn validate_milestones(
+    milestones: &[Milestone],
+    total_amount: u64,
+    config: &Config,
+    terms: &ContractTerms,
+    capacity: usize,
+) -> Result<()> {
+    require!(!milestones.is_empty() && milestones.len() <= capacity, PayGuardError::InvalidMilestones);
+    require!(milestones.len() >= config.min_milestones as usize, PayGuardError::TooFewMilestones);
+    require!(milestones.iter().all(|m| m.amount > 0), PayGuardError::ZeroMilestoneAmount);
+    
+    let total_milestone_amount = checked_sum(milestones.iter().map(|m| m.amount))?;
+    require!(total_milestone_amount == total_amount, PayGuardError::AmountMismatch);
+    require!(
+        milestones.iter().all(|m| m.penalty_per_day_bps as u64 <= BPS_DENOMINATOR),
+        PayGuardError::InvalidPenalty
+    );
+    require!(
+        milestones.iter().all(|m| match m.display_currency {
+            Some(code) => code.iter().all(u8::is_ascii_uppercase) && m.display_rate > 0,
+            None => m.display_rate == 0,
+        }),
+        PayGuardError::InvalidDisplayCurrency
+    );
+    require!(
+        milestones.iter().all(|m| m.vesting_secs.map_or(true, |secs| secs > 0)),
+        PayGuardError::InvalidVesting
+    );
+    require!(
+        !terms.sequential || milestones.windows(2).all(|w| w[0].amount <= w[1].amount),
+        PayGuardError::NonMonotonicMilestones
+    );
+    
+    let bonus_total = checked_sum(milestones.iter().map(|m| m.bonus_amount))?;
+    require!(bonus_total == 0 || terms.bonus_mint.is_some(), PayGuardError::InvalidBonus);
+    
+    // Deadlines are agreed when the milestones are, so they must still lie ahead
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        milestones.iter().all(|m| m.due_date.map_or(true, |due_date| due_date > now)),
+        PayGuardError::InvalidDueDate
+    );
+    
+    Ok(())
+}
+
+/// Transfer tokens out of the escrow vault, signed by the contract PDA
+fn transfer_from_vault<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &impl ToAccountInfo<'info>,
+    to: &impl ToAccountInfo<'info>,
+    contract: &Account<'info, Contract>,
+    amount: u64,
+) -> Result<()> {
+    transfers_from_vault(token, escrow_vault, contract, &[(to.to_account_info(), amount)])
+}
+
+/// Several signed transfers out of the escrow vault
+///
+/// The vault, contract and token program infos and the PDA seeds are built
+/// once and shared, so multi-transfer paths such as splits do not pay for
+/// them per transfer. Zero amounts are skipped, and no destination may be
+/// the vault itself. Under `terms.gross_up_transfer_fees`, transfers to the
+/// freelancer also carry the mint's transfer fee, as far as the vault holds
+/// more than the contract owes.
+fn transfers_from_vault<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &impl ToAccountInfo<'info>,
+    contract: &Account<'info, Contract>,
+    transfers: &[(AccountInfo<'info>, u64)],
+) -> Result<()> {
+    let id_bytes = contract.id.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"contract", &id_bytes, &[contract.bump]];
+    let signer = &[seeds];
+    
+    let program = token.program.to_account_info();
+    let from = escrow_vault.to_account_info();
+    let mint = token.mint.to_account_info();
+    let authority = contract.to_account_info();
+    let mut surplus = if contract.terms.gross_up_transfer_fees && from.key() == contract.escrow_vault {
+        let batch = checked_sum(transfers.iter().map(|(_, amount)| *amount))?;
+        token::accessor::amount(&from)?
+            .saturating_sub(batch)
+            .saturating_sub(contract.vault_obligations()?)
+    } else {
+        0
+    };
+    for (to, amount) in transfers.iter().filter(|(_, amount)| *amount > 0) {
+        require!(to.key != from.key, PayGuardError::SameAccount);
+        let mut amount = *amount;
+        if surplus > 0 && token::accessor::authority(to)? == contract.freelancer {
+            let extra = transfer_fee_to_deliver(token.mint, amount)?.min(surplus);
+            surplus -= extra;
+            amount += extra;
+        }
+        let cpi_accounts = TransferChecked {
+            from: from.clone(),
+            mint: mint.clone(),
+            to: to.clone(),
+            authority: authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(program.clone(), cpi_accounts, signer);
+        token_interface::transfer_checked(cpi_ctx, amount, token.mint.decimals)?;
+    }
+    Ok(())
+}
+
+/// Transfer fee the mint charges on a transfer that must deliver `net`; 0 for
+/// classic SPL mints and Token-2022 mints without a transfer fee
+fn transfer_fee_to_deliver(mint: &InterfaceAccount<'_, Mint>, net: u64) -> Result<u64> {
+    let mint = mint.to_account_info();
+    if mint.owner != &anchor_spl::token_2022::ID {
+        return Ok(0);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Token2022Mint>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net)
+        .ok_or_else(|| error!(PayGuardError::Overflow))
+}
+
+/// Most a token escrow may be funded with: `total_amount`, plus under
+/// `terms.gross_up_transfer_fees` the fee on paying all of it to the freelancer
+fn funding_cap(mint: &InterfaceAccount<'_, Mint>, contract: &Contract) -> Result<u64> {
+    let buffer = if contract.terms.gross_up_transfer_fees {
+        transfer_fee_to_deliver(mint, contract.total_amount)?
+    } else {
+        0
+    };
+    contract.total_amount.checked_add(buffer).ok_or_else(|| error!(PayGuardError::Overflow))
+}
+
+/// What the vault keeps back for the freelancer's pending withdrawal, with its
+/// transfer fee when the terms gross transfers to the freelancer up
+fn withdrawal_reserve(token: TokenCpi<'_, '_>, contract: &Contract) -> Result<u64> {
+    let fee = if contract.terms.gross_up_transfer_fees {
+        transfer_fee_to_deliver(token.mint, contract.approved_unclaimed)?
+    } else {
+        0
+    };
+    Ok(contract.approved_unclaimed.checked_add(fee).ok_or(PayGuardError::Overflow)?)
+}
+
+/// Mark a contract `Cancelled` and refund its escrow to whoever funded it
+fn cancel_and_refund<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &InterfaceAccount<'info, TokenAccount>,
+    refund_account: &InterfaceAccount<'info, TokenAccount>,
+    contract: &mut Account<'info, Contract>,
+) -> Result<()> {
+    // Held payouts belong to the freelancer unless clawed back first
+    require!(contract.held_total == 0, PayGuardError::ClawbackWindowOpen);
+    // A bond belongs to whoever the pending dispute decides for
+    require!(contract.bonds_held()? == 0, PayGuardError::DisputeBondHeld);
+    
+    // Retainage was withheld from milestones the freelancer already earned,
+    // so it joins their approved payouts, which stay behind to be withdrawn
+    contract.approved_unclaimed = contract
+        .approved_unclaimed
+        .checked_add(contract.retainage_held)
+        .ok_or(PayGuardError::Overflow)?;
+    contract.retainage_held = 0;
+    
+    // Everything else left in the vault (undisbursed milestones and any
+    // funding surplus) goes back to whoever funded it
+    let refund_amount = escrow_vault.amount.saturating_sub(withdrawal_reserve(token, contract)?);
+    
+    // Effects before interactions: the contract is already terminal when
+    // the refund CPI runs
+    contract.status = ContractStatus::Cancelled;
+    
+    transfer_from_vault(token, escrow_vault, refund_account, contract, refund_amount)?;
+    
+    emit!(ContractCancelled {
+        contract_id: contract.id,
+        refund_recipient: refund_account.owner,
+        refunded: refund_amount,
+        cancelled_at: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Mark a SOL-escrow contract `Cancelled` and refund its SOL vault to the client
+fn cancel_and_refund_sol<'info>(
+    sol_vault: &SystemAccount<'info>,
+    sol_vault_bump: u8,
+    client: &impl ToAccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    contract: &mut Account<'info, Contract>,
+) -> Result<()> {
+    contract.status = ContractStatus::Cancelled;
+    
+    let refund_amount = sol_vault.lamports();
+    let contract_key = contract.key();
+    let seeds: &[&[u8]] = &[b"sol_vault", contract_key.as_ref(), &[sol_vault_bump]];
+    let cpi_accounts = system_program::Transfer {
+        from: sol_vault.to_account_info(),
+        to: client.to_account_info(),
+    };
+    let signer_seeds = &[seeds];
+    let cpi_ctx = CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer_seeds);
+    system_program::transfer(cpi_ctx, refund_amount)?;
+    
+    emit!(ContractCancelled {
+        contract_id: contract.id,
+        refund_recipient: contract.client,
+        refunded: refund_amount,
+        cancelled_at: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Accounts a milestone release or dispute decision pays out through
+struct DecisionPayout<'a, 'info> {
+    config: &'a Config,
+    token: TokenCpi<'a, 'info>,
+    bonus_mint: Option<&'a InterfaceAccount<'info, Mint>>,
+    escrow_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    /// Approvals only credit the freelancer, so only dispute decisions need it
+    freelancer_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    client_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    client: &'a AccountInfo<'info>,
+    bonus_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    freelancer_bonus_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    fee_treasury_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    restitution_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> DecisionPayout<'a, 'info> {
+    fn freelancer_token_account(&self) -> Result<&'a InterfaceAccount<'info, TokenAccount>> {
+        self.freelancer_token_account
+            .ok_or_else(|| error!(PayGuardError::FreelancerAccountMissing))
+    }
+}
+
+/// Approve a submitted milestone and release it, completing the contract if
+/// this was the last open value
+fn release_milestone<'info>(
+    accounts: &DecisionPayout<'_, 'info>,
+    contract: &mut Account<'info, Contract>,
+    milestone_index: u8,
+) -> Result<()> {
+    approve_submitted(accounts, contract, milestone_index)?;
+    complete_if_released(accounts, contract)
+}
+
+/// Check that a submitted milestone may be approved now
+///
+/// Shared by every approval path, whichever way it then pays the milestone out.
+fn check_approvable(config: &Config, contract: &Contract, milestone_index: u8) -> Result<()> {
+    require!(!config.paused, PayGuardError::ProgramPaused);
+    require!(contract.status != ContractStatus::Disputed, PayGuardError::ContractDisputed);
+    require!(contract.status == ContractStatus::Active, PayGuardError::ContractNotActive);
+    require!(contract.funded_amount > 0, PayGuardError::EscrowNotFunded);
+    require!((milestone_index as usize) < contract.milestones.len(), PayGuardError::InvalidMilestoneIndex);
+    require!(!contract.is_paused(Clock::get()?.unix_timestamp), PayGuardError::ContractPaused);
+    
+    let milestone = &contract.milestones[milestone_index as usize];
+    require!(contract.covers(milestone.amount), PayGuardError::EscrowUnderfunded);
+    require!(milestone.status == MilestoneStatus::Submitted, PayGuardError::MilestoneNotSubmitted);
+    require!(
+        Clock::get()?.unix_timestamp >= milestone.review_period_end()?,
+        PayGuardError::ReviewPeriodNotElapsed
+    );
+    Ok(())
+}
+
+/// Approve a submitted milestone: credit the freelancer net of fee and
+/// retainage and pay the bonus and any late penalty
+fn approve_submitted<'info>(
+    accounts: &DecisionPayout<'_, 'info>,
+    contract: &mut Account<'info, Contract>,
+    milestone_index: u8,
+) -> Result<()> {
+    // SOL escrows release through `approve_milestone_swap` or `approve_milestone_native`
+    require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+    check_approvable(accounts.config, contract, milestone_index)?;
+    
+    let milestone = &mut contract.milestones[milestone_index as usize];
+    let amount = milestone.amount;
+    let bonus = milestone.bonus_amount;
+    let penalty = milestone.late_penalty();
+    milestone.status = MilestoneStatus::Approved;
+    milestone.client_share = penalty;
+    contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+    contract.refunded_amount += penalty;
+    contract.refresh_status_bitmap();
+    
+    // Transfer from escrow to freelancer, net of the platform fee and
+    // withholding retainage
+    let (fee, net) = contract.take_fee(amount - penalty);
+    let payout = contract.withhold_retainage(net);
+    contract.credit_payout(milestone_index, payout, Clock::get()?.unix_timestamp)?;
+    
+    pay_fee(
+        accounts.token,
+        accounts.escrow_vault,
+        accounts.fee_treasury_token_account,
+        contract,
+        fee,
+    )?;
+    
+    release_bonus(accounts, contract, bonus)?;
+    
+    // Late penalty goes back to the client
+    transfer_from_vault(
+        accounts.token,
+        accounts.escrow_vault,
+        accounts.client_token_account,
+        contract,
+        penalty,
+    )?;
+    
+    emit!(MilestoneApproved {
+        contract_id: contract.id,
+        milestone_index,
+        amount,
+        fee,
+        payout,
+        approved_at: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Complete the contract once every milestone is released
+fn complete_if_released<'info>(
+    accounts: &DecisionPayout<'_, 'info>,
+    contract: &mut Account<'info, Contract>,
+) -> Result<()> {
+    if contract.is_fully_released() {
+        complete_contract(
+            accounts.token,
+            accounts.escrow_vault,
+            accounts.client_token_account,
+            accounts.client,
+            contract,
+            accounts.remaining_accounts,
+        )?;
+    }
+    
+    Ok(())
+}
+
+/// Settle a dispute decision: move the milestone to its final state, pay out
+/// and complete the contract if this was the last open value
+fn execute_decision<'info>(
+    accounts: &DecisionPayout<'_, 'info>,
+    contract: &mut Account<'info, Contract>,
+    milestone_index: u8,
+    decision: DisputeDecision,
+) -> Result<()> {
+    require!(!accounts.config.paused, PayGuardError::ProgramPaused);
+    require!(!contract.escrows_sol(), PayGuardError::SolEscrowOnly);
+    if !matches!(decision, DisputeDecision::FavorClient) {
+        require!(
+            contract.covers(contract.milestones[milestone_index as usize].amount),
+            PayGuardError::EscrowUnderfunded
+        );
+    }
+    
+    let milestone = &mut contract.milestones[milestone_index as usize];
+    let amount = milestone.amount;
+    
+    match decision {
+        DisputeDecision::FavorFreelancer => {
+            let bonus = milestone.bonus_amount;
+            let penalty = milestone.late_penalty();
+            milestone.status = MilestoneStatus::Approved;
+            milestone.client_share = penalty;
+            contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+            contract.refunded_amount += penalty;
+            
+            // Credit the freelancer as an approval would
+            let (fee, net) = contract.take_fee(amount - penalty);
+            let payout = contract.withhold_retainage(net);
+            contract.credit_payout(milestone_index, payout, Clock::get()?.unix_timestamp)?;
+            
+            pay_fee(
+                accounts.token,
+                accounts.escrow_vault,
+                accounts.fee_treasury_token_account,
+                contract,
+                fee,
+            )?;
+            
+            release_bonus(accounts, contract, bonus)?;
+            
+            // Late penalty goes back to the client
+            transfer_from_vault(
+                accounts.token,
+                accounts.escrow_vault,
+                accounts.client_token_account,
+                contract,
+                penalty,
+            )?;
+        }
+        DisputeDecision::FavorClient => {
+            milestone.status = MilestoneStatus::Rejected;
+            // Funds stay in escrow for a revision or `claim_rejected_refund`;
+            // an upheld rejection cannot be contested again
+            milestone.rejected_at = None;
+        }
+        DisputeDecision::Forfeit => {
+            // Neither party is paid; the milestone goes to restitution
+            milestone.status = MilestoneStatus::Forfeited;
+            contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+            let restitution = accounts
+                .restitution_token_account
+                .ok_or(PayGuardError::RestitutionAccountMissing)?;
+            transfer_from_vault(accounts.token, accounts.escrow_vault, restitution, contract, amount)?;
+        }
+        DisputeDecision::Split(freelancer_pct) => {
+            require!(freelancer_pct <= 100, PayGuardError::InvalidSplit);
+            // The freelancer's share rounds down and the client takes the
+            // remainder, so the two shares always add up to the milestone
+            let freelancer_amount = (amount as u128 * freelancer_pct as u128 / 100) as u64;
+            let client_amount = amount - freelancer_amount;
+            
+            milestone.status = MilestoneStatus::Resolved;
+            milestone.client_share = client_amount;
+            contract.released_amount = contract.released_amount.checked_add(amount).ok_or(PayGuardError::Overflow)?;
+            contract.refunded_amount += client_amount;
+            
+            // Credit the freelancer's share net of the fee and retainage as an
+            // approval would, and refund the client's share
+            let (fee, net) = contract.take_fee(freelancer_amount);
+            let payout = contract.withhold_retainage(net);
+            contract.credit_payout(milestone_index, payout, Clock::get()?.unix_timestamp)?;
+            transfer_from_vault(
+                accounts.token,
+                accounts.escrow_vault,
+                accounts.client_token_account,
+                contract,
+                client_amount,
+            )?;
+            
+            pay_fee(
+                accounts.token,
+                accounts.escrow_vault,
+                accounts.fee_treasury_token_account,
+                contract,
+                fee,
+            )?;
+        }
+    }
+    settle_dispute_bond(accounts, contract, milestone_index, &decision)?;
+    contract.refresh_dispute_status();
+    contract.refresh_status_bitmap();
+    
+    // Check completion
+    if contract.is_fully_released() {
+        complete_contract(
+            accounts.token,
+            accounts.escrow_vault,
+            accounts.client_token_account,
+            accounts.client,
+            contract,
+            accounts.remaining_accounts,
+        )?;
+    }
+    
+    Ok(())
+}
+
+/// Return a dispute's bonds to their stakers if the decision went their way,
+/// or forward them to the counterparty if not; a forfeit sends them to restitution
+///
+/// The raiser staked the dispute bond and the appellant any appeal bond. A
+/// split counts for a staker when it gives them at least half.
+fn settle_dispute_bond<'info>(
+    accounts: &DecisionPayout<'_, 'info>,
+    contract: &mut Account<'info, Contract>,
+    milestone_index: u8,
+    decision: &DisputeDecision,
+) -> Result<()> {
+    let client = contract.client;
+    let milestone = &mut contract.milestones[milestone_index as usize];
+    let bonds = [
+        (std::mem::take(&mut milestone.dispute_bond), milestone.dispute_raised_by.take()),
+        (std::mem::take(&mut milestone.appeal_bond), milestone.appealed_by.take()),
+    ];
+    
+    for (bond, staker) in bonds {
+        if bond == 0 {
+            continue;
+        }
+        let client_prevailed = match decision {
+            DisputeDecision::FavorFreelancer => false,
+            DisputeDecision::FavorClient => true,
+            DisputeDecision::Split(freelancer_pct) => {
+                if staker == Some(client) { *freelancer_pct <= 50 } else { *freelancer_pct < 50 }
+            }
+            DisputeDecision::Forfeit => {
+                let restitution = accounts
+                    .restitution_token_account
+                    .ok_or(PayGuardError::RestitutionAccountMissing)?;
+                transfer_from_vault(accounts.token, accounts.escrow_vault, restitution, contract, bond)?;
+                continue;
+            }
+        };
+        let recipient = if client_prevailed {
+            accounts.client_token_account
+        } else {
+            accounts.freelancer_token_account()?
+        };
+        transfer_from_vault(accounts.token, accounts.escrow_vault, recipient, contract, bond)?;
+    }
+    Ok(())
+}
+
+/// Message a client signs off-chain to approve a milestone's current submission
+/// through a relayer
+fn approval_message(contract: &Contract, milestone_index: u8) -> Result<Vec<u8>> {
+    let milestone = contract
+        .milestones
+        .get(milestone_index as usize)
+        .ok_or(PayGuardError::InvalidMilestoneIndex)?;
+    let mut message = APPROVAL_MESSAGE_PREFIX.to_vec();
+    message.extend_from_slice(ID.as_ref());
+    message.extend_from_slice(&contract.id.to_le_bytes());
+    message.push(milestone_index);
+    message.push(milestone.revision_count);
+    message.extend_from_slice(&milestone.submitted_at.unwrap_or_default().to_le_bytes());
+    Ok(message)
+}
+
+/// Require the instruction before this one to be an Ed25519 program check of
+/// a single signature by `signer` over `message`, with all data inline
+fn verify_client_approval(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, PayGuardError::InvalidApprovalSignature);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+    require!(ix.program_id == ed25519_program::ID, PayGuardError::InvalidApprovalSignature);
+    
+    // Header: count, padding, then one 14-byte offsets record
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, PayGuardError::InvalidApprovalSignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_ix = read_u16(4);
+    let pubkey_offset = read_u16(6) as usize;
+    let pubkey_ix = read_u16(8);
+    let message_offset = read_u16(10) as usize;
+    let message_len = read_u16(12) as usize;
+    let message_ix = read_u16(14);
+    require!(
+        [signature_ix, pubkey_ix, message_ix].iter().all(|&i| i == u16::MAX),
+        PayGuardError::InvalidApprovalSignature
+    );
+    
+    let signed_pubkey = data.get(pubkey_offset..pubkey_offset + 32);
+    let signed_message = data.get(message_offset..message_offset + message_len);
+    require!(
+        signed_pubkey == Some(signer.as_ref()) && signed_message == Some(message),
+        PayGuardError::InvalidApprovalSignature
+    );
+    Ok(())
+}
+
+/// Record a resolution in the resolver's registry entry, if they have one
+fn record_resolution(profile: &AccountInfo, elapsed_secs: i64, sla_secs: i64) -> Result<()> {
+    if profile.data_is_empty() {
+        return Ok(());
+    }
+    require!(profile.owner == &ID, PayGuardError::InvalidArbitratorProfile);
+    let mut data = profile.try_borrow_mut_data()?;
+    let mut entry = ArbitratorProfile::try_deserialize(&mut &data[..])?;
+    entry.record_resolution(elapsed_secs, sla_secs);
+    entry.try_serialize(&mut &mut data[..])
+}
+
+/// Check that `attestation` is an unexpired KYC attestation from the configured authority
+fn check_kyc(attestation: &AccountInfo, config: &Config, now: i64) -> Result<()> {
+    require!(!attestation.data_is_empty(), PayGuardError::KycMissing);
+    require!(attestation.owner == &ID, PayGuardError::InvalidKycAttestation);
+    let attestation = KycAttestation::try_deserialize(&mut &attestation.try_borrow_data()?[..])?;
+    require!(attestation.authority == config.kyc_authority, PayGuardError::InvalidKycAttestation);
+    require!(attestation.expires_at > now, PayGuardError::KycExpired);
+    Ok(())
+}
+
+/// Read a fresh price from a Pyth price account
+fn oracle_price(price_feed: &AccountInfo, now: i64, max_age_secs: u64) -> Result<Price> {
+    let feed = load_price_feed_from_account_info(price_feed).map_err(|_| PayGuardError::InvalidPriceFeed)?;
+    let price = feed
+        .get_price_no_older_than(now, max_age_secs)
+        .ok_or(PayGuardError::StalePrice)?;
+    require!(price.price > 0, PayGuardError::InvalidPriceFeed);
+    Ok(price)
+}
+
+/// Convert a fee in payment token units to fee token units at oracle prices,
+/// flooring the result
+fn convert_fee(
+    fee: u64,
+    payment_price: &Price,
+    payment_decimals: u8,
+    fee_price: &Price,
+    fee_decimals: u8,
+) -> Result<u64> {
+    // fee * payment_price / fee_price, rescaled from payment to fee token decimals
+    let exponent = payment_price.expo - fee_price.expo + fee_decimals as i32 - payment_decimals as i32;
+    let mut numerator = fee as u128 * payment_price.price as u128;
+    let mut denominator = fee_price.price as u128;
+    let scale = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or(PayGuardError::InvalidPriceFeed)?;
+    if exponent >= 0 {
+        numerator = numerator.checked_mul(scale).ok_or(PayGuardError::InvalidPriceFeed)?;
+    } else {
+        denominator = denominator.checked_mul(scale).ok_or(PayGuardError::InvalidPriceFeed)?;
+    }
+    u64::try_from(numerator / denominator).map_err(|_| error!(PayGuardError::InvalidPriceFeed))
+}
+
+/// Transfer the platform fee to the treasury, if any fee is due
+fn pay_fee<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &InterfaceAccount<'info, TokenAccount>,
+    fee_treasury_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    contract: &Account<'info, Contract>,
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    let treasury = fee_treasury_token_account.ok_or(PayGuardError::FeeAccountMissing)?;
+    transfer_from_vault(token, escrow_vault, treasury, contract, fee)
+}
+
+/// Pay the resolver `terms.arbitrator_fee_bps` of a disputed milestone, once per dispute
+///
+/// The fee leaves the escrow, so the milestone, the contract total and its
+/// funding all shrink by it and the decision divides what remains. A dispute
+/// reopened by an appeal has already paid, so its next decision pays nothing.
+fn pay_arbitrator_fee<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &InterfaceAccount<'info, TokenAccount>,
+    arbitrator_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    contract: &mut Account<'info, Contract>,
+    milestone_index: u8,
+) -> Result<()> {
+    let milestone = &contract.milestones[milestone_index as usize];
+    if milestone.arbitrator_fee_paid {
+        return Ok(());
+    }
+    let fee = contract.arbitrator_fee_on(milestone.amount);
+    if fee == 0 {
+        return Ok(());
+    }
+    let arbitrator_token_account = arbitrator_token_account.ok_or(PayGuardError::ArbitratorAccountMissing)?;
+    require!(contract.covers(fee), PayGuardError::EscrowUnderfunded);
+    
+    let milestone = &mut contract.milestones[milestone_index as usize];
+    milestone.arbitrator_fee_paid = true;
+    milestone.amount -= fee;
+    contract.total_amount -= fee;
+    contract.funded_amount -= fee;
+    transfer_from_vault(token, escrow_vault, arbitrator_token_account, contract, fee)
+}
+
+/// Pay a milestone's bonus out of the secondary bonus vault
+fn release_bonus<'info>(
+    accounts: &DecisionPayout<'_, 'info>,
+    contract: &mut Account<'info, Contract>,
+    bonus: u64,
+) -> Result<()> {
+    if bonus == 0 {
+        return Ok(());
+    }
+    let (Some(bonus_mint), Some(bonus_vault), Some(destination)) = (
+        accounts.bonus_mint,
+        accounts.bonus_vault,
+        accounts.freelancer_bonus_token_account,
+    ) else {
+        return err!(PayGuardError::BonusAccountsMissing);
+    };
+    require!(bonus_vault.key() == contract.bonus_vault, PayGuardError::InvalidVault);
+    require!(contract.terms.bonus_mint == Some(bonus_mint.key()), PayGuardError::InvalidBonus);
+    
+    contract.bonus_released += bonus;
+    let token = TokenCpi::new(accounts.token.program, bonus_mint);
+    transfer_from_vault(token, bonus_vault, destination, contract, bonus)
+}
+
+/// Close an empty escrow vault, returning its rent to `destination`
+fn close_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    escrow_vault: &impl ToAccountInfo<'info>,
+    destination: &impl ToAccountInfo<'info>,
+    contract: &Account<'info, Contract>,
+) -> Result<()> {
+    let id_bytes = contract.id.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"contract", &id_bytes, &[contract.bump]];
+    let signer = &[seeds];
+    
+    let cpi_accounts = CloseAccount {
+        account: escrow_vault.to_account_info(),
+        destination: destination.to_account_info(),
+        authority: contract.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token_interface::close_account(cpi_ctx)
+}
+
+/// Move transfer fees withheld in a Token-2022 vault to its mint
+///
+/// A vault still holding withheld fees cannot be closed; vaults of classic
+/// SPL mints and of mints without a transfer fee are left untouched.
+fn harvest_withheld_fees<'info>(token: TokenCpi<'_, 'info>, vault: &impl ToAccountInfo<'info>) -> Result<()> {
+    let vault = vault.to_account_info();
+    if vault.owner != &anchor_spl::token_2022::ID {
+        return Ok(());
+    }
+    let withheld = {
+        let data = vault.try_borrow_data()?;
+        let state = StateWithExtensions::<Token2022Account>::unpack(&data)?;
+        state
+            .get_extension::<TransferFeeAmount>()
+            .map_or(0, |fees| u64::from(fees.withheld_amount))
+    };
+    if withheld == 0 {
+        return Ok(());
+    }
+    
+    let ix = transfer_fee_ix::harvest_withheld_tokens_to_mint(
+        &anchor_spl::token_2022::ID,
+        &token.mint.key(),
+        &[vault.key],
+    )?;
+    invoke(&ix, &[token.mint.to_account_info(), vault, token.program.to_account_info()])?;
+    Ok(())
+}
+
+/// Credit any retainage held to the freelancer and mark the contract finished
+///
+/// Any dust beyond what the freelancer is owed (such as a funding surplus) is
+/// swept to the client. A vault left with nothing owed is closed, its rent
+/// returning to the client; otherwise it stays open for `withdraw_funds`.
+fn complete_contract<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &InterfaceAccount<'info, TokenAccount>,
+    client_token_account: &InterfaceAccount<'info, TokenAccount>,
+    client: &impl ToAccountInfo<'info>,
+    contract: &mut Account<'info, Contract>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    contract.approved_unclaimed += contract.retainage_held;
+    contract.retainage_held = 0;
+    
+    // Read the live balance; the cached account predates this instruction's transfers
+    let balance = token::accessor::amount(&escrow_vault.to_account_info())?;
+    let dust = balance.saturating_sub(withdrawal_reserve(token, contract)?);
+    transfer_from_vault(token, escrow_vault, client_token_account, contract, dust)?;
+    if contract.approved_unclaimed == 0 {
+        close_drained_vault(token, escrow_vault, client, contract)?;
+    }
+    
+    finish_contract(contract, remaining_accounts)
+}
+
+/// Harvest any withheld transfer fees from an emptied vault and close it to `client`
+fn close_drained_vault<'info>(
+    token: TokenCpi<'_, 'info>,
+    escrow_vault: &InterfaceAccount<'info, TokenAccount>,
+    client: &impl ToAccountInfo<'info>,
+    contract: &Account<'info, Contract>,
+) -> Result<()> {
+    harvest_withheld_fees(token, escrow_vault)?;
+    close_vault(token.program, escrow_vault, client, contract)
+}
+
+/// Complete a fully paid contract and notify the completion callback program,
+/// or leave it awaiting `finalize_contract` when the client must sign off
+fn finish_contract<'info>(
+    contract: &mut Account<'info, Contract>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    if contract.terms.require_sign_off {
+        contract.status = ContractStatus::PendingFinalization;
+        return Ok(());
+    }
+    contract.status = ContractStatus::Completed;
+    emit_completed(contract)?;
+    notify_completion(contract, remaining_accounts)
+}
+
+/// Announce a contract that has just reached `Completed`
+fn emit_completed(contract: &Contract) -> Result<()> {
+    emit!(ContractCompleted {
+        contract_id: contract.id,
+        released_amount: contract.released_amount,
+        refunded_amount: contract.refunded_amount,
+        fees_collected: contract.fees_collected,
+        completed_at: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Invoke the client's completion callback program, if configured
+///
+/// The callback program must be passed in `remaining_accounts` of every
+/// instruction that can complete the contract, so the notice cannot be
+/// skipped. The runtime cannot contain a failed CPI, so a callback that fails
+/// fails the completing instruction with it: completion depends on the
+/// callback accepting the notice. The contract PDA signs the call so the
+/// callee can verify the notice came from PayGuard.
+fn notify_completion<'info>(
+    contract: &Account<'info, Contract>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let Some(callback_program) = contract.terms.completion_callback_program else {
+        return Ok(());
+    };
+    let program_info = remaining_accounts
+        .iter()
+        .find(|a| a.key() == callback_program && a.executable)
+        .ok_or(PayGuardError::CompletionCallbackMissing)?;
+    
+    let notice = CompletionNotice {
+        contract_id: contract.id,
+        total_amount: contract.total_amount,
+        released_amount: contract.released_amount,
+        refunded_amount: contract.refunded_amount,
+    };
+    let mut data = hash(COMPLETION_CALLBACK_IX_NAME).to_bytes()[..8].to_vec();
+    notice.serialize(&mut data)?;
+    
+    let ix = Instruction {
+        program_id: callback_program,
+        accounts: vec![AccountMeta::new_readonly(contract.key(), true)],
+        data,
+    };
+    
+    let id_bytes = contract.id.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"contract", &id_bytes, &[contract.bump]];
+    invoke_signed(&ix, &[contract.to_account_info(), program_info.clone()], &[seeds])?;
+    
+    Ok(())
+}
+
+// ============ ACCOUNTS ============
+
+#[derive(Accounts)]
+#[instruction(contract_id: u64)]
+pub struct CreateContract<'info> {
+    #[account(
+        init,
+        payer = client,
+        space = 8 + Contract::INIT_SPACE,
+        seeds = [b"contract", &contract_id.to_le_bytes()],
+        bump
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    /// CHECK: Freelancer pubkey, validated by business logic
+    pub freelancer: AccountInfo<'info>,
+    
+    /// CHECK: Authorized arbitrator for dispute resolution
+    /// This should be PayGuard's oracle or a trusted third party
+    pub arbitrator: AccountInfo<'info>,
+    
+    /// Token mint for payment
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    /// The contract's escrow vault, bound to it for life
+    #[account(
+        init,
+        payer = client,
+        seeds = [b"vault".as_ref(), &contract_id.to_le_bytes()],
+        bump,
+        token::mint = token_mint,
+        token::authority = contract,
+        token::token_program = token_program
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Blocklist entry for the client; must not exist
+    #[account(
+        seeds = [b"blocked", client.key().as_ref()],
+        bump,
+        constraint = client_block.data_is_empty() @ PayGuardError::AddressBlocked
+    )]
+    pub client_block: UncheckedAccount<'info>,
+    
+    /// CHECK: Blocklist entry for the freelancer; must not exist
+    #[account(
+        seeds = [b"blocked", freelancer.key().as_ref()],
+        bump,
+        constraint = freelancer_block.data_is_empty() @ PayGuardError::AddressBlocked
+    )]
+    pub freelancer_block: UncheckedAccount<'info>,
+    
+    /// CHECK: Client's KYC attestation, validated when the terms require KYC
+    #[account(seeds = [b"kyc", client.key().as_ref()], bump)]
+    pub client_kyc: UncheckedAccount<'info>,
+    
+    /// CHECK: Freelancer's KYC attestation, validated when the terms require KYC
+    #[account(seeds = [b"kyc", freelancer.key().as_ref()], bump)]
+    pub freelancer_kyc: UncheckedAccount<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeMilestones<'info> {
+    #[account(mut, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub freelancer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptContract<'info> {
+    #[account(mut, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub freelancer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AmendContract<'info> {
+    #[account(mut, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    pub freelancer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestones: Vec<MilestoneInput>)]
+pub struct AddMilestones<'info> {
+    #[account(
+        mut,
+        has_one = client,
+        has_one = freelancer,
+        realloc = Contract::space_for(contract.milestones.len() + milestones.len()),
+        realloc::payer = client,
+        realloc::zero = false,
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    pub freelancer: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptMilestones<'info> {
+    #[account(mut, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Client's KYC attestation, validated when the terms require KYC
+    #[account(seeds = [b"kyc", client.key().as_ref()], bump)]
+    pub client_kyc: UncheckedAccount<'info>,
+    
+    /// CHECK: Freelancer's KYC attestation, validated when the terms require KYC
+    #[account(seeds = [b"kyc", contract.freelancer.as_ref()], bump)]
+    pub freelancer_kyc: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrow<'info> {
+    #[account(mut, has_one = token_mint, constraint = contract.may_fund(funder.key) @ PayGuardError::Unauthorized)]
+    pub contract: Account<'info, Contract>,
+    
+    /// The client or the contract's funding delegate
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    
+    #[account(mut, constraint = funder_token_account.mint == contract.token_mint @ PayGuardError::InvalidFunderAccount)]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrowSponsored<'info> {
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = contract.terms.sponsor == Some(sponsor.key()) @ PayGuardError::Unauthorized
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    pub sponsor: Signer<'info>,
+    
+    #[account(mut, constraint = sponsor_token_account.mint == contract.token_mint @ PayGuardError::InvalidFunderAccount)]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct FundEscrowSol<'info> {
+    #[account(mut, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    #[account(mut, seeds = [b"sol_vault", contract.key().as_ref()], bump)]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitMilestone<'info> {
+    #[account(mut, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub freelancer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlagMissedDeadline<'info> {
+    #[account(mut)]
+    pub contract: Account<'info, Contract>,
+    
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    /// Checked before the vault, which a completed contract has already closed
+    #[account(
+        mut,
+        has_one = client,
+        has_one = freelancer,
+        has_one = token_mint,
+        constraint = contract.status != ContractStatus::Completed @ PayGuardError::ContractAlreadyCompleted
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    /// Receives the vault's rent when the final release closes it
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    /// CHECK: Validated by contract
+    pub freelancer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives any late penalty
+    #[account(mut)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Required only when the milestone carries a bonus
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// Required only when a platform fee is due
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestoneSigned<'info> {
+    #[account(
+        mut,
+        has_one = client,
+        has_one = freelancer,
+        has_one = token_mint,
+        constraint = contract.status != ContractStatus::Completed @ PayGuardError::ContractAlreadyCompleted
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    /// Submits the client's signed approval and pays the fees
+    pub relayer: Signer<'info>,
+    
+    /// CHECK: Validated by contract; approves via the Ed25519 instruction and
+    /// receives the vault's rent when the final release closes it
+    #[account(mut)]
+    pub client: UncheckedAccount<'info>,
+    
+    /// CHECK: Validated by contract
+    pub freelancer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// The relayer is untrusted, so refunds may only reach the client's own account
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// CHECK: Instructions sysvar, read for the preceding Ed25519 check
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimeoutRelease<'info> {
+    /// Checked before the vault, which a completed contract has already closed
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = contract.status != ContractStatus::Completed @ PayGuardError::ContractAlreadyCompleted
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    /// Anyone may release a submission once its review window has lapsed
+    pub caller: Signer<'info>,
+    
+    /// CHECK: The contract's client; receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives any late penalty
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Required only when the milestone carries a bonus
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// Required only when a platform fee is due
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(milestone_index: u8, renewal_id: u64)]
+pub struct CompleteAndRenew<'info> {
+    /// Closed to the client once the final approval completes it
+    #[account(
+        mut,
+        has_one = client,
+        has_one = freelancer,
+        has_one = token_mint,
+        close = client,
+        constraint = contract.status != ContractStatus::Completed @ PayGuardError::ContractAlreadyCompleted
+    )]
+    pub contract: Box<Account<'info, Contract>>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    /// CHECK: Validated by contract
+    pub freelancer: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Paid what the freelancer is owed, since the old contract closes here
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives any late penalty and funds the renewal
+    #[account(mut)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        init,
+        payer = client,
+        space = 8 + Contract::INIT_SPACE,
+        seeds = [b"contract", &renewal_id.to_le_bytes()],
+        bump
+    )]
+    pub renewal: Box<Account<'info, Contract>>,
+    
+    #[account(
+        init,
+        payer = client,
+        seeds = [b"vault".as_ref(), &renewal_id.to_le_bytes()],
+        bump,
+        token::mint = token_mint,
+        token::authority = renewal,
+        token::token_program = token_program
+    )]
+    pub renewal_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Blocklist entry for the client; must not exist
+    #[account(
+        seeds = [b"blocked", client.key().as_ref()],
+        bump,
+        constraint = client_block.data_is_empty() @ PayGuardError::AddressBlocked
+    )]
+    pub client_block: UncheckedAccount<'info>,
+    
+    /// CHECK: Blocklist entry for the freelancer; must not exist
+    #[account(
+        seeds = [b"blocked", freelancer.key().as_ref()],
+        bump,
+        constraint = freelancer_block.data_is_empty() @ PayGuardError::AddressBlocked
+    )]
+    pub freelancer_block: UncheckedAccount<'info>,
+    
+    /// CHECK: Client's KYC attestation, validated when the terms require KYC
+    #[account(seeds = [b"kyc", client.key().as_ref()], bump)]
+    pub client_kyc: UncheckedAccount<'info>,
+    
+    /// CHECK: Freelancer's KYC attestation, validated when the terms require KYC
+    #[account(seeds = [b"kyc", freelancer.key().as_ref()], bump)]
+    pub freelancer_kyc: UncheckedAccount<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestoneSwap<'info> {
+    #[account(mut, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    pub freelancer: Signer<'info>,
+    
+    #[account(mut, seeds = [b"sol_vault", contract.key().as_ref()], bump)]
+    pub sol_vault: SystemAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized,
+        constraint = freelancer_token_account.mint == contract.token_mint @ PayGuardError::InvalidVault
+    )]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Must be the swap program recorded in the contract terms
+    #[account(
+        executable,
+        constraint = contract.terms.swap_program == Some(swap_program.key()) @ PayGuardError::NotSolEscrow
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestoneNative<'info> {
+    #[account(mut, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    #[account(mut)]
+    pub freelancer: SystemAccount<'info>,
+    
+    #[account(mut, seeds = [b"sol_vault", contract.key().as_ref()], bump)]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct SettleHeldRelease<'info> {
+    #[account(mut, has_one = token_mint)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Freelancer claiming, or arbitrator approving a clawback
+    pub authority: Signer<'info>,
+    
+    /// CHECK: The contract's client; signs a clawback and receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFunds<'info> {
+    #[account(mut, has_one = token_mint, has_one = freelancer, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    pub freelancer: Signer<'info>,
+    
+    /// CHECK: Validated by contract; receives the vault's rent when a finished contract is paid out
+    #[account(mut)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeContract<'info> {
+    #[account(mut, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddTip<'info> {
+    #[account(mut, has_one = token_mint, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    #[account(mut)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Required only when tips carry the platform fee
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct RejectMilestone<'info> {
+    #[account(mut, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireRejectedMilestone<'info> {
+    #[account(mut, has_one = token_mint)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Anyone may expire a rejection once its windows have passed
+    pub payer: Signer<'info>,
+    
+    /// CHECK: The contract's client; receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRejectedRefund<'info> {
+    #[account(mut, has_one = token_mint, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Receives the vault's rent on completion
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    #[account(mut, constraint = contract.client == *authority.key || contract.freelancer == *authority.key)]
+    pub contract: Account<'info, Contract>,
+    
+    pub authority: Signer<'info>,
+    
+    /// The bond accounts below are required only when the contract sets a
+    /// `dispute_bond`; the raiser pays it from this account
+    #[account(mut)]
+    pub raiser_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, address = contract.escrow_vault @ PayGuardError::InvalidVault)]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = contract.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AppealResolution<'info> {
+    #[account(mut, constraint = contract.client == *authority.key || contract.freelancer == *authority.key)]
+    pub contract: Account<'info, Contract>,
+    
+    pub authority: Signer<'info>,
+    
+    /// The bond accounts below are required only when the terms name a
+    /// `senior_arbitrator`; the appellant pays `appeal_bond` from this account
+    #[account(mut)]
+    pub appellant_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, address = contract.escrow_vault @ PayGuardError::InvalidVault)]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = contract.token_mint)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveAppeal<'info> {
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = contract.terms.senior_arbitrator == Some(senior_arbitrator.key()) @ PayGuardError::Unauthorized
+    )]
+    pub contract: Account<'info, Contract>,
+    
+    pub senior_arbitrator: Signer<'info>,
+    
+    /// CHECK: The contract's client; receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = restitution_token_account.owner == contract.restitution_treasury @ PayGuardError::InvalidRestitutionAccount,
+        constraint = restitution_token_account.mint == contract.token_mint @ PayGuardError::InvalidRestitutionAccount
+    )]
+    pub restitution_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut, has_one = token_mint, constraint = contract.is_resolver(arbitrator.key) @ PayGuardError::Unauthorized)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Resolver required by the dispute type: the contract arbitrator for
+    /// technical disputes, the scope resolver for scope disputes
+    pub arbitrator: Signer<'info>,
+    
+    /// CHECK: The contract's client; receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Required only when a FavorFreelancer decision releases a bonus
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// Required only when a platform fee is due
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Required only for a Forfeit decision
+    #[account(
+        mut,
+        constraint = restitution_token_account.owner == contract.restitution_treasury @ PayGuardError::InvalidRestitutionAccount,
+        constraint = restitution_token_account.mint == contract.token_mint @ PayGuardError::InvalidRestitutionAccount
+    )]
+    pub restitution_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
     
-    /// CHECK: Authorized arbitrator for dispute resolution
-    /// This should be PayGuard's oracle or a trusted third party
-    pub arbitrator: AccountInfo<'info>,
+    /// Required only when the contract pays an arbitrator fee
+    #[account(
+        mut,
+        constraint = arbitrator_token_account.owner == arbitrator.key() @ PayGuardError::InvalidArbitratorAccount,
+        constraint = arbitrator_token_account.mint == contract.token_mint @ PayGuardError::InvalidArbitratorAccount
+    )]
+    pub arbitrator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: The resolver's registry entry, updated if they are registered
+    #[account(mut, seeds = [b"arbitrator", arbitrator.key().as_ref()], bump)]
+    pub arbitrator_profile: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GovernanceResolve<'info> {
+    #[account(mut, has_one = token_mint)]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(seeds = [b"config"], bump = config.bump, has_one = governance @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    
+    pub governance: Signer<'info>,
+    
+    /// CHECK: The contract's client; receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = restitution_token_account.owner == contract.restitution_treasury @ PayGuardError::InvalidRestitutionAccount,
+        constraint = restitution_token_account.mint == contract.token_mint @ PayGuardError::InvalidRestitutionAccount
+    )]
+    pub restitution_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleResolution<'info> {
+    #[account(mut, has_one = token_mint)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Anyone may settle once the finality window has passed
+    pub payer: Signer<'info>,
+    
+    /// CHECK: The contract's client; receives the vault's rent on completion
+    #[account(mut, address = contract.client @ PayGuardError::Unauthorized)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = freelancer_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized)]
+    pub freelancer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub bonus_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = freelancer_bonus_token_account.owner == contract.freelancer @ PayGuardError::Unauthorized
+    )]
+    pub freelancer_bonus_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub bonus_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = fee_treasury_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = fee_treasury_token_account.mint == contract.token_mint @ PayGuardError::InvalidFeeAccount
+    )]
+    pub fee_treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = restitution_token_account.owner == contract.restitution_treasury @ PayGuardError::InvalidRestitutionAccount,
+        constraint = restitution_token_account.mint == contract.token_mint @ PayGuardError::InvalidRestitutionAccount
+    )]
+    pub restitution_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct PauseContract<'info> {
+    #[account(mut, constraint = contract.client == *authority.key || contract.freelancer == *authority.key @ PayGuardError::Unauthorized)]
+    pub contract: Account<'info, Contract>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveCancellation<'info> {
+    #[account(mut)]
+    pub contract: Account<'info, Contract>,
+    
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelContractSol<'info> {
+    #[account(mut, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    /// CHECK: The contract's freelancer; must also sign once they have accepted
+    pub freelancer: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"sol_vault", contract.key().as_ref()], bump)]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FreelancerWithdrawSol<'info> {
+    #[account(mut, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub freelancer: Signer<'info>,
+    
+    /// CHECK: The contract's client, who receives the refund
+    #[account(mut)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"sol_vault", contract.key().as_ref()], bump)]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelExpiredSol<'info> {
+    #[account(mut, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Anyone may cancel once the deadline has passed
+    pub caller: Signer<'info>,
+    
+    /// CHECK: The contract's client, who receives the refund
+    #[account(mut)]
+    pub client: UncheckedAccount<'info>,
+    
+    #[account(mut, seeds = [b"sol_vault", contract.key().as_ref()], bump)]
+    pub sol_vault: SystemAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelContract<'info> {
+    #[account(mut, has_one = token_mint, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    /// CHECK: The contract's freelancer; must also sign once they have accepted
+    pub freelancer: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the refund: the sponsor's token account on sponsored contracts
+    #[account(mut, constraint = client_token_account.owner == contract.funder() @ PayGuardError::InvalidRefundAccount)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelExpired<'info> {
+    #[account(mut, has_one = token_mint)]
+    pub contract: Account<'info, Contract>,
+    
+    /// Anyone may cancel once the deadline has passed
+    pub caller: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the refund: the sponsor's token account on sponsored contracts
+    #[account(mut, constraint = client_token_account.owner == contract.funder() @ PayGuardError::InvalidRefundAccount)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FreelancerWithdraw<'info> {
+    #[account(mut, has_one = token_mint, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    pub freelancer: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the refund: the sponsor's token account on sponsored contracts
+    #[account(mut, constraint = client_token_account.owner == contract.funder() @ PayGuardError::InvalidRefundAccount)]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeMilestone<'info> {
+    #[account(mut, has_one = token_mint, has_one = client, has_one = freelancer)]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    pub freelancer: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = contract.accepts_vault(&escrow_vault.key()) @ PayGuardError::InvalidVault,
+        constraint = escrow_vault.owner == contract.key() @ PayGuardError::InvalidVaultOwner,
+        constraint = escrow_vault.mint == contract.token_mint @ PayGuardError::InvalidVaultMint
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = client_token_account.owner == contract.client @ PayGuardError::Unauthorized,
+        constraint = client_token_account.mint == contract.token_mint @ PayGuardError::InvalidVault
+    )]
+    pub client_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ViewContract<'info> {
+    pub contract: Account<'info, Contract>,
+}
+
+#[derive(Accounts)]
+pub struct FundBonus<'info> {
+    #[account(mut, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    #[account(mut)]
+    pub client_bonus_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub bonus_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = contract.terms.bonus_mint == Some(bonus_mint.key()) @ PayGuardError::InvalidBonus)]
+    pub bonus_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimBonus<'info> {
+    #[account(has_one = client, has_one = bonus_vault)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    #[account(mut)]
+    pub bonus_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub client_bonus_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = contract.terms.bonus_mint == Some(bonus_mint.key()) @ PayGuardError::InvalidBonus)]
+    pub bonus_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseContract<'info> {
+    #[account(mut, close = client, has_one = client)]
+    pub contract: Account<'info, Contract>,
+    
+    #[account(mut)]
+    pub client: Signer<'info>,
+    
+    /// CHECK: Must be the contract's vault; closed, or still open but empty
+    #[account(mut)]
+    pub escrow_vault: UncheckedAccount<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseContracts<'info> {
+    /// Anyone may sweep finished contracts; rent always returns to each client
+    pub operator: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(mut, constraint = treasury_token_account.owner == config.key() @ PayGuardError::InvalidFeeAccount)]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = treasury_token_account.mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeRecipient<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+    
+    /// Existing contract to move to the new recipient
+    #[account(mut)]
+    pub contract: Option<Account<'info, Contract>>,
+}
+
+#[derive(Accounts)]
+pub struct CollectTokenFee<'info> {
+    #[account(mut, has_one = client, has_one = token_mint)]
+    pub contract: Account<'info, Contract>,
+    
+    pub client: Signer<'info>,
+    
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub fee_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, constraint = client_fee_token_account.mint == fee_mint.key() @ PayGuardError::InvalidFeeAccount)]
+    pub client_fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury_fee_token_account.owner == contract.fee_treasury @ PayGuardError::InvalidFeeAccount,
+        constraint = treasury_fee_token_account.mint == fee_mint.key() @ PayGuardError::InvalidFeeAccount
+    )]
+    pub treasury_fee_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Pyth price account for the payment token, checked against the fee token settings
+    pub payment_price_feed: UncheckedAccount<'info>,
+    
+    /// CHECK: Pyth price account for the fee token, checked against the fee token settings
+    pub fee_price_feed: UncheckedAccount<'info>,
     
-    /// CHECK: Token mint for payment
-    pub token_mint: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     
-    pub system_program: Program<'info, System>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
 }
 
 #[derive(Accounts)]
-pub struct FundEscrow<'info> {
-    #[account(mut, has_one = client)]
-    pub contract: Account<'info, Contract>,
+#[instruction(address: Pubkey)]
+pub struct BlockAddress<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
     
     #[account(mut)]
-    pub client: Signer<'info>,
-    
-    #[account(mut)]
-    pub client_token_account: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
     
-    #[account(mut)]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + BlockedAddress::INIT_SPACE,
+        seeds = [b"blocked", address.as_ref()],
+        bump
+    )]
+    pub blocked: Account<'info, BlockedAddress>,
     
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SubmitMilestone<'info> {
-    #[account(mut, has_one = freelancer)]
-    pub contract: Account<'info, Contract>,
+#[instruction(address: Pubkey)]
+pub struct UnblockAddress<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
     
-    pub freelancer: Signer<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(mut, close = admin, seeds = [b"blocked", address.as_ref()], bump = blocked.bump)]
+    pub blocked: Account<'info, BlockedAddress>,
 }
 
 #[derive(Accounts)]
-pub struct ApproveMilestone<'info> {
-    #[account(mut, has_one = client, has_one = freelancer)]
-    pub contract: Account<'info, Contract>,
+#[instruction(arbitrator: Pubkey)]
+pub struct RegisterArbitrator<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
     
-    pub client: Signer<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
     
-    /// CHECK: Validated by contract
-    pub freelancer: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ArbitratorProfile::INIT_SPACE,
+        seeds = [b"arbitrator", arbitrator.as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, ArbitratorProfile>,
     
-    #[account(mut)]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct IssueKycAttestation<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = kyc_authority @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
     
     #[account(mut)]
-    pub freelancer_token_account: Account<'info, TokenAccount>,
+    pub kyc_authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = kyc_authority,
+        space = 8 + KycAttestation::INIT_SPACE,
+        seeds = [b"kyc", subject.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, KycAttestation>,
     
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RaiseDispute<'info> {
-    #[account(mut, constraint = contract.client == *authority.key || contract.freelancer == *authority.key)]
+pub struct ProposeVaultMigration<'info> {
+    #[account(mut)]
     pub contract: Account<'info, Contract>,
     
-    pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ResolveDispute<'info> {
-    #[account(mut)]
+pub struct MigrateVault<'info> {
+    #[account(mut, has_one = token_mint)]
     pub contract: Account<'info, Contract>,
     
-    /// Arbitration oracle/authority - MUST be the authorized arbitrator
-    #[account(
-        constraint = arbitrator.key() == contract.arbitrator @ PayGuardError::Unauthorized
-    )]
-    pub arbitrator: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ PayGuardError::Unauthorized)]
+    pub config: Account<'info, Config>,
     
-    #[account(mut)]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
     
+    /// CHECK: The contract's current vault; may be closed or frozen, so it is
+    /// only deserialized when it is still a live token account
     #[account(mut)]
-    pub freelancer_token_account: Account<'info, TokenAccount>,
+    pub old_vault: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub client_token_account: Account<'info, TokenAccount>,
+    pub new_vault: InterfaceAccount<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct CancelContract<'info> {
-    #[account(mut, has_one = client)]
+pub struct ReconcileAccounting<'info> {
+    #[account(mut)]
     pub contract: Account<'info, Contract>,
     
-    pub client: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
     
-    #[account(mut)]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    #[account(constraint = authority.key() == config.admin || authority.key() == contract.arbitrator @ PayGuardError::Unauthorized)]
+    pub authority: Signer<'info>,
     
-    #[account(mut)]
-    pub client_token_account: Account<'info, TokenAccount>,
+    #[account(address = contract.escrow_vault @ PayGuardError::InvalidVault)]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CheckInvariants<'info> {
+    pub contract: Account<'info, Contract>,
     
-    pub token_program: Program<'info, Token>,
+    #[account(address = contract.escrow_vault @ PayGuardError::InvalidVault)]
+    pub escrow_vault: Option<InterfaceAccount<'info, TokenAccount>>,
 }
 
 // ============ STATE ============
@@ -394,26 +5127,774 @@ pub struct Contract {
     pub token_mint: Pubkey,
     /// Authorized arbitrator for dispute resolution
     pub arbitrator: Pubkey,
+    /// Kept ahead of any variable-length field so it sits at
+    /// `DESCRIPTION_HASH_OFFSET` for memcmp lookups
+    pub description_hash: [u8; 32],
+    /// Bit `i` is set once milestone `i` is settled; at `MILESTONE_BITMAP_OFFSET`
+    pub milestone_status_bitmap: u32,
     pub total_amount: u64,
+    /// Milestone value settled so far, whether paid out or returned to the client
     pub released_amount: u64,
-    #[max_len(10)]
+    /// Portion of `released_amount` returned to the client (penalties, split shares)
+    pub refunded_amount: u64,
+    /// Tokens actually received by the vault, net of transfer fees
+    pub funded_amount: u64,
+    /// Token account holding the escrow: the PDA vault `create_contract`
+    /// creates, or the one an admin later migrates the contract to
+    pub escrow_vault: Pubkey,
+    /// Vault awaiting an admin migration, if any
+    pub pending_vault: Option<Pubkey>,
+    /// Earliest time the pending vault migration may execute
+    pub vault_migration_eta: i64,
+    /// Retainage withheld from releases, paid out at completion
+    pub retainage_held: u64,
+    /// Token account holding bonus tokens of `terms.bonus_mint`
+    pub bonus_vault: Pubkey,
+    /// Sum of all milestone bonuses
+    pub bonus_total: u64,
+    /// Bonus tokens paid out so far
+    pub bonus_released: u64,
+    /// Owner of the token account receiving platform fees
+    pub fee_treasury: Pubkey,
+    /// Platform fees paid out so far, valued in the payment token
+    pub fees_collected: u64,
+    /// Protocol token fees are charged in, if not the payment token
+    pub fee_token: Option<FeeToken>,
+    /// Fees accrued in payment token value awaiting `collect_token_fee`
+    pub fees_owed: u64,
+    /// Cancel approvers who have agreed to cancel so far
+    #[max_len(MAX_CANCEL_APPROVERS)]
+    pub cancel_approvals: Vec<Pubkey>,
+    /// Party awaiting the other's approval of a pause
+    pub pause_requested_by: Option<Pubkey>,
+    /// Length of the requested pause, in seconds
+    pub pause_duration: i64,
+    /// Submissions and approvals are blocked until this time
+    pub paused_until: i64,
+    /// Approved payouts still held in the vault during their clawback windows
+    pub held_total: u64,
+    /// Approved payouts the freelancer has yet to claim with `withdraw_funds`
+    pub approved_unclaimed: u64,
+    /// Owner of the account receiving forfeited milestones
+    pub restitution_treasury: Pubkey,
+    /// Most recent dispute decisions, oldest first, capped at `MAX_DISPUTE_LOG`
+    #[max_len(MAX_DISPUTE_LOG)]
+    pub dispute_log: Vec<DisputeRecord>,
+    pub terms: ContractTerms,
+    /// Sized for `MAX_INITIAL_MILESTONES`; `add_milestones` reallocates for more
+    #[max_len(MAX_INITIAL_MILESTONES)]
     pub milestones: Vec<Milestone>,
-    pub description_hash: [u8; 32],
     pub status: ContractStatus,
     pub created_at: i64,
     pub bump: u8,
 }
 
+impl Contract {
+    /// Account size, discriminator included, for a contract holding `milestones` milestones
+    pub fn space_for(milestones: usize) -> usize {
+        8 + Self::INIT_SPACE + milestones.saturating_sub(MAX_INITIAL_MILESTONES) * Milestone::INIT_SPACE
+    }
+    
+    /// Record the decision on milestone `index`'s open dispute
+    pub fn log_dispute(&mut self, index: u8, decision: DisputeDecision, now: i64) {
+        let milestone = &self.milestones[index as usize];
+        let record = DisputeRecord {
+            milestone_index: index,
+            raised_by: milestone.disputed_by.unwrap_or_default(),
+            reason_hash: milestone.dispute_reason.unwrap_or_default(),
+            decision,
+            resolved_at: now,
+        };
+        if self.dispute_log.len() == MAX_DISPUTE_LOG {
+            self.dispute_log.remove(0);
+        }
+        self.dispute_log.push(record);
+    }
+    
+    /// Milestone value settled by approval, resolution, clawback, forfeit or
+    /// refund; all of it must be counted in `released_amount`
+    pub fn settled_amount(&self) -> Result<u64> {
+        checked_sum(
+            self.milestones
+                .iter()
+                .filter(|m| {
+                    matches!(
+                        m.status,
+                        MilestoneStatus::Approved
+                            | MilestoneStatus::Resolved
+                            | MilestoneStatus::ClawedBack
+                            | MilestoneStatus::Forfeited
+                            | MilestoneStatus::Refunded
+                    )
+                })
+                .map(|m| m.amount),
+        )
+    }
+    
+    /// Whether `terms.deadline` is set and has passed
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.terms.deadline > 0 && now > self.terms.deadline
+    }
+    
+    /// Whether an agreed pause is still in effect
+    pub fn is_paused(&self, now: i64) -> bool {
+        now < self.paused_until
+    }
+    
+    /// Whether the contract has reached a terminal state and can be closed
+    pub fn is_closeable(&self) -> bool {
+        matches!(self.status, ContractStatus::Completed | ContractStatus::Cancelled)
+    }
+    
+    /// Fail unless the contract is terminal, owes no fees, has nothing left for
+    /// the freelancer to withdraw and accounts for every released token with a
+    /// settled milestone
+    pub fn check_closeable(&self) -> Result<()> {
+        require!(self.is_closeable(), PayGuardError::ContractNotClosable);
+        require!(self.fees_owed == 0, PayGuardError::ContractNotClosable);
+        require!(self.approved_unclaimed == 0, PayGuardError::ContractNotClosable);
+        require!(self.released_amount == self.settled_amount()?, PayGuardError::ContractNotClosable);
+        Ok(())
+    }
+    
+    /// Whether any milestone dispute is waiting for a decision
+    pub fn has_open_dispute(&self) -> bool {
+        self.milestones
+            .iter()
+            .any(|m| matches!(m.status, MilestoneStatus::Disputed | MilestoneStatus::Appealed))
+    }
+    
+    /// Mark a running contract `Disputed` while any milestone dispute is open,
+    /// and `Active` again once the last one is decided
+    pub fn refresh_dispute_status(&mut self) {
+        if matches!(self.status, ContractStatus::Active | ContractStatus::Disputed) {
+            self.status = if self.has_open_dispute() {
+                ContractStatus::Disputed
+            } else {
+                ContractStatus::Active
+            };
+        }
+    }
+    
+    /// Tokens the vault holds on the contract's behalf: milestone value not yet
+    /// released, payouts credited or held for the freelancer, retainage and bonds
+    pub fn vault_obligations(&self) -> Result<u64> {
+        let unreleased = if self.status == ContractStatus::Cancelled {
+            0
+        } else {
+            self.total_amount
+                .checked_sub(self.released_amount)
+                .ok_or(PayGuardError::InvariantReleasedExceedsTotal)?
+        };
+        checked_sum(
+            [
+                unreleased,
+                self.approved_unclaimed,
+                self.held_total,
+                self.retainage_held,
+                self.bonds_held()?,
+            ]
+            .into_iter(),
+        )
+    }
+    
+    /// Dispute and appeal bonds sitting in the vault until their disputes are decided
+    pub fn bonds_held(&self) -> Result<u64> {
+        checked_sum(self.milestones.iter().flat_map(|m| [m.dispute_bond, m.appeal_bond]))
+    }
+    
+    /// Whether the escrow holds lamports in the SOL vault rather than tokens
+    pub fn escrows_sol(&self) -> bool {
+        self.terms.swap_program.is_some() || self.terms.is_native
+    }
+    
+    /// Whether `vault` is this contract's escrow: the vault created with it,
+    /// or the one an admin migrated it to
+    pub fn accepts_vault(&self, vault: &Pubkey) -> bool {
+        self.escrow_vault == *vault
+    }
+    
+    /// Whether `signer` is one of the resolvers named at creation; each dispute
+    /// further pins the one its type requires
+    pub fn is_resolver(&self, signer: &Pubkey) -> bool {
+        *signer == self.arbitrator
+            || self.terms.scope_resolver == Some(*signer)
+            || self.terms.arbitrators.contains(signer)
+    }
+    
+    /// Whether `signer` may fund or top up the escrow with `fund_escrow`
+    pub fn may_fund(&self, signer: &Pubkey) -> bool {
+        *signer == self.client || self.terms.funding_delegate == Some(*signer)
+    }
+    
+    /// Who funds the escrow and is refunded on cancellation
+    pub fn funder(&self) -> Pubkey {
+        self.terms.sponsor.unwrap_or(self.client)
+    }
+    
+    /// Take the platform fee off a release, returning `(fee, net)`
+    ///
+    /// The fee is floored so rounding always favors the freelancer, and
+    /// `fee + net == amount` exactly. When fees are charged in a separate fee
+    /// token the release is left whole and the fee accrues to the client instead.
+    pub fn take_fee(&mut self, amount: u64) -> (u64, u64) {
+        let fee = self.fee_on(amount);
+        if self.fee_token.is_some() {
+            self.fees_owed += fee;
+            return (0, amount);
+        }
+        self.fees_collected += fee;
+        (fee, amount - fee)
+    }
+    
+    /// Recompute the settled-milestone bitmap from the milestone vector
+    pub fn refresh_status_bitmap(&mut self) {
+        self.milestone_status_bitmap = self
+            .milestones
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.is_settled())
+            .fold(0, |bits, (i, _)| bits | 1 << i);
+    }
+    
+    /// Whether every milestone is settled and no payout is still held for clawback
+    pub fn is_fully_released(&self) -> bool {
+        self.released_amount == self.total_amount && self.held_total == 0
+    }
+    
+    /// Whether the escrow holds enough to settle another `amount` of milestone value
+    pub fn covers(&self, amount: u64) -> bool {
+        self.funded_amount >= self.released_amount + amount
+    }
+    
+    /// Withhold retainage from a release, returning the amount to pay out now
+    pub fn withhold_retainage(&mut self, amount: u64) -> u64 {
+        let retained = self.retainage_on(amount);
+        self.retainage_held += retained;
+        amount - retained
+    }
+    
+    /// Credit a milestone's released payout to the freelancer
+    ///
+    /// A vesting milestone holds the payout for `claim_vested`, and a clawback
+    /// window holds it for `claim_release`; otherwise it waits in the vault for
+    /// `withdraw_funds`.
+    pub fn credit_payout(&mut self, milestone_index: u8, payout: u64, now: i64) -> Result<()> {
+        let clawback_window = self.terms.clawback_window;
+        let milestone = &mut self.milestones[milestone_index as usize];
+        milestone.approved_at = Some(now);
+        if milestone.vesting_secs.is_some() {
+            // Vesting replaces any clawback hold
+            milestone.held_payout = payout;
+            milestone.vested_claimed = 0;
+        } else if clawback_window > 0 {
+            milestone.held_payout = payout;
+            milestone.claimable_at = Some(now.checked_add(clawback_window).ok_or(PayGuardError::Overflow)?);
+        } else {
+            self.approved_unclaimed = self.approved_unclaimed.checked_add(payout).ok_or(PayGuardError::Overflow)?;
+            return Ok(());
+        }
+        self.held_total = self.held_total.checked_add(payout).ok_or(PayGuardError::Overflow)?;
+        Ok(())
+    }
+    
+    /// Platform fee on a release of `amount`, floored
+    pub fn fee_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.terms.fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+    
+    /// Arbitrator fee on a disputed milestone of `amount`, floored
+    pub fn arbitrator_fee_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.terms.arbitrator_fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+    
+    /// Retainage withheld from a payout of `amount`, floored
+    pub fn retainage_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.terms.retainage_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+    
+    /// What the freelancer nets from approval of `milestone`, by the same
+    /// arithmetic as the release itself
+    pub fn estimate_payout(&self, milestone: &Milestone) -> PayoutEstimate {
+        let late_penalty = milestone.late_penalty();
+        let gross = milestone.amount - late_penalty;
+        // A fee charged in the fee token is the client's to pay
+        let fee = if self.fee_token.is_some() { 0 } else { self.fee_on(gross) };
+        let retainage = self.retainage_on(gross - fee);
+        PayoutEstimate {
+            amount: milestone.amount,
+            late_penalty,
+            fee,
+            retainage,
+            payout: gross - fee - retainage,
+            bonus: milestone.bonus_amount,
+        }
+    }
+}
+
+/// Protocol-wide configuration, a singleton PDA
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    /// Operator authority for administrative instructions
+    pub admin: Pubkey,
+    /// Delay imposed on sensitive admin actions such as vault migration
+    pub timelock_secs: i64,
+    /// Fewest milestones a new contract may have; raise to forbid lump sums
+    pub min_milestones: u8,
+    /// Owner of the fee token accounts recorded on new contracts
+    pub fee_treasury: Pubkey,
+    /// Protocol token new contracts pay fees in, if not the payment token
+    pub fee_token: Option<FeeToken>,
+    /// Issuer of the KYC attestations accepted for `require_kyc` contracts
+    pub kyc_authority: Pubkey,
+    pub bump: u8,
+    /// Seconds a registered arbitrator has to resolve a dispute; 0 disables the SLA
+    pub arbitrator_sla_secs: i64,
+    /// Owner of the account new contracts send forfeited milestones to
+    pub restitution_treasury: Pubkey,
+    /// Authority of last resort for disputes left unresolved (e.g. a DAO multisig)
+    pub governance: Pubkey,
+    /// Seconds a dispute must stay open before governance may resolve it
+    pub governance_min_staleness: i64,
+    /// Emergency stop on funding, approvals and dispute resolutions;
+    /// cancellations and refunds still go through
+    pub paused: bool,
+    /// Payment mints new contracts may use; empty allows any mint
+    #[max_len(MAX_ALLOWED_MINTS)]
+    pub allowed_mints: Vec<Pubkey>,
+}
+
+/// Blocklist entry; its existence bars the address from new contracts
+#[account]
+#[derive(InitSpace)]
+pub struct BlockedAddress {
+    pub address: Pubkey,
+    pub blocked_at: i64,
+    pub bump: u8,
+}
+
+/// KYC attestation for an address, issued by the config's KYC authority
+#[account]
+#[derive(InitSpace)]
+pub struct KycAttestation {
+    pub subject: Pubkey,
+    pub authority: Pubkey,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+/// Arbitrator registry entry tracking resolution times against the SLA
+///
+/// `reputation_bps` is the weight off-chain arbitrator selection should use.
+#[account]
+#[derive(InitSpace)]
+pub struct ArbitratorProfile {
+    pub arbitrator: Pubkey,
+    pub resolved_count: u32,
+    pub sla_met_count: u32,
+    pub total_resolution_secs: u64,
+    pub reputation_bps: u16,
+    pub bump: u8,
+}
+
+impl ArbitratorProfile {
+    /// Count a resolution, rewarding it if within the SLA and penalising it if not
+    pub fn record_resolution(&mut self, elapsed_secs: i64, sla_secs: i64) {
+        self.resolved_count += 1;
+        self.total_resolution_secs += elapsed_secs.max(0) as u64;
+        if sla_secs == 0 {
+            return;
+        }
+        if elapsed_secs <= sla_secs {
+            self.sla_met_count += 1;
+            self.reputation_bps = (self.reputation_bps + SLA_MET_REPUTATION_BPS).min(INITIAL_REPUTATION_BPS);
+        } else {
+            self.reputation_bps = self.reputation_bps.saturating_sub(SLA_MISSED_REPUTATION_BPS);
+        }
+    }
+}
+
+/// Protocol token the platform fee is paid in, priced through Pyth
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct FeeToken {
+    pub mint: Pubkey,
+    /// Pyth price account for the contract's payment token
+    pub payment_price_feed: Pubkey,
+    /// Pyth price account for the fee token
+    pub fee_price_feed: Pubkey,
+    /// Oldest price accepted for the conversion, in seconds
+    pub max_price_age_secs: u64,
+}
+
+/// Commercial terms agreed at contract creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct ContractTerms {
+    /// Share of each release held back until final completion, in basis points
+    pub retainage_bps: u16,
+    /// Platform fee taken from each release to the freelancer, in basis points
+    pub fee_bps: u16,
+    /// Client-side manager who resolves scope disputes
+    pub scope_resolver: Option<Pubkey>,
+    /// Program notified via CPI when the contract completes; completion
+    /// requires it and fails if the callback fails
+    pub completion_callback_program: Option<Pubkey>,
+    /// Secondary mint used to pay milestone bonuses from a separate vault
+    pub bonus_mint: Option<Pubkey>,
+    /// Seconds the freelancer has to contest a rejection by raising a dispute
+    pub rejection_contest_window: i64,
+    /// Whether tips carry the platform fee; by default they go to the freelancer in full
+    pub fee_on_tips: bool,
+    /// Seconds a dispute decision waits, open to appeal, before it can be settled
+    pub resolution_finality_window: i64,
+    /// Parties (e.g. client and guarantor) whose approval can cancel the contract
+    #[max_len(MAX_CANCEL_APPROVERS)]
+    pub cancel_approvers: Vec<Pubkey>,
+    /// Approvals required before `cancel_contract` runs; 0 lets the client cancel alone
+    pub cancel_threshold: u8,
+    /// Both parties must hold a valid KYC attestation to create or activate the contract
+    pub require_kyc: bool,
+    /// Let anyone flag pending milestones past their due date, halting them until explained
+    pub flag_missed_deadlines: bool,
+    /// Swap program converting the SOL escrow into `token_mint` on release; when
+    /// set, amounts are in lamports and the contract is funded in SOL
+    pub swap_program: Option<Pubkey>,
+    /// Seconds the resolver has to decide each dispute; 0 sets no deadline
+    pub dispute_resolution_window: i64,
+    /// Seconds an approved payout stays in escrow, reversible by the client with
+    /// arbitrator approval; 0 makes releases final
+    pub clawback_window: i64,
+    /// Milestones escalate: each amount must be at least the previous one's
+    pub sequential: bool,
+    /// Third party funding the escrow in the client's place; cancellation
+    /// refunds go to the sponsor
+    pub sponsor: Option<Pubkey>,
+    /// Seconds the freelancer has to resubmit a rejected milestone before anyone
+    /// can refund it to the client; 0 leaves rejected funds in escrow
+    pub resubmit_window: i64,
+    /// Milestone amounts are given in basis points of the total and normalized
+    /// to token amounts on creation, the rounding remainder going to the last
+    pub amounts_in_bps: bool,
+    /// Wallet allowed to fund and top up the escrow besides the client, e.g. a
+    /// custody treasury; refunds still go to the client
+    pub funding_delegate: Option<Pubkey>,
+    /// Fully paid contracts wait for the client's `finalize_contract` instead of
+    /// completing on the last release
+    pub require_sign_off: bool,
+    /// Seconds the client has to review a submission before anyone can release
+    /// it with `claim_timeout_release`; 0 waits for the client indefinitely
+    pub review_window: i64,
+    /// Escrow and pay out plain SOL; amounts are in lamports
+    pub is_native: bool,
+    /// Tokens a party stakes to raise a dispute; 0 needs no bond
+    pub dispute_bond: u64,
+    /// Panel that decides technical disputes by vote in place of `arbitrator`;
+    /// empty leaves them to `arbitrator` alone
+    #[max_len(MAX_ARBITRATORS)]
+    pub arbitrators: Vec<Pubkey>,
+    /// Matching panel votes needed before a decision executes
+    pub arbitration_threshold: u8,
+    /// Each milestone can only be submitted once the one before it is
+    /// approved or resolved
+    pub ordered: bool,
+    /// Time after which anyone may cancel the contract with `cancel_expired`;
+    /// 0 sets no deadline
+    pub deadline: i64,
+    /// Share of a disputed milestone paid to the resolver who decides it, in basis points
+    pub arbitrator_fee_bps: u16,
+    /// Second-tier arbitrator who decides appealed resolutions; `None` sends
+    /// an appeal back to the resolver
+    pub senior_arbitrator: Option<Pubkey>,
+    /// Tokens the appellant stakes to appeal to the senior arbitrator; must
+    /// exceed `dispute_bond`
+    pub appeal_bond: u64,
+    /// Gross transfers to the freelancer up by the mint's transfer fee so they
+    /// net what was released, paying the fee out of funding beyond what is owed
+    pub gross_up_transfer_fees: bool,
+}
+
+/// Freelancer's expected take from one milestone, as `estimate_net_payout` reports it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PayoutEstimate {
+    pub amount: u64,
+    pub late_penalty: u64,
+    /// Platform fee taken from the release; 0 when the client pays it in the fee token
+    pub fee: u64,
+    /// Withheld at approval and paid out on completion
+    pub retainage: u64,
+    /// Paid at approval, or held until claimable under a clawback window
+    pub payout: u64,
+    /// Paid separately from the bonus vault
+    pub bonus: u64,
+}
+
+/// Computed state of a contract, as `contract_summary` reports it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ContractSummary {
+    /// `total_amount - released_amount`
+    pub remaining: u64,
+    /// Milestones in each status, indexed in `MilestoneStatus` declaration order
+    pub status_counts: [u8; MILESTONE_STATUS_COUNT],
+    pub fully_funded: bool,
+}
+
+/// One decided dispute in a contract's `dispute_log`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct DisputeRecord {
+    pub milestone_index: u8,
+    pub raised_by: Pubkey,
+    pub reason_hash: [u8; 32],
+    pub decision: DisputeDecision,
+    pub resolved_at: i64,
+}
+
+/// Final accounting passed to a completion callback program
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompletionNotice {
+    pub contract_id: u64,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub refunded_amount: u64,
+}
+
+/// Deliverable attributes agreed for a milestone
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct DeliverableFormat {
+    /// Hash of the deliverable specification
+    pub spec_hash: [u8; 32],
+    /// Short format tag, e.g. `b"pdf"` zero-padded
+    pub tag: [u8; 8],
+}
+
+/// The parts of a milestone its parties agree on when scheduling it
+///
+/// Everything else on `Milestone` is state the program tracks, which starts
+/// out empty and is never taken from the caller.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MilestoneInput {
+    pub amount: u64,
+    pub description: String,
+    /// Submission deadline; late submissions incur `penalty_per_day_bps`
+    pub due_date: Option<i64>,
+    /// Reduction per started day late, in basis points of the milestone amount
+    pub penalty_per_day_bps: u16,
+    /// Bonus paid in `terms.bonus_mint` when the milestone is approved
+    pub bonus_amount: u64,
+    /// Minimum time the client must have to review a submission before approving
+    pub min_review_seconds: i64,
+    /// ISO 4217 code of the fiat currency frontends show this milestone in
+    pub display_currency: Option<[u8; 3]>,
+    /// Display currency per whole payment token when agreed, scaled by `DISPLAY_RATE_SCALE`
+    pub display_rate: u64,
+    /// Deliverable spec and format the submission must reference
+    pub expected_format: Option<DeliverableFormat>,
+    /// Resubmissions allowed after a rejection; 0 for no limit
+    pub max_revisions: u8,
+    /// Period over which the approved payout vests linearly; `None` releases it at once
+    pub vesting_secs: Option<i64>,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct Milestone {
     pub amount: u64,
     pub status: MilestoneStatus,
     #[max_len(100)]
     pub description: String,
-    pub proof_hash: Option<[u8; 32]>,
+    /// Hashes of the submitted deliverable artifacts, one per file
+    #[max_len(MAX_PROOF_HASHES)]
+    pub proof_hashes: Vec<[u8; 32]>,
     pub dispute_reason: Option<[u8; 32]>,
     pub arbitration_proof: Option<[u8; 32]>,
     pub submitted_at: Option<i64>,
+    /// Hash of the arbitrator's written reasoning for the decision
+    pub rationale_hash: Option<[u8; 32]>,
+    /// Submission deadline; late submissions incur `penalty_per_day_bps`
+    pub due_date: Option<i64>,
+    /// Reduction per started day late, in basis points of the milestone amount
+    pub penalty_per_day_bps: u16,
+    /// Bonus paid in `terms.bonus_mint` when the milestone is approved
+    pub bonus_amount: u64,
+    pub dispute_type: Option<DisputeType>,
+    /// Authority required to resolve the open dispute; `None` while the
+    /// arbitrator panel votes on it
+    pub dispute_resolver: Option<Pubkey>,
+    /// When the client rejected the submission, opening the contest window
+    pub rejected_at: Option<i64>,
+    /// Tips paid to the freelancer for this milestone, net of any fee
+    pub tip_amount: u64,
+    /// Resolver's decision awaiting settlement after the finality window
+    pub pending_decision: Option<DisputeDecision>,
+    /// When the pending decision was made, opening the finality window
+    pub resolved_at: Option<i64>,
+    /// Minimum time the client must have to review a submission before approving
+    pub min_review_seconds: i64,
+    /// Part of the settled amount returned to the client (late penalty or split share)
+    pub client_share: u64,
+    /// ISO 4217 code of the fiat currency frontends show this milestone in
+    pub display_currency: Option<[u8; 3]>,
+    /// Display currency per whole payment token when agreed, scaled by `DISPLAY_RATE_SCALE`
+    pub display_rate: u64,
+    /// Hash of the freelancer's explanation for missing the due date
+    pub deadline_explanation: Option<[u8; 32]>,
+    /// When the current dispute on this milestone was opened
+    pub disputed_at: Option<i64>,
+    /// When the resolver is due to decide the current dispute
+    pub dispute_deadline: Option<i64>,
+    /// Approved payout held in the vault until `claimable_at`, or vesting from
+    /// `approved_at` when `vesting_secs` is set
+    pub held_payout: u64,
+    /// When the held payout can be claimed and the clawback window closes
+    pub claimable_at: Option<i64>,
+    /// Party who opened the current dispute
+    pub disputed_by: Option<Pubkey>,
+    /// Deliverable spec and format the submission must reference
+    pub expected_format: Option<DeliverableFormat>,
+    /// Whether the latest submission came after `due_date`
+    pub late: bool,
+    /// Resubmissions allowed after a rejection; 0 for no limit
+    pub max_revisions: u8,
+    /// Resubmissions made so far
+    pub revision_count: u8,
+    /// The client's feedback on the latest rejection
+    pub feedback_hash: Option<[u8; 32]>,
+    /// Bond staked by the raiser of the open dispute, held in the vault
+    pub dispute_bond: u64,
+    /// Party who raised the dispute and staked its bond; an appeal does not
+    /// change it, unlike `disputed_by`
+    pub dispute_raised_by: Option<Pubkey>,
+    /// Panel votes on the open dispute, one per arbitrator; conflicting
+    /// decisions are counted apart
+    #[max_len(MAX_ARBITRATORS)]
+    pub arbitration_votes: Vec<ArbitrationVote>,
+    /// Period over which the approved payout vests linearly; `None` releases it at once
+    pub vesting_secs: Option<i64>,
+    /// When the client approved the milestone
+    pub approved_at: Option<i64>,
+    /// Part of the vesting payout already claimed
+    pub vested_claimed: u64,
+    /// Bond staked by the appellant of the resolution, held in the vault
+    pub appeal_bond: u64,
+    /// Party who appealed the resolution to the senior arbitrator
+    pub appealed_by: Option<Pubkey>,
+    /// Whether the resolver of the current dispute has been paid their fee;
+    /// a decision remade after an appeal is not charged again
+    pub arbitrator_fee_paid: bool,
+    /// Appeals made against decisions on the current dispute
+    pub appeal_count: u8,
+}
+
+impl From<MilestoneInput> for Milestone {
+    fn from(input: MilestoneInput) -> Self {
+        Self {
+            amount: input.amount,
+            status: MilestoneStatus::Pending,
+            description: input.description,
+            proof_hashes: Vec::new(),
+            dispute_reason: None,
+            arbitration_proof: None,
+            submitted_at: None,
+            rationale_hash: None,
+            due_date: input.due_date,
+            penalty_per_day_bps: input.penalty_per_day_bps,
+            bonus_amount: input.bonus_amount,
+            dispute_type: None,
+            dispute_resolver: None,
+            rejected_at: None,
+            tip_amount: 0,
+            pending_decision: None,
+            resolved_at: None,
+            min_review_seconds: input.min_review_seconds,
+            client_share: 0,
+            display_currency: input.display_currency,
+            display_rate: input.display_rate,
+            deadline_explanation: None,
+            disputed_at: None,
+            dispute_deadline: None,
+            held_payout: 0,
+            claimable_at: None,
+            disputed_by: None,
+            expected_format: input.expected_format,
+            late: false,
+            max_revisions: input.max_revisions,
+            revision_count: 0,
+            feedback_hash: None,
+            dispute_bond: 0,
+            dispute_raised_by: None,
+            arbitration_votes: Vec::new(),
+            vesting_secs: input.vesting_secs,
+            approved_at: None,
+            vested_claimed: 0,
+            appeal_bond: 0,
+            appealed_by: None,
+            arbitrator_fee_paid: false,
+            appeal_count: 0,
+        }
+    }
+}
+
+impl Milestone {
+    /// Start this milestone's own dispute clock; other milestones are unaffected
+    pub fn open_dispute(&mut self, now: i64, resolution_window: i64) -> Result<()> {
+        self.disputed_at = Some(now);
+        self.dispute_deadline = if resolution_window > 0 {
+            Some(now.checked_add(resolution_window).ok_or(PayGuardError::Overflow)?)
+        } else {
+            None
+        };
+        self.arbitration_votes.clear();
+        Ok(())
+    }
+    
+    /// Record the vote of the panel arbitrator in `seat`, replacing any earlier
+    /// one, and return how many votes now back `decision`
+    pub fn cast_vote(&mut self, seat: u8, decision: DisputeDecision) -> u8 {
+        self.arbitration_votes.retain(|vote| vote.seat != seat);
+        self.arbitration_votes.push(ArbitrationVote { seat, decision: decision.clone() });
+        self.arbitration_votes.iter().filter(|vote| vote.decision == decision).count() as u8
+    }
+    
+    /// Whether the milestone has reached a terminal state: paid out, split or revoked
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self.status,
+            MilestoneStatus::Approved
+                | MilestoneStatus::Resolved
+                | MilestoneStatus::Cancelled
+                | MilestoneStatus::ClawedBack
+                | MilestoneStatus::Forfeited
+                | MilestoneStatus::Refunded
+        )
+    }
+    
+    /// When the minimum review period of the current submission ends
+    pub fn review_period_end(&self) -> Result<i64> {
+        let end = self.submitted_at.unwrap_or_default().checked_add(self.min_review_seconds);
+        Ok(end.ok_or(PayGuardError::Overflow)?)
+    }
+    
+    /// Late penalty owed back to the client, capped at the milestone amount
+    pub fn late_penalty(&self) -> u64 {
+        let (Some(due_date), Some(submitted_at)) = (self.due_date, self.submitted_at) else {
+            return 0;
+        };
+        if submitted_at <= due_date {
+            return 0;
+        }
+        
+        // Every started day counts as a full day late
+        let days_late = ((submitted_at - due_date) as u128).div_ceil(SECONDS_PER_DAY as u128);
+        let penalty = self.amount as u128 * self.penalty_per_day_bps as u128 * days_late / BPS_DENOMINATOR as u128;
+        penalty.min(self.amount as u128) as u64
+    }
+    
+    /// Vested payout not yet claimed at `now`; `None` unless the milestone
+    /// vests and has been approved
+    pub fn vested_unclaimed(&self, now: i64) -> Option<u64> {
+        let vesting_secs = self.vesting_secs?;
+        let elapsed = (now - self.approved_at?).clamp(0, vesting_secs);
+        let vested = self.held_payout as u128 * elapsed as u128 / vesting_secs as u128;
+        Some(vested as u64 - self.vested_claimed)
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
@@ -422,9 +5903,15 @@ pub enum ContractStatus {
     Completed,
     Cancelled,
     Disputed,
+    /// Awaiting a freelancer-proposed milestone breakdown
+    Draft,
+    /// Fully paid out, awaiting the client's `finalize_contract` sign-off
+    PendingFinalization,
+    /// Created by the client, awaiting the freelancer's `accept_contract`
+    Pending,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
 pub enum MilestoneStatus {
     Pending,
     Submitted,
@@ -432,13 +5919,180 @@ pub enum MilestoneStatus {
     Rejected,
     Disputed,
     Resolved,
+    /// Revoked by both parties before submission; kept for history
+    Cancelled,
+    /// Decided by the resolver, awaiting settlement after the finality window
+    ResolutionPending,
+    /// Missed its due date; awaits the freelancer's explanation before submission
+    Flagged,
+    /// Approved, then reversed within the clawback window; the payout went back to the client
+    ClawedBack,
+    /// Forfeited by a dispute decision; the amount went to the restitution treasury
+    Forfeited,
+    /// Rejected and not resubmitted within the resubmission window; refunded to the client
+    Refunded,
+    /// Resolution appealed to the senior arbitrator; the payout stays frozen
+    Appealed,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum DisputeType {
+    /// Quality or correctness of the deliverable; needs an expert
+    Technical,
+    /// Whether the work is in scope; settled by the client's manager
+    Scope,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
 pub enum DisputeDecision {
     FavorFreelancer,
     FavorClient,
     Split(u8), // percentage to freelancer (0-100)
+    /// Bad faith on both sides: the milestone goes to the restitution treasury
+    Forfeit,
+}
+
+/// A panel arbitrator's vote on a milestone dispute
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ArbitrationVote {
+    /// The voter's index in `terms.arbitrators`
+    pub seat: u8,
+    pub decision: DisputeDecision,
+}
+
+// ============ EVENTS ============
+
+/// A submitted milestone is worth more than the escrow can currently cover
+#[event]
+pub struct LowEscrowWarning {
+    pub contract_id: u64,
+    pub milestone_index: u8,
+    /// Tokens the client must top up before the milestone can be approved
+    pub shortfall: u64,
+}
+
+/// Accounting totals before and after `reconcile_accounting`
+#[event]
+pub struct AccountingReconciled {
+    pub contract_id: u64,
+    pub released_before: u64,
+    pub released_after: u64,
+    pub refunded_before: u64,
+    pub refunded_after: u64,
+    pub funded_before: u64,
+    pub funded_after: u64,
+}
+
+/// The client signed off on a fully paid contract
+#[event]
+pub struct ContractFinalized {
+    pub contract_id: u64,
+    pub client: Pubkey,
+    pub finalized_at: i64,
+}
+
+/// A contract was created, or renewed into a new one
+#[event]
+pub struct ContractCreated {
+    pub contract_id: u64,
+    pub client: Pubkey,
+    pub freelancer: Pubkey,
+    pub arbitrator: Pubkey,
+    pub token_mint: Pubkey,
+    pub total_amount: u64,
+    pub created_at: i64,
+}
+
+/// Funds reached the escrow
+#[event]
+pub struct EscrowFunded {
+    pub contract_id: u64,
+    pub funder: Pubkey,
+    /// What the vault received from this deposit
+    pub amount: u64,
+    /// Cumulative funding after the deposit
+    pub funded_amount: u64,
+    pub funded_at: i64,
+}
+
+#[event]
+pub struct MilestoneSubmitted {
+    pub contract_id: u64,
+    pub milestone_index: u8,
+    pub freelancer: Pubkey,
+    pub submitted_at: i64,
+    pub late: bool,
+}
+
+/// A milestone was approved and its payout released or held
+#[event]
+pub struct MilestoneApproved {
+    pub contract_id: u64,
+    pub milestone_index: u8,
+    pub amount: u64,
+    pub fee: u64,
+    /// Net amount owed to the freelancer after fee, penalty and retainage
+    pub payout: u64,
+    pub approved_at: i64,
+}
+
+#[event]
+pub struct FundsWithdrawn {
+    pub contract_id: u64,
+    pub freelancer: Pubkey,
+    pub amount: u64,
+    pub withdrawn_at: i64,
+}
+
+#[event]
+pub struct DisputeRaised {
+    pub contract_id: u64,
+    pub milestone_index: u8,
+    pub raised_by: Pubkey,
+    pub dispute_type: DisputeType,
+    pub reason_hash: [u8; 32],
+    pub raised_at: i64,
+}
+
+/// A dispute was decided; with a finality window the payout follows at settlement
+#[event]
+pub struct DisputeResolved {
+    pub contract_id: u64,
+    pub milestone_index: u8,
+    pub resolver: Pubkey,
+    pub decision: DisputeDecision,
+    /// `None` when governance stepped in for the resolver
+    pub arbitration_proof: Option<[u8; 32]>,
+    pub resolved_at: i64,
+}
+
+/// A panel arbitrator voted on a dispute; the decision executes once
+/// `matching_votes` reaches the contract's threshold
+#[event]
+pub struct DisputeVoteCast {
+    pub contract_id: u64,
+    pub milestone_index: u8,
+    pub arbitrator: Pubkey,
+    pub decision: DisputeDecision,
+    pub matching_votes: u8,
+    pub cast_at: i64,
+}
+
+#[event]
+pub struct ContractCancelled {
+    pub contract_id: u64,
+    pub refund_recipient: Pubkey,
+    pub refunded: u64,
+    pub cancelled_at: i64,
+}
+
+#[event]
+pub struct ContractCompleted {
+    pub contract_id: u64,
+    pub released_amount: u64,
+    pub refunded_amount: u64,
+    pub fees_collected: u64,
+    pub completed_at: i64,
 }
 
 // ============ ERRORS ============
@@ -461,4 +6115,246 @@ pub enum PayGuardError {
     MilestoneNotDisputed,
     #[msg("Unauthorized")]
     Unauthorized,
+    #[msg("Retainage must not exceed 100%")]
+    InvalidRetainage,
+    #[msg("Escrow vault does not belong to this contract")]
+    InvalidVault,
+    #[msg("Timelock must not be negative")]
+    InvalidTimelock,
+    #[msg("No matching vault migration is pending")]
+    NoPendingMigration,
+    #[msg("Timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Dispute resolution requires a rationale hash")]
+    MissingRationale,
+    #[msg("Late penalty must not exceed 100% per day")]
+    InvalidPenalty,
+    #[msg("Remaining accounts are malformed")]
+    InvalidRemainingAccounts,
+    #[msg("Contract cannot be closed in its current state")]
+    ContractNotClosable,
+    #[msg("Contract has no scope resolver for scope disputes")]
+    NoScopeResolver,
+    #[msg("Submission requires at least one proof hash")]
+    MissingProof,
+    #[msg("Too many proof hashes for one submission")]
+    TooManyProofs,
+    #[msg("Bonus configuration is invalid")]
+    InvalidBonus,
+    #[msg("Bonus vault and freelancer bonus account are required")]
+    BonusAccountsMissing,
+    #[msg("Contract has fewer milestones than the protocol minimum")]
+    TooFewMilestones,
+    #[msg("Cannot revoke the last open milestone; cancel the contract instead")]
+    CannotRevokeLastMilestone,
+    #[msg("Fee must be at most 1000 basis points")]
+    InvalidFee,
+    #[msg("Fee treasury token account is required when a fee is due")]
+    FeeAccountMissing,
+    #[msg("Fee treasury token account does not match the contract")]
+    InvalidFeeAccount,
+    #[msg("Address is blocked from participating")]
+    AddressBlocked,
+    #[msg("Contract is not a draft")]
+    ContractNotDraft,
+    #[msg("The window to contest this rejection has closed")]
+    ContestWindowClosed,
+    #[msg("Milestone has not been approved")]
+    MilestoneNotApproved,
+    #[msg("Escrow has already been funded; use top_up_escrow")]
+    AlreadyFunded,
+    #[msg("Escrow has not been funded yet")]
+    EscrowNotFunded,
+    #[msg("Funding would exceed the contract total")]
+    OverFunded,
+    #[msg("Escrow does not hold enough funds to settle this milestone")]
+    EscrowUnderfunded,
+    #[msg("Milestone has no resolution awaiting settlement")]
+    NoPendingResolution,
+    #[msg("The resolution's finality window is still open")]
+    FinalityWindowOpen,
+    #[msg("The resolution's finality window has closed")]
+    FinalityWindowClosed,
+    #[msg("The minimum review period has not elapsed since submission")]
+    ReviewPeriodNotElapsed,
+    #[msg("Contract does not charge fees in a separate token")]
+    NoFeeToken,
+    #[msg("Price feed does not match the fee token settings or is invalid")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is too old")]
+    StalePrice,
+    #[msg("Cancel threshold exceeds the number of cancel approvers")]
+    InvalidCancelThreshold,
+    #[msg("Not enough cancel approvals to cancel this contract")]
+    CancelApprovalsRequired,
+    #[msg("Contract is paused")]
+    ContractPaused,
+    #[msg("No pause has been requested")]
+    NoPauseRequest,
+    #[msg("Pause duration must be positive")]
+    InvalidPauseDuration,
+    #[msg("KYC attestation is missing")]
+    KycMissing,
+    #[msg("KYC attestation was not issued by the KYC authority")]
+    InvalidKycAttestation,
+    #[msg("KYC attestation has expired")]
+    KycExpired,
+    #[msg("Display currency must be a three-letter uppercase code with a positive rate")]
+    InvalidDisplayCurrency,
+    #[msg("Contract is already completed")]
+    ContractAlreadyCompleted,
+    #[msg("Missed deadlines are not flagged on this contract")]
+    DeadlineFlaggingDisabled,
+    #[msg("Milestone has not missed its due date")]
+    DeadlineNotMissed,
+    #[msg("Milestone is not flagged for a missed deadline")]
+    MilestoneNotFlagged,
+    #[msg("SOL escrow contracts cannot carry retainage, fees or bonuses")]
+    InvalidSwapTerms,
+    #[msg("Contract does not escrow SOL")]
+    NotSolEscrow,
+    #[msg("Contract escrows SOL; use the SOL escrow instructions")]
+    SolEscrowOnly,
+    #[msg("Swap did not spend exactly the milestone amount")]
+    SwapAmountMismatch,
+    #[msg("Swap returned less than the minimum amount out")]
+    SlippageExceeded,
+    #[msg("Invariant violated: released amount exceeds the contract total")]
+    InvariantReleasedExceedsTotal,
+    #[msg("Invariant violated: milestone amounts do not sum to the contract total")]
+    InvariantMilestoneSum,
+    #[msg("Invariant violated: vault holds less than the unreleased funding")]
+    InvariantVaultBalance,
+    #[msg("Invariant violated: contract status disagrees with its milestones")]
+    InvariantStatus,
+    #[msg("Invariant violated: stored bump does not derive the contract address")]
+    InvariantBump,
+    #[msg("Clawback window must be non-negative and cannot be combined with retainage")]
+    InvalidClawbackTerms,
+    #[msg("Milestone has no payout held for clawback")]
+    NoHeldPayout,
+    #[msg("Clawback window is still open")]
+    ClawbackWindowOpen,
+    #[msg("Clawback window has closed")]
+    ClawbackWindowClosed,
+    #[msg("Missing or invalid client signature over the milestone approval")]
+    InvalidApprovalSignature,
+    #[msg("Sequential milestone amounts must be non-decreasing")]
+    NonMonotonicMilestones,
+    #[msg("Arbitrator SLA must be non-negative")]
+    InvalidArbitratorSla,
+    #[msg("Arbitrator profile is not owned by the program")]
+    InvalidArbitratorProfile,
+    #[msg("Sponsored contracts are funded by their sponsor")]
+    SponsorOnly,
+    #[msg("Refund account is not owned by the contract's funder")]
+    InvalidRefundAccount,
+    #[msg("Renewal requires the approval to complete the contract")]
+    RenewalRequiresCompletion,
+    #[msg("Restitution account is not the contract's restitution treasury")]
+    InvalidRestitutionAccount,
+    #[msg("Restitution account required for a forfeit")]
+    RestitutionAccountMissing,
+    #[msg("Resubmission window must be non-negative")]
+    InvalidResubmitWindow,
+    #[msg("Resubmission window has closed")]
+    ResubmitWindowClosed,
+    #[msg("Resubmission window is still open")]
+    ResubmitWindowOpen,
+    #[msg("Milestone is not rejected")]
+    MilestoneNotRejected,
+    #[msg("Contract has no resubmission window; rejected funds stay in escrow")]
+    NoResubmitWindow,
+    #[msg("Governance staleness must be positive")]
+    InvalidGovernanceStaleness,
+    #[msg("Dispute has not been open long enough for governance to resolve it")]
+    DisputeNotStale,
+    #[msg("Milestone basis points must sum to 10000")]
+    InvalidMilestoneBps,
+    #[msg("Escrow vault is not owned by the contract")]
+    InvalidVaultOwner,
+    #[msg("Escrow vault does not hold the contract's token")]
+    InvalidVaultMint,
+    #[msg("Split percentage must be between 0 and 100")]
+    InvalidSplit,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Submission does not reference the milestone's agreed deliverable format")]
+    FormatMismatch,
+    #[msg("Contract is not awaiting the client's sign-off")]
+    NotPendingFinalization,
+    #[msg("Contract is not awaiting the freelancer's acceptance")]
+    ContractNotPending,
+    #[msg("Milestone due dates must be in the future")]
+    InvalidDueDate,
+    #[msg("Review window must not be negative")]
+    InvalidReviewWindow,
+    #[msg("Dispute resolution window must not be negative")]
+    InvalidResolutionWindow,
+    #[msg("Contract has no review window")]
+    NoReviewWindow,
+    #[msg("The client's review window has not elapsed since submission")]
+    ReviewWindowNotElapsed,
+    #[msg("The milestone has used all of its revisions")]
+    RevisionLimitReached,
+    #[msg("Milestones can only be amended before the escrow is funded")]
+    CannotAmendFunded,
+    #[msg("Release would leave the SOL vault below rent exemption")]
+    SolVaultBelowRent,
+    #[msg("Raising a dispute on this contract requires the bond token accounts")]
+    DisputeBondAccountsMissing,
+    #[msg("A dispute bond is still held in the vault")]
+    DisputeBondHeld,
+    #[msg("Milestones cannot be submitted or approved while a dispute is open")]
+    ContractDisputed,
+    #[msg("Arbitration threshold must be between 1 and the number of distinct panel arbitrators")]
+    ThresholdNotMet,
+    #[msg("The previous milestone must be approved or resolved first")]
+    PreviousMilestoneIncomplete,
+    #[msg("No approved payout is waiting to be withdrawn")]
+    NothingToWithdraw,
+    #[msg("The client and freelancer must be different accounts")]
+    SelfDealing,
+    #[msg("Contracts and their milestones must have a nonzero amount")]
+    ZeroMilestoneAmount,
+    #[msg("The contract has no deadline or it has not passed yet")]
+    DeadlineNotReached,
+    #[msg("Funder token account does not hold the contract's mint")]
+    InvalidFunderAccount,
+    #[msg("Source and destination token accounts must differ")]
+    SameAccount,
+    #[msg("Vesting period must be positive")]
+    InvalidVesting,
+    #[msg("Arbitrator fee cannot exceed the milestone amount")]
+    InvalidArbitratorFee,
+    #[msg("Arbitrator token account required for the arbitrator fee")]
+    ArbitratorAccountMissing,
+    #[msg("Arbitrator token account must belong to the resolver and hold the contract's mint")]
+    InvalidArbitratorAccount,
+    #[msg("A senior arbitrator must be neither party, and appealing to them must cost more than the dispute bond")]
+    InvalidAppealTerms,
+    #[msg("The milestone has no appeal awaiting the senior arbitrator")]
+    MilestoneNotAppealed,
+    #[msg("Fee withdrawal must be nonzero and within the treasury balance")]
+    InvalidFeeWithdrawal,
+    #[msg("The program is paused by the admin")]
+    ProgramPaused,
+    #[msg("The token mint is not on the config's whitelist")]
+    MintNotAllowed,
+    #[msg("The mint whitelist is full")]
+    AllowedMintsFull,
+    #[msg("Freelancer token account required to pay the freelancer")]
+    FreelancerAccountMissing,
+    #[msg("The vault holds less than the retainage, payouts and bonds it owes")]
+    VaultShortfall,
+    #[msg("The contract's completion callback program must be passed to complete it")]
+    CompletionCallbackMissing,
+    #[msg("This dispute has already been appealed as often as allowed")]
+    AppealLimitReached,
+    #[msg("Renewing would strand the unpaid tokens left in the bonus vault")]
+    BonusUnreclaimed,
+    #[msg("Accounting cannot be reconciled on a contract that grosses up transfer fees")]
+    GrossUpNotReconcilable,
+    #[msg("The contract's bonus vault has already been funded")]
+    BonusAlreadyFunded,
 }
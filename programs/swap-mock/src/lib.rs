@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+declare_id!("SwapMock11111111111111111111111111111111111");
+
+/// Lamports exchanged for one base unit of the pool's token
+pub const LAMPORTS_PER_TOKEN: u64 = 1_000;
+
+/// Stand-in for an aggregator route swapping SOL into an SPL token at a
+/// fixed rate. Only used by the integration tests.
+#[program]
+pub mod swap_mock {
+    use super::*;
+
+    /// Take `lamports_in` from the source and pay out pool tokens at the fixed rate
+    pub fn swap(ctx: Context<Swap>, lamports_in: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.pool_authority.to_account_info(),
+                },
+            ),
+            lamports_in,
+        )?;
+        
+        let seeds: &[&[u8]] = &[b"pool", &[ctx.bumps.pool_authority]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_account.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                &[seeds],
+            ),
+            lamports_in / LAMPORTS_PER_TOKEN,
+        )?;
+        
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    /// Owner of the SOL being swapped
+    #[account(mut)]
+    pub source: Signer<'info>,
+    
+    #[account(mut, seeds = [b"pool"], bump)]
+    pub pool_authority: SystemAccount<'info>,
+    
+    #[account(mut, token::authority = pool_authority)]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
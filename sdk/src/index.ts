@@ -36,6 +36,7 @@ export enum MilestoneStatus {
 }
 
 export enum ContractStatus {
+  Pending = "pending",
   Active = "active",
   Completed = "completed",
   Cancelled = "cancelled",
@@ -82,6 +83,23 @@ export class PayGuardClient {
     );
   }
 
+  /**
+   * Get PDA for a contract's escrow vault, created along with the contract
+   */
+  getVaultPDA(contractId: BN): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), contractId.toArrayLike(Buffer, "le", 8)],
+      this.program.programId
+    );
+  }
+
+  /**
+   * Get PDA for the protocol config, which carries the admin pause switch
+   */
+  getConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from("config")], this.program.programId);
+  }
+
   /**
    * Hash a string to 32 bytes (for on-chain storage)
    */
@@ -96,15 +114,21 @@ export class PayGuardClient {
   async createContract(params: CreateContractParams): Promise<string> {
     const contractId = new BN(Date.now()); // Simple unique ID
     const [contractPDA] = this.getContractPDA(contractId);
+    const [escrowVault] = this.getVaultPDA(contractId);
 
+    // Only the agreed fields are sent; the program starts every milestone pending
     const milestones = params.milestones.map((m) => ({
       amount: new BN(m.amount),
       description: m.description,
-      status: { pending: {} },
-      proofHash: null,
-      disputeReason: null,
-      arbitrationProof: null,
-      submittedAt: null,
+      dueDate: null,
+      penaltyPerDayBps: 0,
+      bonusAmount: new BN(0),
+      minReviewSeconds: new BN(0),
+      displayCurrency: null,
+      displayRate: new BN(0),
+      expectedFormat: null,
+      maxRevisions: 0,
+      vestingSecs: null,
     }));
 
     const descriptionHash = this.hashString(params.description);
@@ -121,6 +145,8 @@ export class PayGuardClient {
         client: this.provider.wallet.publicKey,
         freelancer: params.freelancer,
         tokenMint: params.tokenMint,
+        escrowVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -128,6 +154,23 @@ export class PayGuardClient {
     return tx;
   }
 
+  /**
+   * Accept a contract's terms, activating it (freelancer)
+   */
+  async acceptContract(contractId: BN): Promise<string> {
+    const [contractPDA] = this.getContractPDA(contractId);
+
+    const tx = await this.program.methods
+      .acceptContract()
+      .accounts({
+        contract: contractPDA,
+        freelancer: this.provider.wallet.publicKey,
+      })
+      .rpc();
+
+    return tx;
+  }
+
   /**
    * Fund escrow with tokens
    */
@@ -143,10 +186,11 @@ export class PayGuardClient {
       .fundEscrow(new BN(amount))
       .accounts({
         contract: contractPDA,
-        client: this.provider.wallet.publicKey,
-        clientTokenAccount,
+        funder: this.provider.wallet.publicKey,
+        funderTokenAccount: clientTokenAccount,
         escrowVault,
         tokenProgram: TOKEN_PROGRAM_ID,
+        config: this.getConfigPDA()[0],
       })
       .rpc();
 
@@ -165,7 +209,7 @@ export class PayGuardClient {
     const proofHash = this.hashString(proofDescription);
 
     const tx = await this.program.methods
-      .submitMilestone(milestoneIndex, proofHash)
+      .submitMilestone(milestoneIndex, proofHash, null)
       .accounts({
         contract: contractPDA,
         freelancer: this.provider.wallet.publicKey,
@@ -182,8 +226,7 @@ export class PayGuardClient {
     contractId: BN,
     milestoneIndex: number,
     freelancer: PublicKey,
-    escrowVault: PublicKey,
-    freelancerTokenAccount: PublicKey
+    escrowVault: PublicKey
   ): Promise<string> {
     const [contractPDA] = this.getContractPDA(contractId);
 
@@ -194,8 +237,8 @@ export class PayGuardClient {
         client: this.provider.wallet.publicKey,
         freelancer,
         escrowVault,
-        freelancerTokenAccount,
         tokenProgram: TOKEN_PROGRAM_ID,
+        config: this.getConfigPDA()[0],
       })
       .rpc();
 
@@ -269,6 +312,22 @@ export class PayGuardClient {
 
     return contracts.map((c) => c.account as unknown as ContractData);
   }
+
+  /**
+   * Get all contracts whose description hashes to the given spec
+   */
+  async getContractsByDescription(description: string): Promise<ContractData[]> {
+    const contracts = await this.program.account.contract.all([
+      {
+        memcmp: {
+          offset: 8 + 8 + 32 * 4, // discriminator + id + client + freelancer + mint + arbitrator
+          bytes: anchor.utils.bytes.bs58.encode(Buffer.from(this.hashString(description))),
+        },
+      },
+    ]);
+
+    return contracts.map((c) => c.account as unknown as ContractData);
+  }
 }
 
 // AI Arbitration Module
@@ -1,18 +1,30 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Payguard } from "../target/types/payguard";
+import { CallbackMock } from "../target/types/callback_mock";
+import { SwapMock } from "../target/types/swap_mock";
 import { 
   PublicKey, 
   Keypair, 
   SystemProgram,
-  LAMPORTS_PER_SOL 
+  LAMPORTS_PER_SOL,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+  TransactionInstruction
 } from "@solana/web3.js";
 import { 
   TOKEN_PROGRAM_ID,
   createMint,
   createAccount,
   mintTo,
-  getAccount
+  getAccount,
+  transfer,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  getMintLen,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeMintInstruction
 } from "@solana/spl-token";
 import { expect } from "chai";
 import { BN } from "bn.js";
@@ -26,12 +38,14 @@ describe("payguard", () => {
   // Test accounts
   let client: Keypair;
   let freelancer: Keypair;
+  let arbitrator: Keypair;
   let tokenMint: PublicKey;
   let clientTokenAccount: PublicKey;
   let freelancerTokenAccount: PublicKey;
   let escrowVault: PublicKey;
   let contractPDA: PublicKey;
   let contractBump: number;
+  let configPDA: PublicKey;
   
   const contractId = new BN(Date.now());
   const totalAmount = new BN(1000 * 10 ** 6); // 1000 USDC (6 decimals)
@@ -40,6 +54,7 @@ describe("payguard", () => {
     // Generate keypairs
     client = Keypair.generate();
     freelancer = Keypair.generate();
+    arbitrator = Keypair.generate();
     
     // Airdrop SOL to client for fees
     const airdropSig = await provider.connection.requestAirdrop(
@@ -88,49 +103,364 @@ describe("payguard", () => {
       program.programId
     );
     
-    // Create escrow vault (PDA-owned token account)
-    escrowVault = await createAccount(
-      provider.connection,
-      client,
-      tokenMint,
-      contractPDA,
-      undefined,
-      TOKEN_PROGRAM_ID
+    // Protocol config, shared by every test; the provider wallet is admin
+    [configPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
     );
+    try {
+      await program.methods
+        .initializeConfig(new BN(2))
+        .accounts({
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    } catch (err) {
+      // Already initialized by a previous run against the same validator
+    }
+    
+    // Escrow vault, created with the contract as a PDA of its id
+    escrowVault = vaultPDA(contractId);
   });
 
+  // Creates and funds a fresh contract, returning its PDA and vault
+  // Milestones may be given as plain amounts or as full milestone objects
+  async function createFundedContract(
+    amounts: (number | ReturnType<typeof milestone>)[],
+    terms = defaultTerms()
+  ): Promise<{ pda: PublicKey; vault: PublicKey }> {
+    const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+    const [pda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const milestones = amounts.map((a, i) =>
+      typeof a === "number" ? milestone(a, `Milestone ${i}`) : a
+    );
+    const total = milestones.reduce((sum, m) => sum + m.amount.toNumber(), 0);
+
+    await program.methods
+      .createContract(id, new BN(total), milestones, Array(32).fill(9), terms)
+      .accounts({
+        contract: pda,
+        client: client.publicKey,
+        freelancer: freelancer.publicKey,
+        arbitrator: arbitrator.publicKey,
+        tokenMint: tokenMint,
+        escrowVault: vaultPDA(id),
+        config: configPDA,
+        clientBlock: blockedPDA(client.publicKey),
+        freelancerBlock: blockedPDA(freelancer.publicKey),
+        clientKyc: kycPDA(client.publicKey),
+        freelancerKyc: kycPDA(freelancer.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([client])
+      .rpc();
+    await acceptContract(pda);
+    const vault = vaultPDA(id);
+
+    await program.methods
+      .fundEscrow(new BN(total))
+      .accounts({
+        contract: pda,
+        funder: client.publicKey,
+        funderTokenAccount: clientTokenAccount,
+        escrowVault: vault,
+        tokenMint: tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: configPDA,
+      })
+      .signers([client])
+      .rpc();
+
+    return { pda, vault };
+  }
+
+  // What the client signs to approve a milestone's current submission off-chain
+  async function approvalMessage(pda: PublicKey, index: number): Promise<Buffer> {
+    const contract = await program.account.contract.fetch(pda);
+    const milestone = contract.milestones[index];
+    return Buffer.concat([
+      Buffer.from("payguard:approve_milestone:"),
+      program.programId.toBuffer(),
+      contract.id.toArrayLike(Buffer, "le", 8),
+      Buffer.from([index, milestone.revisionCount]),
+      (milestone.submittedAt ?? new BN(0)).toArrayLike(Buffer, "le", 8),
+    ]);
+  }
+
+  // corrupt_contract is left out of the IDL, so its arguments are encoded here;
+  // it needs a build with the test-mutators feature
+  const mutatorCoder = new anchor.BorshCoder({
+    version: "0.1.0",
+    name: "mutators",
+    instructions: [
+      {
+        name: "corruptContract",
+        accounts: [{ name: "contract", isMut: true, isSigner: false }],
+        args: [{ name: "corruption", type: { defined: "Corruption" } }],
+      },
+    ],
+    types: [
+      program.idl.types.find((t) => t.name === "ContractStatus"),
+      {
+        name: "Corruption",
+        type: {
+          kind: "enum",
+          variants: [
+            { name: "ReleasedAmount", fields: ["u64"] },
+            { name: "MilestoneAmount", fields: [{ name: "index", type: "u8" }, { name: "amount", type: "u64" }] },
+            { name: "FundedAmount", fields: ["u64"] },
+            { name: "Status", fields: [{ defined: "ContractStatus" }] },
+            { name: "Bump", fields: ["u8"] },
+            { name: "DueDate", fields: [{ name: "index", type: "u8" }, { name: "dueDate", type: "i64" }] },
+          ],
+        },
+      },
+    ],
+  } as anchor.Idl);
+
+  async function corruptContract(pda: PublicKey, corruption: any) {
+    const ix = new TransactionInstruction({
+      programId: program.programId,
+      keys: [{ pubkey: pda, isSigner: false, isWritable: true }],
+      data: mutatorCoder.instruction.encode("corruptContract", { corruption }),
+    });
+    await provider.sendAndConfirm(new Transaction().add(ix));
+  }
+
+  // Creation only takes future deadlines; test builds can move them back
+  async function setDueDate(pda: PublicKey, index: number, dueDate: number) {
+    await corruptContract(pda, { dueDate: { index, dueDate: new BN(dueDate) } });
+  }
+
+  async function acceptContract(pda: PublicKey) {
+    await program.methods
+      .acceptContract()
+      .accounts({ contract: pda, freelancer: freelancer.publicKey })
+      .signers([freelancer])
+      .rpc();
+  }
+
+  async function submit(pda: PublicKey, index: number) {
+    await program.methods
+      .submitMilestone(index, [Array(32).fill(index + 1)], null)
+      .accounts({ contract: pda, freelancer: freelancer.publicKey })
+      .signers([freelancer])
+      .rpc();
+  }
+
+  async function approve(
+    pda: PublicKey,
+    vault: PublicKey,
+    index: number,
+    remainingAccounts: anchor.web3.AccountMeta[] = []
+  ) {
+    return program.methods
+      .approveMilestone(index)
+      .accounts({
+        contract: pda,
+        client: client.publicKey,
+        freelancer: freelancer.publicKey,
+        escrowVault: vault,
+        clientTokenAccount: clientTokenAccount,
+        bonusVault: null,
+        freelancerBonusTokenAccount: null,
+        bonusMint: null,
+        feeTreasuryTokenAccount: null,
+        tokenMint: tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: configPDA,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers([client])
+      .rpc();
+  }
+
+  async function approveWithFee(pda: PublicKey, vault: PublicKey, index: number, treasury: PublicKey) {
+    await program.methods
+      .approveMilestone(index)
+      .accounts({
+        contract: pda,
+        client: client.publicKey,
+        freelancer: freelancer.publicKey,
+        escrowVault: vault,
+        clientTokenAccount: clientTokenAccount,
+        bonusVault: null,
+        freelancerBonusTokenAccount: null,
+        bonusMint: null,
+        feeTreasuryTokenAccount: treasury,
+        tokenMint: tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: configPDA,
+      })
+      .signers([client])
+      .rpc();
+  }
+
+  async function reject(pda: PublicKey, index: number) {
+    await program.methods
+      .rejectMilestone(index, Array(32).fill(5))
+      .accounts({ contract: pda, client: client.publicKey })
+      .signers([client])
+      .rpc();
+  }
+
+  // The client's token account, mint and token program a deposit goes through
+  type FundingToken = { account: PublicKey; mint: PublicKey; program: PublicKey };
+
+  async function deposit(
+    method: "fundEscrow" | "topUpEscrow",
+    pda: PublicKey,
+    vault: PublicKey,
+    amount: number,
+    token: FundingToken = { account: clientTokenAccount, mint: tokenMint, program: TOKEN_PROGRAM_ID }
+  ) {
+    await program.methods[method](new BN(amount))
+      .accounts({
+        contract: pda,
+        funder: client.publicKey,
+        funderTokenAccount: token.account,
+        escrowVault: vault,
+        tokenMint: token.mint,
+        tokenProgram: token.program,
+        config: configPDA,
+      })
+      .signers([client])
+      .rpc();
+  }
+
+  async function withdraw(pda: PublicKey, vault: PublicKey) {
+    return program.methods
+      .withdrawFunds()
+      .accounts({
+        contract: pda,
+        freelancer: freelancer.publicKey,
+        client: client.publicKey,
+        escrowVault: vault,
+        freelancerTokenAccount: freelancerTokenAccount,
+        tokenMint: tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: configPDA,
+      })
+      .signers([freelancer])
+      .rpc();
+  }
+
+  async function raiseDispute(
+    pda: PublicKey,
+    index: number,
+    raiser: Keypair = client,
+    disputeType: any = { technical: {} }
+  ) {
+    await program.methods
+      .raiseDispute(index, Array(32).fill(7), disputeType)
+      .accounts({
+        contract: pda,
+        authority: raiser.publicKey,
+        raiserTokenAccount: null,
+        escrowVault: null,
+        tokenMint: null,
+        tokenProgram: null,
+        config: configPDA,
+      })
+      .signers([raiser])
+      .rpc();
+  }
+
+  async function resolveDispute(
+    pda: PublicKey,
+    vault: PublicKey,
+    index: number,
+    decision: any,
+    rationaleHash: number[] = Array(32).fill(8),
+    resolver: Keypair = arbitrator,
+    arbitratorTokenAccount: PublicKey | null = null
+  ) {
+    return program.methods
+      .resolveDispute(index, decision, Array(32).fill(6), rationaleHash)
+      .accounts({
+        contract: pda,
+        arbitrator: resolver.publicKey,
+        client: client.publicKey,
+        escrowVault: vault,
+        freelancerTokenAccount: freelancerTokenAccount,
+        clientTokenAccount: clientTokenAccount,
+        bonusVault: null,
+        freelancerBonusTokenAccount: null,
+        bonusMint: null,
+        feeTreasuryTokenAccount: null,
+        restitutionTokenAccount: null,
+        arbitratorTokenAccount,
+        tokenMint: tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: configPDA,
+        arbitratorProfile: arbitratorPDA(resolver.publicKey),
+      })
+      .signers([resolver])
+      .rpc();
+  }
+
+  async function balance(account: PublicKey): Promise<number> {
+    return Number((await getAccount(provider.connection, account)).amount);
+  }
+
+  function blockedPDA(address: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("blocked"), address.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function vaultPDA(id: BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  }
+
+  function kycPDA(address: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("kyc"), address.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function arbitratorPDA(address: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("arbitrator"), address.toBuffer()],
+      program.programId
+    )[0];
+  }
+
   describe("create_contract", () => {
     it("should create a new escrow contract", async () => {
       const milestones = [
-        {
-          amount: new BN(500 * 10 ** 6),
-          description: "Design mockups",
-          status: { pending: {} },
-          proofHash: null,
-          disputeReason: null,
-          arbitrationProof: null,
-          submittedAt: null,
-        },
-        {
-          amount: new BN(500 * 10 ** 6),
-          description: "Final delivery",
-          status: { pending: {} },
-          proofHash: null,
-          disputeReason: null,
-          arbitrationProof: null,
-          submittedAt: null,
-        },
+        milestone(500 * 10 ** 6, "Design mockups"),
+        milestone(500 * 10 ** 6, "Final delivery"),
       ];
       
       const descriptionHash = Array(32).fill(1); // Mock hash
       
       const tx = await program.methods
-        .createContract(contractId, totalAmount, milestones, descriptionHash)
+        .createContract(contractId, totalAmount, milestones, descriptionHash, defaultTerms())
         .accounts({
           contract: contractPDA,
           client: client.publicKey,
           freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
           tokenMint: tokenMint,
+          escrowVault: vaultPDA(contractId),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .signers([client])
@@ -145,6 +475,120 @@ describe("payguard", () => {
       expect(contract.totalAmount.toNumber()).to.equal(totalAmount.toNumber());
       expect(contract.releasedAmount.toNumber()).to.equal(0);
       expect(contract.milestones.length).to.equal(2);
+      expect(contract.status).to.deep.equal({ pending: {} });
+    });
+
+    it("rejects a client contracting with themselves", async () => {
+      const id = new BN(Date.now() + 3);
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      try {
+        await program.methods
+          .createContract(id, new BN(100), [milestone(100, "Self review")], Array(32).fill(1), defaultTerms())
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: client.publicKey,
+            arbitrator: arbitrator.publicKey,
+            tokenMint: tokenMint,
+            escrowVault: vaultPDA(id),
+            config: configPDA,
+            clientBlock: blockedPDA(client.publicKey),
+            freelancerBlock: blockedPDA(client.publicKey),
+            clientKyc: kycPDA(client.publicKey),
+            freelancerKyc: kycPDA(client.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected a self-dealing contract");
+      } catch (err) {
+        expect(err.toString()).to.include("SelfDealing");
+      }
+    });
+
+    it("rejects a token mint that is not a mint account", async () => {
+      const id = new BN(Date.now() + 4);
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      try {
+        await program.methods
+          .createContract(id, new BN(100), [milestone(100, "Bogus mint")], Array(32).fill(1), defaultTerms())
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            arbitrator: arbitrator.publicKey,
+            // A token account is owned by the token program but is not a mint
+            tokenMint: clientTokenAccount,
+            escrowVault: vaultPDA(id),
+            config: configPDA,
+            clientBlock: blockedPDA(client.publicKey),
+            freelancerBlock: blockedPDA(freelancer.publicKey),
+            clientKyc: kycPDA(client.publicKey),
+            freelancerKyc: kycPDA(freelancer.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected the bogus mint");
+      } catch (err) {
+        expect(err.toString()).to.include("AccountDidNotDeserialize");
+      }
+    });
+
+    it("rejects a zero-amount milestone", async () => {
+      try {
+        await createFundedContract([100, 0]);
+        expect.fail("should have rejected the empty milestone");
+      } catch (err) {
+        expect(err.toString()).to.include("ZeroMilestoneAmount");
+      }
+    });
+  });
+
+  describe("accept_contract", () => {
+    it("should not fund a contract the freelancer has not accepted", async () => {
+      try {
+        await program.methods
+          .fundEscrow(totalAmount)
+          .accounts({
+            contract: contractPDA,
+            funder: client.publicKey,
+            funderTokenAccount: clientTokenAccount,
+            escrowVault: escrowVault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required acceptance");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotActive");
+      }
+    });
+
+    it("should only let the freelancer accept", async () => {
+      try {
+        await program.methods
+          .acceptContract()
+          .accounts({ contract: contractPDA, freelancer: client.publicKey })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required the freelancer");
+      } catch (err) {
+        expect(err.toString()).to.match(/ConstraintHasOne|has one/i);
+      }
+
+      await acceptContract(contractPDA);
+      const contract = await program.account.contract.fetch(contractPDA);
       expect(contract.status).to.deep.equal({ active: {} });
     });
   });
@@ -155,10 +599,12 @@ describe("payguard", () => {
         .fundEscrow(totalAmount)
         .accounts({
           contract: contractPDA,
-          client: client.publicKey,
-          clientTokenAccount: clientTokenAccount,
+          funder: client.publicKey,
+          funderTokenAccount: clientTokenAccount,
           escrowVault: escrowVault,
+          tokenMint: tokenMint,
           tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
         })
         .signers([client])
         .rpc();
@@ -176,7 +622,7 @@ describe("payguard", () => {
       const proofHash = Array(32).fill(2); // Mock proof hash
       
       const tx = await program.methods
-        .submitMilestone(0, proofHash)
+        .submitMilestone(0, [proofHash], null)
         .accounts({
           contract: contractPDA,
           freelancer: freelancer.publicKey,
@@ -206,28 +652,36 @@ describe("payguard", () => {
           client: client.publicKey,
           freelancer: freelancer.publicKey,
           escrowVault: escrowVault,
-          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: tokenMint,
           tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
         })
         .signers([client])
         .rpc();
       
       console.log("Approve milestone tx:", tx);
       
-      // Verify funds transferred
+      // Verify the payout is credited, not yet transferred
+      const expectedAmount = 500 * 10 ** 6;
+      const contract = await program.account.contract.fetch(contractPDA);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.releasedAmount.toNumber()).to.equal(expectedAmount);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(expectedAmount);
+      
+      // Verify funds transferred once the freelancer withdraws
+      await withdraw(contractPDA, escrowVault);
       const freelancerBalanceAfter = await getAccount(
         provider.connection, 
         freelancerTokenAccount
       );
-      const expectedAmount = 500 * 10 ** 6;
       expect(Number(freelancerBalanceAfter.amount)).to.equal(
         Number(freelancerBalanceBefore.amount) + expectedAmount
       );
-      
-      // Verify contract state
-      const contract = await program.account.contract.fetch(contractPDA);
-      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
-      expect(contract.releasedAmount.toNumber()).to.equal(expectedAmount);
     });
   });
 
@@ -236,7 +690,7 @@ describe("payguard", () => {
       // First submit milestone 2
       const proofHash = Array(32).fill(3);
       await program.methods
-        .submitMilestone(1, proofHash)
+        .submitMilestone(1, [proofHash], null)
         .accounts({
           contract: contractPDA,
           freelancer: freelancer.publicKey,
@@ -247,10 +701,15 @@ describe("payguard", () => {
       // Now raise dispute
       const reasonHash = Array(32).fill(4);
       const tx = await program.methods
-        .raiseDispute(1, reasonHash)
+        .raiseDispute(1, reasonHash, { technical: {} })
         .accounts({
           contract: contractPDA,
           authority: client.publicKey,
+          raiserTokenAccount: null,
+          escrowVault: null,
+          tokenMint: null,
+          tokenProgram: null,
+          config: configPDA,
         })
         .signers([client])
         .rpc();
@@ -273,50 +732,44 @@ describe("payguard", () => {
       );
       
       const cancelAmount = new BN(100 * 10 ** 6);
-      const milestones = [
-        {
-          amount: cancelAmount,
-          description: "Test milestone",
-          status: { pending: {} },
-          proofHash: null,
-          disputeReason: null,
-          arbitrationProof: null,
-          submittedAt: null,
-        },
-      ];
+      const milestones = [milestone(cancelAmount, "Test milestone")];
       
       // Create contract
       await program.methods
-        .createContract(cancelContractId, cancelAmount, milestones, Array(32).fill(5))
+        .createContract(cancelContractId, cancelAmount, milestones, Array(32).fill(5), defaultTerms())
         .accounts({
           contract: cancelPDA,
           client: client.publicKey,
           freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
           tokenMint: tokenMint,
+          escrowVault: vaultPDA(cancelContractId),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .signers([client])
         .rpc();
+      await acceptContract(cancelPDA);
       
-      // Create escrow vault for this contract
-      const cancelVault = await createAccount(
-        provider.connection,
-        client,
-        tokenMint,
-        cancelPDA,
-        undefined,
-        TOKEN_PROGRAM_ID
-      );
+      // The escrow vault was created along with the contract
+      const cancelVault = vaultPDA(cancelContractId);
       
       // Fund it
       await program.methods
         .fundEscrow(cancelAmount)
         .accounts({
           contract: cancelPDA,
-          client: client.publicKey,
-          clientTokenAccount: clientTokenAccount,
+          funder: client.publicKey,
+          funderTokenAccount: clientTokenAccount,
           escrowVault: cancelVault,
+          tokenMint: tokenMint,
           tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
         })
         .signers([client])
         .rpc();
@@ -329,11 +782,13 @@ describe("payguard", () => {
         .accounts({
           contract: cancelPDA,
           client: client.publicKey,
+          freelancer: freelancer.publicKey,
           escrowVault: cancelVault,
           clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .signers([client])
+        .signers([client, freelancer])
         .rpc();
       
       console.log("Cancel contract tx:", tx);
@@ -348,8 +803,6443 @@ describe("payguard", () => {
       const contract = await program.account.contract.fetch(cancelPDA);
       expect(contract.status).to.deep.equal({ cancelled: {} });
     });
+
+    it("requires the freelancer to agree once the contract is active", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      try {
+        await program.methods
+          .cancelContract()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required the freelancer's signature");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+      expect(await balance(vault)).to.equal(100);
+    });
   });
-});
+
+  describe("retainage", () => {
+    it("withholds retainage on each release and pays it out at completion", async () => {
+      const { pda, vault } = await createFundedContract(
+        [400 * 10 ** 6, 600 * 10 ** 6],
+        defaultTerms({ retainageBps: 1000 }) // 10%
+      );
+
+      const before = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      // 10% of 400 is withheld
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 360 * 10 ** 6);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.retainageHeld.toNumber()).to.equal(40 * 10 ** 6);
+      expect(await balance(vault)).to.equal(640 * 10 ** 6);
+
+      await submit(pda, 1);
+      await approve(pda, vault, 1);
+
+      // Final release credits 540 plus all 100 of accumulated retainage
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.retainageHeld.toNumber()).to.equal(0);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(640 * 10 ** 6);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 1000 * 10 ** 6);
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+
+    it("leaves earned retainage to the freelancer on cancellation", async () => {
+      const { pda, vault } = await createFundedContract([400, 600], defaultTerms({ retainageBps: 1000 }));
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+
+      const clientBefore = await balance(clientTokenAccount);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await program.methods
+        .cancelContract()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+
+      // Only the unreleased milestone is refunded; the 40 withheld from the
+      // approved one is credited for withdrawal
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 600);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.retainageHeld.toNumber()).to.equal(0);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(40);
+
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 40);
+    });
+
+    it("rejects retainage above 100%", async () => {
+      try {
+        await createFundedContract([100], defaultTerms({ retainageBps: 10_001 }));
+        expect.fail("should have rejected retainage above 100%");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidRetainage");
+      }
+    });
+  });
+
+  describe("funding net of transfer fees", () => {
+    it("records the amount the vault actually received", async () => {
+      const { pda, vault } = await createFundedContract([250 * 10 ** 6]);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(await balance(vault));
+      expect(contract.fundedAmount.toNumber()).to.equal(250 * 10 ** 6);
+    });
+
+    it("accepts over-funding to cover transfer fees", async () => {
+      const id = new BN(Date.now() + 7);
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+
+      await program.methods
+        .fundEscrow(new BN(101))
+        .accounts({
+          contract: pda,
+          funder: client.publicKey,
+          funderTokenAccount: clientTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(101);
+    });
+  });
+
+  describe("migrate_vault", () => {
+    it("re-points a contract to a new vault after the timelock", async () => {
+      const { pda, vault } = await createFundedContract([300 * 10 ** 6]);
+      const newVault = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        pda,
+        Keypair.generate(),
+        TOKEN_PROGRAM_ID
+      );
+
+      await program.methods
+        .proposeVaultMigration(newVault)
+        .accounts({ contract: pda, config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+
+      const migrate = () =>
+        program.methods
+          .migrateVault(newVault)
+          .accounts({
+            contract: pda,
+            config: configPDA,
+            admin: provider.wallet.publicKey,
+            oldVault: vault,
+            newVault: newVault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      try {
+        await migrate();
+        expect.fail("should have enforced the timelock");
+      } catch (err) {
+        expect(err.toString()).to.include("TimelockNotElapsed");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await migrate();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.escrowVault.toString()).to.equal(newVault.toString());
+      expect(await balance(vault)).to.equal(0);
+      expect(await balance(newVault)).to.equal(300 * 10 ** 6);
+    });
+
+    it("rejects proposals from non-admins", async () => {
+      const { pda } = await createFundedContract([10]);
+      try {
+        await program.methods
+          .proposeVaultMigration(Keypair.generate().publicKey)
+          .accounts({ contract: pda, config: configPDA, admin: client.publicKey })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected a non-admin");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("dispute rationale", () => {
+    it("rejects a resolution without a rationale hash", async () => {
+      const { pda, vault } = await createFundedContract([100 * 10 ** 6]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await resolveDispute(pda, vault, 0, { favorFreelancer: {} }, Array(32).fill(0));
+        expect.fail("should have required a rationale");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingRationale");
+      }
+    });
+
+    it("stores the rationale hash on the milestone", async () => {
+      const { pda, vault } = await createFundedContract([100 * 10 ** 6]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      const rationale = Array(32).fill(42);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} }, rationale);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].rationaleHash).to.deep.equal(rationale);
+    });
+  });
+
+  describe("late penalties", () => {
+    const DAY = 86_400;
+    const now = () => Math.floor(Date.now() / 1000);
+
+    async function releaseWithDueDate(dueDate: number | null) {
+      const { pda, vault } = await createFundedContract([
+        milestone(100 * 10 ** 6, "Deadline work", {
+          penaltyPerDayBps: 500, // 5% per day
+        }),
+      ]);
+      if (dueDate !== null) await setDueDate(pda, 0, dueDate);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      const clientBefore = await balance(clientTokenAccount);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      if ((await program.account.contract.fetch(pda)).approvedUnclaimed.toNumber() > 0) {
+        await withdraw(pda, vault);
+      }
+      return {
+        pda,
+        freelancerGain: (await balance(freelancerTokenAccount)) - freelancerBefore,
+        clientGain: (await balance(clientTokenAccount)) - clientBefore,
+      };
+    }
+
+    it("pays in full when submitted on time", async () => {
+      const { freelancerGain, clientGain } = await releaseWithDueDate(now() + DAY);
+      expect(freelancerGain).to.equal(100 * 10 ** 6);
+      expect(clientGain).to.equal(0);
+    });
+
+    it("deducts 5% per day when three days late", async () => {
+      const { pda, freelancerGain, clientGain } = await releaseWithDueDate(now() - 3 * DAY + 600);
+      expect(freelancerGain).to.equal(85 * 10 ** 6);
+      expect(clientGain).to.equal(15 * 10 ** 6);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.refundedAmount.toNumber()).to.equal(15 * 10 ** 6);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("caps the penalty at the milestone amount", async () => {
+      const { freelancerGain, clientGain } = await releaseWithDueDate(now() - 100 * DAY);
+      expect(freelancerGain).to.equal(0);
+      expect(clientGain).to.equal(100 * 10 ** 6);
+    });
+  });
+
+  describe("close_contract", () => {
+    function close(pda: PublicKey, vault: PublicKey) {
+      return program.methods
+        .closeContract()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          escrowVault: vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+    }
+
+    it("returns the rent of a completed contract to the client", async () => {
+      const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+
+      const rent = (await provider.connection.getAccountInfo(pda)).lamports;
+      const clientLamportsBefore = await provider.connection.getBalance(client.publicKey);
+      await close(pda, vault);
+
+      expect(await provider.connection.getAccountInfo(pda)).to.be.null;
+      // The client pays the transaction fee out of the reclaimed rent
+      expect(await provider.connection.getBalance(client.publicKey)).to.be.greaterThan(
+        clientLamportsBefore + rent - 10_000
+      );
+    });
+
+    it("closes the emptied vault of a cancelled contract", async () => {
+      const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+      await program.methods
+        .cancelContract()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+      expect(await provider.connection.getAccountInfo(vault)).to.not.be.null;
+
+      await close(pda, vault);
+
+      expect(await provider.connection.getAccountInfo(pda)).to.be.null;
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+
+    it("refuses to close an active contract", async () => {
+      const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+      try {
+        await close(pda, vault);
+        expect.fail("should have refused an active contract");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotClosable");
+      }
+      expect(await provider.connection.getAccountInfo(pda)).to.not.be.null;
+    });
+
+    it("refuses to close while the freelancer has a payout to withdraw", async () => {
+      const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      try {
+        await close(pda, vault);
+        expect.fail("should have kept the unclaimed payout");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotClosable");
+      }
+      expect(await balance(vault)).to.equal(10 * 10 ** 6);
+    });
+
+    it("rejects a vault that is not the contract's own", async () => {
+      const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+      const other = await createFundedContract([10 * 10 ** 6]);
+      for (const wrongVault of [other.vault, Keypair.generate().publicKey]) {
+        try {
+          await close(pda, wrongVault);
+          expect.fail("should have required the contract's vault");
+        } catch (err) {
+          expect(err.toString()).to.include("InvalidVault");
+        }
+      }
+      expect(await provider.connection.getAccountInfo(pda)).to.not.be.null;
+    });
+  });
+
+  describe("close_contracts", () => {
+    it("closes three completed contracts and their vaults in one call", async () => {
+      const finished = [];
+      for (let i = 0; i < 3; i++) {
+        const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+        await submit(pda, 0);
+        await approve(pda, vault, 0);
+        await withdraw(pda, vault);
+        finished.push({ pda, vault });
+      }
+
+      const clientLamportsBefore = await provider.connection.getBalance(client.publicKey);
+
+      await program.methods
+        .closeContracts()
+        .accounts({ operator: provider.wallet.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+        .remainingAccounts(
+          finished.flatMap(({ pda, vault }) => [
+            { pubkey: pda, isWritable: true, isSigner: false },
+            { pubkey: client.publicKey, isWritable: true, isSigner: false },
+            { pubkey: vault, isWritable: true, isSigner: false },
+          ])
+        )
+        .rpc();
+
+      for (const { pda, vault } of finished) {
+        expect(await provider.connection.getAccountInfo(pda)).to.be.null;
+        expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+      }
+      expect(await provider.connection.getBalance(client.publicKey)).to.be.greaterThan(
+        clientLamportsBefore
+      );
+    });
+
+    it("refuses to close an active contract", async () => {
+      const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+      try {
+        await program.methods
+          .closeContracts()
+          .accounts({ operator: provider.wallet.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+          .remainingAccounts([
+            { pubkey: pda, isWritable: true, isSigner: false },
+            { pubkey: client.publicKey, isWritable: true, isSigner: false },
+            { pubkey: vault, isWritable: true, isSigner: false },
+          ])
+          .rpc();
+        expect.fail("should have refused an active contract");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotClosable");
+      }
+    });
+
+    it("refuses a batch holding a contract whose releases do not add up", async () => {
+      // Needs a build with the test-mutators feature for corrupt_contract
+      const finished = [];
+      for (let i = 0; i < 2; i++) {
+        const { pda, vault } = await createFundedContract([10 * 10 ** 6]);
+        await submit(pda, 0);
+        await approve(pda, vault, 0);
+        await withdraw(pda, vault);
+        finished.push({ pda, vault });
+      }
+      await corruptContract(finished[1].pda, { releasedAmount: [new BN(10 * 10 ** 6 - 1)] });
+
+      try {
+        await program.methods
+          .closeContracts()
+          .accounts({ operator: provider.wallet.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+          .remainingAccounts(
+            finished.flatMap(({ pda, vault }) => [
+              { pubkey: pda, isWritable: true, isSigner: false },
+              { pubkey: client.publicKey, isWritable: true, isSigner: false },
+              { pubkey: vault, isWritable: true, isSigner: false },
+            ])
+          )
+          .rpc();
+        expect.fail("should have refused the inconsistent contract");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotClosable");
+      }
+      for (const { pda } of finished) {
+        expect(await provider.connection.getAccountInfo(pda)).to.not.be.null;
+      }
+    });
+  });
+
+  describe("dispute routing", () => {
+    const manager = Keypair.generate();
+
+    it("routes technical disputes to the arbitrator and scope disputes to the manager", async () => {
+      const { pda, vault } = await createFundedContract(
+        [50 * 10 ** 6, 50 * 10 ** 6],
+        defaultTerms({ scopeResolver: manager.publicKey })
+      );
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await raiseDispute(pda, 0, client, { technical: {} });
+      await raiseDispute(pda, 1, client, { scope: {} });
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].disputeResolver.toString()).to.equal(arbitrator.publicKey.toString());
+      expect(contract.milestones[1].disputeResolver.toString()).to.equal(manager.publicKey.toString());
+
+      // The arbitrator cannot settle the scope dispute
+      try {
+        await resolveDispute(pda, vault, 1, { favorClient: {} });
+        expect.fail("arbitrator should not resolve scope disputes");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+      await resolveDispute(pda, vault, 1, { favorClient: {} }, Array(32).fill(8), manager);
+
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.milestones[1].status).to.deep.equal({ rejected: {} });
+    });
+
+    it("keeps a scope resolver from routing payouts to its own accounts", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ scopeResolver: manager.publicKey }));
+      await submit(pda, 0);
+      await raiseDispute(pda, 0, client, { scope: {} });
+      const managerAccount = await createAccount(provider.connection, client, tokenMint, manager.publicKey);
+
+      for (const [freelancerAccount, clientAccount] of [
+        [managerAccount, clientTokenAccount],
+        [freelancerTokenAccount, managerAccount],
+      ]) {
+        try {
+          await program.methods
+            .resolveDispute(0, { split: [50] }, Array(32).fill(6), Array(32).fill(8))
+            .accounts({
+              contract: pda,
+              arbitrator: manager.publicKey,
+              client: client.publicKey,
+              escrowVault: vault,
+              freelancerTokenAccount: freelancerAccount,
+              clientTokenAccount: clientAccount,
+              bonusVault: null,
+              freelancerBonusTokenAccount: null,
+              bonusMint: null,
+              feeTreasuryTokenAccount: null,
+              restitutionTokenAccount: null,
+              arbitratorTokenAccount: null,
+              tokenMint: tokenMint,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              config: configPDA,
+              arbitratorProfile: arbitratorPDA(manager.publicKey),
+            })
+            .signers([manager])
+            .rpc();
+          expect.fail("should have required the parties' own token accounts");
+        } catch (err) {
+          expect(err.toString()).to.include("Unauthorized");
+        }
+      }
+      expect(await balance(managerAccount)).to.equal(0);
+    });
+
+    it("rejects scope disputes when no manager is designated", async () => {
+      const { pda } = await createFundedContract([50 * 10 ** 6]);
+      await submit(pda, 0);
+      try {
+        await raiseDispute(pda, 0, client, { scope: {} });
+        expect.fail("should require a scope resolver");
+      } catch (err) {
+        expect(err.toString()).to.include("NoScopeResolver");
+      }
+    });
+  });
+
+  describe("multi-artifact submissions", () => {
+    it("stores several proof hashes for one milestone", async () => {
+      const { pda } = await createFundedContract([10 * 10 ** 6]);
+      const proofs = [Array(32).fill(11), Array(32).fill(12), Array(32).fill(13)];
+
+      await program.methods
+        .submitMilestone(0, proofs, null)
+        .accounts({ contract: pda, freelancer: freelancer.publicKey })
+        .signers([freelancer])
+        .rpc();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].proofHashes).to.deep.equal(proofs);
+    });
+
+    it("rejects submissions over the proof cap", async () => {
+      const { pda } = await createFundedContract([10 * 10 ** 6]);
+      const proofs = Array.from({ length: 6 }, (_, i) => Array(32).fill(i + 1));
+
+      try {
+        await program.methods
+          .submitMilestone(0, proofs, null)
+          .accounts({ contract: pda, freelancer: freelancer.publicKey })
+          .signers([freelancer])
+          .rpc();
+        expect.fail("should have rejected six proofs");
+      } catch (err) {
+        expect(err.toString()).to.include("TooManyProofs");
+      }
+    });
+  });
+
+  describe("completion callback", () => {
+    const callbackProgram = anchor.workspace.CallbackMock as Program<CallbackMock>;
+
+    it("notifies the callback program when the contract completes", async () => {
+      const { pda, vault } = await createFundedContract(
+        [20 * 10 ** 6],
+        defaultTerms({ completionCallbackProgram: callbackProgram.programId })
+      );
+      await submit(pda, 0);
+      const sig = await approve(pda, vault, 0, [
+        { pubkey: callbackProgram.programId, isWritable: false, isSigner: false },
+      ]);
+
+      await provider.connection.confirmTransaction(sig, "confirmed");
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const logs = tx.meta.logMessages.join("\n");
+      expect(logs).to.include(`Program ${callbackProgram.programId} invoke`);
+      expect(logs).to.include(`released=${20 * 10 ** 6}`);
+    });
+
+    it("refuses to complete without the callback program", async () => {
+      const { pda, vault } = await createFundedContract(
+        [20 * 10 ** 6],
+        defaultTerms({ completionCallbackProgram: callbackProgram.programId })
+      );
+      await submit(pda, 0);
+
+      try {
+        await approve(pda, vault, 0);
+        expect.fail("should have required the callback program");
+      } catch (err) {
+        expect(err.toString()).to.include("CompletionCallbackMissing");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+    });
+  });
+
+  describe("bonus sub-vault", () => {
+    it("funds and releases from primary and bonus vaults", async () => {
+      const bonusMint = await createMint(provider.connection, client, client.publicKey, null, 9);
+      const clientBonus = await createAccount(provider.connection, client, bonusMint, client.publicKey);
+      const freelancerBonus = await createAccount(provider.connection, client, bonusMint, freelancer.publicKey);
+      await mintTo(provider.connection, client, bonusMint, clientBonus, client, 1_000);
+
+      const { pda, vault } = await createFundedContract(
+        [
+          milestone(30 * 10 ** 6, "Primary plus bonus", { bonusAmount: new BN(700) }),
+          milestone(30 * 10 ** 6, "Primary only"),
+        ],
+        defaultTerms({ bonusMint })
+      );
+      const bonusVault = await createAccount(
+        provider.connection,
+        client,
+        bonusMint,
+        pda,
+        Keypair.generate(),
+        TOKEN_PROGRAM_ID
+      );
+
+      await program.methods
+        .fundBonus(new BN(700))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          clientBonusTokenAccount: clientBonus,
+          bonusVault,
+          bonusMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      expect(await balance(bonusVault)).to.equal(700);
+
+      const primaryBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await program.methods
+        .approveMilestone(0)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault,
+          freelancerBonusTokenAccount: freelancerBonus,
+          bonusMint,
+          feeTreasuryTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      await withdraw(pda, vault);
+
+      expect(await balance(freelancerTokenAccount)).to.equal(primaryBefore + 30 * 10 ** 6);
+      expect(await balance(freelancerBonus)).to.equal(700);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.bonusReleased.toNumber()).to.equal(700);
+    });
+
+    it("rejects a bonus account the freelancer does not own", async () => {
+      const bonusMint = await createMint(provider.connection, client, client.publicKey, null, 9);
+      const clientBonus = await createAccount(provider.connection, client, bonusMint, client.publicKey);
+      await mintTo(provider.connection, client, bonusMint, clientBonus, client, 700);
+
+      const { pda, vault } = await createFundedContract(
+        [milestone(30 * 10 ** 6, "Primary plus bonus", { bonusAmount: new BN(700) })],
+        defaultTerms({ bonusMint })
+      );
+      const bonusVault = await createAccount(
+        provider.connection,
+        client,
+        bonusMint,
+        pda,
+        Keypair.generate(),
+        TOKEN_PROGRAM_ID
+      );
+      await program.methods
+        .fundBonus(new BN(700))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          clientBonusTokenAccount: clientBonus,
+          bonusVault,
+          bonusMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      await submit(pda, 0);
+
+      try {
+        await program.methods
+          .approveMilestone(0)
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            bonusVault,
+            freelancerBonusTokenAccount: clientBonus,
+            bonusMint,
+            feeTreasuryTokenAccount: null,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required the freelancer's own bonus account");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      expect(await balance(bonusVault)).to.equal(700);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+    });
+
+    it("funds the bonus vault only once", async () => {
+      const bonusMint = await createMint(provider.connection, client, client.publicKey, null, 9);
+      const clientBonus = await createAccount(provider.connection, client, bonusMint, client.publicKey);
+      await mintTo(provider.connection, client, bonusMint, clientBonus, client, 1_400);
+
+      const { pda } = await createFundedContract(
+        [milestone(30 * 10 ** 6, "Primary plus bonus", { bonusAmount: new BN(700) })],
+        defaultTerms({ bonusMint })
+      );
+      const fundBonus = async () => {
+        const bonusVault = await createAccount(
+          provider.connection,
+          client,
+          bonusMint,
+          pda,
+          Keypair.generate(),
+          TOKEN_PROGRAM_ID
+        );
+        await program.methods
+          .fundBonus(new BN(700))
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            clientBonusTokenAccount: clientBonus,
+            bonusVault,
+            bonusMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+        return bonusVault;
+      };
+
+      const bonusVault = await fundBonus();
+      try {
+        await fundBonus();
+        expect.fail("should have kept the first bonus vault");
+      } catch (err) {
+        expect(err.toString()).to.include("BonusAlreadyFunded");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.bonusVault.toBase58()).to.equal(bonusVault.toBase58());
+      expect(await balance(clientBonus)).to.equal(700);
+    });
+  });
+
+  describe("min_milestones", () => {
+    const setMin = (min: number) =>
+      program.methods
+        .setMinMilestones(min)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+
+    after(async () => {
+      await setMin(1);
+    });
+
+    it("accepts contracts at the minimum and rejects those below it", async () => {
+      await setMin(2);
+
+      await createFundedContract([10, 10]);
+
+      try {
+        await createFundedContract([20]);
+        expect.fail("should have rejected a lump-sum contract");
+      } catch (err) {
+        expect(err.toString()).to.include("TooFewMilestones");
+      }
+    });
+  });
+
+  describe("revoke_milestone", () => {
+    it("revokes a pending milestone and refunds its funded amount", async () => {
+      const { pda, vault } = await createFundedContract([40 * 10 ** 6, 60 * 10 ** 6]);
+      const clientBefore = await balance(clientTokenAccount);
+
+      await program.methods
+        .revokeMilestone(1)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 60 * 10 ** 6);
+      expect(await balance(vault)).to.equal(40 * 10 ** 6);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.totalAmount.toNumber()).to.equal(40 * 10 ** 6);
+      expect(contract.milestones.length).to.equal(2);
+      expect(contract.milestones[1].status).to.deep.equal({ cancelled: {} });
+
+      // The remaining milestone still completes the contract
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      const completed = await program.account.contract.fetch(pda);
+      expect(completed.status).to.deep.equal({ completed: {} });
+    });
+
+    it("requires the freelancer's signature", async () => {
+      const { pda, vault } = await createFundedContract([10, 10]);
+      try {
+        await program.methods
+          .revokeMilestone(0)
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required both signatures");
+      } catch (err) {
+        expect(err.toString()).to.match(/Signature verification failed|missing signature/i);
+      }
+    });
+
+    it("only refunds to the client's own token account", async () => {
+      const { pda, vault } = await createFundedContract([10, 10]);
+      try {
+        await program.methods
+          .revokeMilestone(1)
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: freelancerTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client, freelancer])
+          .rpc();
+        expect.fail("should have required the client's token account");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      expect(await balance(vault)).to.equal(20);
+    });
+  });
+
+  describe("platform fee rounding", () => {
+    it("floors the fee so the freelancer never loses a unit to rounding", async () => {
+      const config = await program.account.config.fetch(configPDA);
+      const treasury = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        config.feeTreasury,
+        Keypair.generate()
+      );
+      // 333 bps of 1001 is 33.33 and of 7 is 0.23; both round down
+      const { pda, vault } = await createFundedContract([1001, 7], defaultTerms({ feeBps: 333 }));
+      const freelancerBefore = await balance(freelancerTokenAccount);
+
+      for (const index of [0, 1]) {
+        await submit(pda, index);
+        await program.methods
+          .approveMilestone(index)
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            bonusVault: null,
+            freelancerBonusTokenAccount: null,
+            bonusMint: null,
+            feeTreasuryTokenAccount: treasury,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+      }
+
+      await withdraw(pda, vault);
+
+      expect(await balance(treasury)).to.equal(33);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 968 + 7);
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.feesCollected.toNumber()).to.equal(33);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("floors the fee on the freelancer's share of a split", async () => {
+      const config = await program.account.config.fetch(configPDA);
+      const treasury = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        config.feeTreasury,
+        Keypair.generate()
+      );
+      const { pda, vault } = await createFundedContract([999], defaultTerms({ feeBps: 250 }));
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      await program.methods
+        .resolveDispute(0, { split: [50] }, Array(32).fill(6), Array(32).fill(8))
+        .accounts({
+          contract: pda,
+          arbitrator: arbitrator.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: treasury,
+          restitutionTokenAccount: null,
+          arbitratorTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+          arbitratorProfile: arbitratorPDA(arbitrator.publicKey),
+        })
+        .signers([arbitrator])
+        .rpc();
+      await withdraw(pda, vault);
+
+      // Freelancer share is 499; 250 bps of it is 12.475, floored to 12
+      expect(await balance(treasury)).to.equal(12);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 487);
+    });
+
+    it("splits an approval exactly at 250 bps", async () => {
+      const config = await program.account.config.fetch(configPDA);
+      const treasury = await createAccount(provider.connection, client, tokenMint, config.feeTreasury, Keypair.generate());
+      const { pda, vault } = await createFundedContract([1000], defaultTerms({ feeBps: 250 }));
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await program.methods
+        .approveMilestone(0)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: treasury,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+
+      await withdraw(pda, vault);
+
+      expect(await balance(treasury)).to.equal(25);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 975);
+      // The fee counts towards the release, so the contract still completes
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.releasedAmount.toNumber()).to.equal(1000);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("rejects a fee above 1000 bps", async () => {
+      try {
+        await createFundedContract([1000], defaultTerms({ feeBps: 1001 }));
+        expect.fail("should have capped the fee");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFee");
+      }
+    });
+
+    it("rejects a release without the treasury account when a fee is due", async () => {
+      const { pda, vault } = await createFundedContract([1000], defaultTerms({ feeBps: 100 }));
+      await submit(pda, 0);
+      try {
+        await approve(pda, vault, 0);
+        expect.fail("should have required the fee treasury account");
+      } catch (err) {
+        expect(err.toString()).to.include("FeeAccountMissing");
+      }
+    });
+  });
+
+  describe("blocklist", () => {
+    async function blockAddress(address: PublicKey) {
+      await program.methods
+        .blockAddress(address)
+        .accounts({
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+          blocked: blockedPDA(address),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    async function createBetween(payer: Keypair, freelancerKey: PublicKey) {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: payer.publicKey,
+          freelancer: freelancerKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(payer.publicKey),
+          freelancerBlock: blockedPDA(freelancerKey),
+          clientKyc: kycPDA(payer.publicKey),
+          freelancerKyc: kycPDA(freelancerKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+    }
+
+    it("rejects a blocked client", async () => {
+      const blockedClient = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(blockedClient.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(sig);
+      await blockAddress(blockedClient.publicKey);
+
+      try {
+        await createBetween(blockedClient, freelancer.publicKey);
+        expect.fail("should have rejected a blocked client");
+      } catch (err) {
+        expect(err.toString()).to.include("AddressBlocked");
+      }
+    });
+
+    it("rejects a blocked freelancer until unblocked", async () => {
+      const blockedFreelancer = Keypair.generate().publicKey;
+      await blockAddress(blockedFreelancer);
+
+      try {
+        await createBetween(client, blockedFreelancer);
+        expect.fail("should have rejected a blocked freelancer");
+      } catch (err) {
+        expect(err.toString()).to.include("AddressBlocked");
+      }
+
+      await program.methods
+        .unblockAddress(blockedFreelancer)
+        .accounts({
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+          blocked: blockedPDA(blockedFreelancer),
+        })
+        .rpc();
+      await createBetween(client, blockedFreelancer);
+    });
+
+    it("only lets the admin block addresses", async () => {
+      const target = Keypair.generate().publicKey;
+      try {
+        await program.methods
+          .blockAddress(target)
+          .accounts({
+            config: configPDA,
+            admin: client.publicKey,
+            blocked: blockedPDA(target),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected a non-admin");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("description hash lookup", () => {
+    // Matches DESCRIPTION_HASH_OFFSET in the program
+    const DESCRIPTION_HASH_OFFSET = 8 + 8 + 32 * 4;
+
+    it("stores the description hash at a stable offset for memcmp filters", async () => {
+      const descriptionHash = hashDescription(`Spec ${Date.now()}`);
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], descriptionHash, defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+
+      const info = await provider.connection.getAccountInfo(pda);
+      const stored = info.data.subarray(DESCRIPTION_HASH_OFFSET, DESCRIPTION_HASH_OFFSET + 32);
+      expect(Array.from(stored)).to.deep.equal(descriptionHash);
+
+      const matches = await program.account.contract.all([
+        {
+          memcmp: {
+            offset: DESCRIPTION_HASH_OFFSET,
+            bytes: anchor.utils.bytes.bs58.encode(Buffer.from(descriptionHash)),
+          },
+        },
+      ]);
+      expect(matches.map((m) => m.publicKey.toBase58())).to.deep.equal([pda.toBase58()]);
+    });
+  });
+
+  describe("milestones_with_status", () => {
+    it("returns the indices of milestones in a given state", async () => {
+      const { pda } = await createFundedContract([10, 20, 30, 40]);
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await submit(pda, 2);
+      await raiseDispute(pda, 1);
+
+      const submitted = await program.methods
+        .milestonesWithStatus({ submitted: {} })
+        .accounts({ contract: pda })
+        .view();
+      expect(Buffer.from(submitted).toJSON().data).to.deep.equal([0, 2]);
+
+      const disputed = await program.methods
+        .milestonesWithStatus({ disputed: {} })
+        .accounts({ contract: pda })
+        .view();
+      expect(Buffer.from(disputed).toJSON().data).to.deep.equal([1]);
+    });
+  });
+
+  describe("contract_summary", () => {
+    it("returns the remaining balance, status counts and funding", async () => {
+      const { pda, vault } = await createFundedContract([10, 20, 30, 40]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await submit(pda, 1);
+      await submit(pda, 2);
+      await raiseDispute(pda, 2);
+
+      const summary = await program.methods.contractSummary().accounts({ contract: pda }).view();
+      expect(summary.remaining.toNumber()).to.equal(90);
+      expect(summary.fullyFunded).to.equal(true);
+      // Pending, Submitted, Approved, Rejected, Disputed, then the rest
+      expect(summary.statusCounts.slice(0, 5)).to.deep.equal([1, 1, 1, 0, 1]);
+      expect(summary.statusCounts.reduce((a: number, b: number) => a + b, 0)).to.equal(4);
+    });
+  });
+
+  describe("freelancer-proposed milestones", () => {
+    async function createDraft(total: number): Promise<PublicKey> {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(total), [], Array(32).fill(3), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      return pda;
+    }
+
+    async function propose(pda: PublicKey, milestones: ReturnType<typeof milestone>[]) {
+      await program.methods
+        .proposeMilestones(milestones)
+        .accounts({ contract: pda, freelancer: freelancer.publicKey, config: configPDA })
+        .signers([freelancer])
+        .rpc();
+    }
+
+    it("activates a contract once the client accepts the freelancer's breakdown", async () => {
+      const pda = await createDraft(300);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ draft: {} });
+
+      // The freelancer may revise before acceptance
+      await propose(pda, [milestone(300, "Everything")]);
+      await propose(pda, [milestone(100, "Design"), milestone(200, "Build")]);
+
+      await program.methods
+        .acceptMilestones()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          config: configPDA,
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+        })
+        .signers([client])
+        .rpc();
+
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+      expect(contract.milestones.map((m) => m.amount.toNumber())).to.deep.equal([100, 200]);
+
+      // Accepted milestones are locked
+      try {
+        await propose(pda, [milestone(300, "Changed")]);
+        expect.fail("should have locked the milestones");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotDraft");
+      }
+    });
+
+    it("rejects a proposal that does not add up to the total", async () => {
+      const pda = await createDraft(300);
+      try {
+        await propose(pda, [milestone(100, "Design"), milestone(100, "Build")]);
+        expect.fail("should have rejected the proposal");
+      } catch (err) {
+        expect(err.toString()).to.include("AmountMismatch");
+      }
+    });
+
+    it("cannot be accepted before a proposal exists", async () => {
+      const pda = await createDraft(300);
+      try {
+        await program.methods
+          .acceptMilestones()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            config: configPDA,
+            clientKyc: kycPDA(client.publicKey),
+            freelancerKyc: kycPDA(freelancer.publicKey),
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required a proposal");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidMilestones");
+      }
+    });
+  });
+
+  describe("vault closure on completion", () => {
+    it("sweeps dust to the client and closes the vault once the payout is withdrawn", async () => {
+      const { pda, vault } = await createFundedContract([500]);
+      // Leave a few tokens of dust in the vault beyond the contract total
+      await mintTo(provider.connection, client, tokenMint, vault, client, 3);
+      const vaultRent = (await provider.connection.getAccountInfo(vault)).lamports;
+      const clientTokensBefore = await balance(clientTokenAccount);
+
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      // Completion only credits the freelancer, so the payout waits in the vault
+      expect(await balance(clientTokenAccount)).to.equal(clientTokensBefore + 3);
+      expect(await balance(vault)).to.equal(500);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(500);
+
+      // The freelancer pays the withdrawal's fee, so the client gains the full rent
+      const clientLamportsBefore = await provider.connection.getBalance(client.publicKey);
+      await withdraw(pda, vault);
+
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+      expect(await provider.connection.getBalance(client.publicKey)).to.equal(clientLamportsBefore + vaultRent);
+    });
+  });
+
+  describe("rejection contest window", () => {
+    it("lets the freelancer escalate a rejection within the window", async () => {
+      const { pda } = await createFundedContract(
+        [100],
+        defaultTerms({ rejectionContestWindow: new BN(60) })
+      );
+      await submit(pda, 0);
+      await reject(pda, 0);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ rejected: {} });
+
+      // Only the freelancer can contest
+      try {
+        await raiseDispute(pda, 0, client);
+        expect.fail("should have rejected a client contest");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      await raiseDispute(pda, 0, freelancer);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ disputed: {} });
+    });
+
+    it("lets the rejection stand once the window has passed", async () => {
+      const { pda } = await createFundedContract(
+        [100],
+        defaultTerms({ rejectionContestWindow: new BN(1) })
+      );
+      await submit(pda, 0);
+      await reject(pda, 0);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      try {
+        await raiseDispute(pda, 0, freelancer);
+        expect.fail("should have closed the contest window");
+      } catch (err) {
+        expect(err.toString()).to.include("ContestWindowClosed");
+      }
+    });
+  });
+
+  describe("tips", () => {
+    let treasury: PublicKey;
+
+    before(async () => {
+      const config = await program.account.config.fetch(configPDA);
+      treasury = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        config.feeTreasury,
+        Keypair.generate()
+      );
+    });
+
+    async function tip(pda: PublicKey, index: number, amount: number) {
+      await program.methods
+        .addTip(index, new BN(amount))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          clientTokenAccount: clientTokenAccount,
+          freelancerTokenAccount: freelancerTokenAccount,
+          feeTreasuryTokenAccount: treasury,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+    }
+
+    it("delivers a tip in full while the release pays the fee", async () => {
+      const { pda, vault } = await createFundedContract([1000, 1000], defaultTerms({ feeBps: 1000 }));
+      await submit(pda, 0);
+
+      let freelancerBefore = await balance(freelancerTokenAccount);
+      let treasuryBefore = await balance(treasury);
+      await approveWithFee(pda, vault, 0, treasury);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 900);
+      expect(await balance(treasury)).to.equal(treasuryBefore + 100);
+
+      freelancerBefore = await balance(freelancerTokenAccount);
+      treasuryBefore = await balance(treasury);
+      await tip(pda, 0, 500);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 500);
+      expect(await balance(treasury)).to.equal(treasuryBefore);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].tipAmount.toNumber()).to.equal(500);
+    });
+
+    it("charges the fee on tips when configured", async () => {
+      const { pda, vault } = await createFundedContract(
+        [1000, 1000],
+        defaultTerms({ feeBps: 1000, feeOnTips: true })
+      );
+      await submit(pda, 0);
+      await approveWithFee(pda, vault, 0, treasury);
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      const treasuryBefore = await balance(treasury);
+      await tip(pda, 0, 500);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 450);
+      expect(await balance(treasury)).to.equal(treasuryBefore + 50);
+    });
+
+    it("only tips approved milestones", async () => {
+      const { pda } = await createFundedContract([1000]);
+      try {
+        await tip(pda, 0, 500);
+        expect.fail("should have required an approved milestone");
+      } catch (err) {
+        expect(err.toString()).to.include("MilestoneNotApproved");
+      }
+    });
+  });
+
+  describe("top_up_escrow", () => {
+    it("tops a partial funding up to exactly the total and rejects an overshoot", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(60, "First"), milestone(40, "Second")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+
+      try {
+        await deposit("fundEscrow", pda, vault, 101);
+        expect.fail("should have rejected an overshoot on the first deposit");
+      } catch (err) {
+        expect(err.toString()).to.include("OverFunded");
+      }
+
+      await deposit("fundEscrow", pda, vault, 60);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(60);
+
+      // The funded milestone can be released; the unfunded one cannot
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await approve(pda, vault, 0);
+      try {
+        await approve(pda, vault, 1);
+        expect.fail("should have required funding");
+      } catch (err) {
+        expect(err.toString()).to.include("EscrowUnderfunded");
+      }
+
+      try {
+        await deposit("topUpEscrow", pda, vault, 41);
+        expect.fail("should have rejected an overshoot");
+      } catch (err) {
+        expect(err.toString()).to.include("OverFunded");
+      }
+
+      await deposit("topUpEscrow", pda, vault, 40);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(100);
+
+      await approve(pda, vault, 1);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("keeps initial funding separate from top-ups", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      try {
+        await deposit("fundEscrow", pda, vault, 1);
+        expect.fail("should have rejected a second initial funding");
+      } catch (err) {
+        expect(err.toString()).to.include("AlreadyFunded");
+      }
+    });
+  });
+
+  describe("resolution finality window", () => {
+    async function settle(pda: PublicKey, vault: PublicKey, index: number) {
+      await program.methods
+        .settleResolution(index)
+        .accounts({
+          contract: pda,
+          payer: provider.wallet.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          restitutionTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .rpc();
+    }
+
+    it("holds the payout until the window passes, then settles", async () => {
+      const { pda, vault } = await createFundedContract(
+        [100, 100],
+        defaultTerms({ resolutionFinalityWindow: new BN(2) })
+      );
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ resolutionPending: {} });
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+
+      try {
+        await settle(pda, vault, 0);
+        expect.fail("should have waited for the finality window");
+      } catch (err) {
+        expect(err.toString()).to.include("FinalityWindowOpen");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await settle(pda, vault, 0);
+
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(100);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+    });
+
+    it("lets an appeal within the window block settlement", async () => {
+      const { pda, vault } = await createFundedContract(
+        [100, 100],
+        defaultTerms({ resolutionFinalityWindow: new BN(60) })
+      );
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      await program.methods
+        .appealResolution(0)
+        .accounts({
+          contract: pda,
+          authority: client.publicKey,
+          appellantTokenAccount: null,
+          escrowVault: null,
+          tokenMint: null,
+          tokenProgram: null,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ disputed: {} });
+      expect(contract.milestones[0].pendingDecision).to.be.null;
+
+      try {
+        await settle(pda, vault, 0);
+        expect.fail("should have been frozen by the appeal");
+      } catch (err) {
+        expect(err.toString()).to.include("NoPendingResolution");
+      }
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+    });
+
+    it("allows one appeal per dispute", async () => {
+      const { pda, vault } = await createFundedContract(
+        [100, 100],
+        defaultTerms({ resolutionFinalityWindow: new BN(60) })
+      );
+      const appeal = () =>
+        program.methods
+          .appealResolution(0)
+          .accounts({
+            contract: pda,
+            authority: client.publicKey,
+            appellantTokenAccount: null,
+            escrowVault: null,
+            tokenMint: null,
+            tokenProgram: null,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+      await appeal();
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      try {
+        await appeal();
+        expect.fail("should have refused a second appeal");
+      } catch (err) {
+        expect(err.toString()).to.include("AppealLimitReached");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ resolutionPending: {} });
+      expect(contract.milestones[0].appealCount).to.equal(1);
+    });
+  });
+
+  describe("low escrow warnings", () => {
+    it("warns with the shortfall when submitting against an underfunded escrow", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(30, "First"), milestone(70, "Second")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+      await program.methods
+        .fundEscrow(new BN(50))
+        .accounts({
+          contract: pda,
+          funder: client.publicKey,
+          funderTokenAccount: clientTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+
+      const warnings = [];
+      const listener = program.addEventListener("LowEscrowWarning", (event) => {
+        if (event.contractId.eq(id)) warnings.push(event);
+      });
+
+      // 30 fits within the 50 funded; 70 exceeds it by 20
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+      await program.removeEventListener(listener);
+
+      expect(warnings.length).to.equal(1);
+      expect(warnings[0].milestoneIndex).to.equal(1);
+      expect(warnings[0].shortfall.toNumber()).to.equal(20);
+    });
+  });
+
+  describe("minimum review period", () => {
+    it("rejects approval before the review period and accepts it after", async () => {
+      const { pda, vault } = await createFundedContract([
+        milestone(100, "Reviewed", { minReviewSeconds: new BN(2) }),
+      ]);
+      await submit(pda, 0);
+
+      try {
+        await approve(pda, vault, 0);
+        expect.fail("should have enforced the review period");
+      } catch (err) {
+        expect(err.toString()).to.include("ReviewPeriodNotElapsed");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await approve(pda, vault, 0);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+    });
+  });
+
+  describe("milestone status bitmap", () => {
+    // Matches MILESTONE_BITMAP_OFFSET in the program
+    const MILESTONE_BITMAP_OFFSET = 8 + 8 + 32 * 4 + 32;
+
+    async function bitmap(pda: PublicKey): Promise<number> {
+      const contract = await program.account.contract.fetch(pda);
+      const info = await provider.connection.getAccountInfo(pda);
+      // The raw bytes at the stable offset agree with the decoded field
+      expect(info.data.readUInt32LE(MILESTONE_BITMAP_OFFSET)).to.equal(contract.milestoneStatusBitmap);
+      return contract.milestoneStatusBitmap;
+    }
+
+    it("tracks settled milestones through transitions", async () => {
+      const { pda, vault } = await createFundedContract([10, 20, 30]);
+      expect(await bitmap(pda)).to.equal(0b000);
+
+      await submit(pda, 0);
+      expect(await bitmap(pda)).to.equal(0b000);
+      await approve(pda, vault, 0);
+      expect(await bitmap(pda)).to.equal(0b001);
+
+      await program.methods
+        .revokeMilestone(2)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+      expect(await bitmap(pda)).to.equal(0b101);
+
+      await submit(pda, 1);
+      await raiseDispute(pda, 1);
+      await resolveDispute(pda, vault, 1, { split: [50] });
+      expect(await bitmap(pda)).to.equal(0b111);
+
+      // Fully settled three-milestone contracts can be found with memcmp
+      const bits = Buffer.alloc(4);
+      bits.writeUInt32LE(0b111);
+      const matches = await program.account.contract.all([
+        { memcmp: { offset: MILESTONE_BITMAP_OFFSET, bytes: anchor.utils.bytes.bs58.encode(bits) } },
+      ]);
+      expect(matches.map((m) => m.publicKey.toBase58())).to.include(pda.toBase58());
+    });
+  });
+
+  describe("fees in a separate token", () => {
+    // Fixture price accounts loaded by Anchor.toml
+    const paymentPriceFeed = new PublicKey("8ZgsPqNqF7ugBfRV8bQe1WDDhLLkw31yWXVAQNo6AThr");
+    const feePriceFeed = new PublicKey("8ZgsPqNqF7ugBfRV8bQdbV4X8aY5hxyunU7nthBJEtzt");
+    let feeMint: PublicKey;
+    let clientFeeAccount: PublicKey;
+    let treasuryFeeAccount: PublicKey;
+
+    async function setFeeToken(maxPriceAgeSecs: number | null) {
+      const feeToken =
+        maxPriceAgeSecs === null
+          ? null
+          : { mint: feeMint, paymentPriceFeed, feePriceFeed, maxPriceAgeSecs: new BN(maxPriceAgeSecs) };
+      await program.methods
+        .setFeeToken(feeToken)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    }
+
+    async function collect(pda: PublicKey) {
+      await program.methods
+        .collectTokenFee()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          tokenMint: tokenMint,
+          feeMint,
+          clientFeeTokenAccount: clientFeeAccount,
+          treasuryFeeTokenAccount: treasuryFeeAccount,
+          paymentPriceFeed,
+          feePriceFeed,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+    }
+
+    before(async () => {
+      feeMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      clientFeeAccount = await createAccount(provider.connection, client, feeMint, client.publicKey);
+      const config = await program.account.config.fetch(configPDA);
+      treasuryFeeAccount = await createAccount(
+        provider.connection,
+        client,
+        feeMint,
+        config.feeTreasury,
+        Keypair.generate()
+      );
+      await mintTo(provider.connection, client, feeMint, clientFeeAccount, client, 1_000);
+    });
+
+    after(async () => {
+      await setFeeToken(null);
+    });
+
+    it("leaves releases whole and collects the fee in the fee token", async () => {
+      // The fixture prices are old, so accept any age here
+      await setFeeToken(10 ** 10);
+      const { pda, vault } = await createFundedContract([1000], defaultTerms({ feeBps: 1000 }));
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 1000);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.feesOwed.toNumber()).to.equal(100);
+
+      // 100 payment tokens at 1.00 buy 200 fee tokens at 0.50
+      await collect(pda);
+      expect(await balance(treasuryFeeAccount)).to.equal(200);
+      expect(await balance(clientFeeAccount)).to.equal(800);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.feesOwed.toNumber()).to.equal(0);
+      expect(contract.feesCollected.toNumber()).to.equal(100);
+    });
+
+    it("refuses stale oracle prices", async () => {
+      await setFeeToken(60);
+      const { pda, vault } = await createFundedContract([1000], defaultTerms({ feeBps: 1000 }));
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      try {
+        await collect(pda);
+        expect.fail("should have refused a stale price");
+      } catch (err) {
+        expect(err.toString()).to.include("StalePrice");
+      }
+    });
+  });
+
+  describe("cancel_contract ordering", () => {
+    async function cancel(pda: PublicKey, vault: PublicKey, tokenProgram: PublicKey) {
+      await program.methods
+        .cancelContract()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram,
+        })
+        .signers([client, freelancer])
+        .rpc();
+    }
+
+    it("refuses a substitute token program that could re-enter", async () => {
+      const callbackProgram = anchor.workspace.CallbackMock as Program<CallbackMock>;
+      const { pda, vault } = await createFundedContract([100]);
+
+      try {
+        await cancel(pda, vault, callbackProgram.programId);
+        expect.fail("should have refused a substitute token program");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidProgramId");
+      }
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      await cancel(pda, vault, TOKEN_PROGRAM_ID);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ cancelled: {} });
+
+      // The terminal status blocks any second refund
+      try {
+        await cancel(pda, vault, TOKEN_PROGRAM_ID);
+        expect.fail("should have refused a second cancel");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotActive");
+      }
+    });
+  });
+
+  describe("multi-sig cancellation", () => {
+    it("requires two approvals before a cancel refund executes", async () => {
+      const guarantor = Keypair.generate();
+      const { pda, vault } = await createFundedContract(
+        [100],
+        defaultTerms({ cancelApprovers: [client.publicKey, guarantor.publicKey], cancelThreshold: 2 })
+      );
+
+      const cancel = () =>
+        program.methods
+          .cancelContract()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client, freelancer])
+          .rpc();
+      const approveCancellation = (approver: Keypair) =>
+        program.methods
+          .approveCancellation()
+          .accounts({ contract: pda, approver: approver.publicKey })
+          .signers([approver])
+          .rpc();
+
+      try {
+        await cancel();
+        expect.fail("should have required approvals");
+      } catch (err) {
+        expect(err.toString()).to.include("CancelApprovalsRequired");
+      }
+
+      // A repeated approval only counts once
+      await approveCancellation(client);
+      await approveCancellation(client);
+      try {
+        await cancel();
+        expect.fail("should have required a second approval");
+      } catch (err) {
+        expect(err.toString()).to.include("CancelApprovalsRequired");
+      }
+
+      try {
+        await approveCancellation(freelancer);
+        expect.fail("should have rejected a non-approver");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      await approveCancellation(guarantor);
+      const before = await balance(clientTokenAccount);
+      await cancel();
+      expect(await balance(clientTokenAccount)).to.equal(before + 100);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ cancelled: {} });
+    });
+
+    it("rejects a threshold above the number of approvers", async () => {
+      try {
+        await createFundedContract(
+          [100],
+          defaultTerms({ cancelApprovers: [client.publicKey], cancelThreshold: 2 })
+        );
+        expect.fail("should have rejected the threshold");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidCancelThreshold");
+      }
+    });
+  });
+
+  describe("contract pause", () => {
+    const pauseAs = (pda: PublicKey, authority: Keypair, duration: number) =>
+      program.methods
+        .requestPause(new BN(duration))
+        .accounts({ contract: pda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+    const approvePauseAs = (pda: PublicKey, authority: Keypair) =>
+      program.methods
+        .approvePause()
+        .accounts({ contract: pda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+    it("blocks submissions and approvals and extends deadlines", async () => {
+      const dueDate = Math.floor(Date.now() / 1000) + 86_400;
+      const { pda, vault } = await createFundedContract(
+        [milestone(40, "Before leave"), milestone(60, "After leave", { dueDate: new BN(dueDate) })],
+        defaultTerms({ deadline: new BN(dueDate) })
+      );
+      await submit(pda, 0);
+
+      await pauseAs(pda, freelancer, 2);
+      try {
+        await approvePauseAs(pda, freelancer);
+        expect.fail("requester should not approve their own pause");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      await approvePauseAs(pda, client);
+
+      const paused = await program.account.contract.fetch(pda);
+      expect(paused.pauseRequestedBy).to.be.null;
+      expect(paused.milestones[1].dueDate.toNumber()).to.equal(dueDate + 2);
+      expect(paused.terms.deadline.toNumber()).to.equal(dueDate + 2);
+
+      try {
+        await approve(pda, vault, 0);
+        expect.fail("approval should be blocked while paused");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractPaused");
+      }
+      try {
+        await submit(pda, 1);
+        expect.fail("submission should be blocked while paused");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractPaused");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await approve(pda, vault, 0);
+      await submit(pda, 1);
+      const resumed = await program.account.contract.fetch(pda);
+      expect(resumed.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(resumed.milestones[1].status).to.deep.equal({ submitted: {} });
+    });
+
+    it("requires a pending request to approve", async () => {
+      const { pda } = await createFundedContract([100]);
+      try {
+        await approvePauseAs(pda, client);
+        expect.fail("should have required a request");
+      } catch (err) {
+        expect(err.toString()).to.include("NoPauseRequest");
+      }
+    });
+  });
+
+  describe("reconcile_accounting", () => {
+    it("restores funding that bypassed the program", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(40, "First"), milestone(60, "Second")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+      await program.methods
+        .fundEscrow(new BN(50))
+        .accounts({
+          contract: pda,
+          funder: client.publicKey,
+          funderTokenAccount: clientTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      // The client sends the rest straight to the vault instead of topping up,
+      // so the vault holds 60 beyond the unclaimed payout while the contract
+      // still thinks 50 was funded
+      await transfer(provider.connection, client, clientTokenAccount, vault, client, 50);
+      await submit(pda, 1);
+      try {
+        await approve(pda, vault, 1);
+        expect.fail("should have seen the escrow as underfunded");
+      } catch (err) {
+        expect(err.toString()).to.include("EscrowUnderfunded");
+      }
+
+      const reconcile = (authority: Keypair) =>
+        program.methods
+          .reconcileAccounting()
+          .accounts({ contract: pda, config: configPDA, authority: authority.publicKey, escrowVault: vault })
+          .signers([authority])
+          .rpc();
+
+      try {
+        await reconcile(client);
+        expect.fail("should have rejected the client");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      const events = [];
+      const listener = program.addEventListener("AccountingReconciled", (event) => {
+        if (event.contractId.eq(id)) events.push(event);
+      });
+      await reconcile(arbitrator);
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+      await program.removeEventListener(listener);
+
+      expect(events.length).to.equal(1);
+      expect(events[0].fundedBefore.toNumber()).to.equal(50);
+      expect(events[0].fundedAfter.toNumber()).to.equal(100);
+      expect(events[0].releasedBefore.toNumber()).to.equal(40);
+      expect(events[0].releasedAfter.toNumber()).to.equal(40);
+
+      await approve(pda, vault, 1);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+  });
+
+  describe("KYC gating", () => {
+    const kycIssuer = Keypair.generate();
+    const oneYear = 365 * 86_400;
+
+    const setKycAuthority = (authority: PublicKey) =>
+      program.methods
+        .setKycAuthority(authority)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+
+    async function issue(subject: PublicKey, expiresAt: number) {
+      await program.methods
+        .issueKycAttestation(subject, new BN(expiresAt))
+        .accounts({
+          config: configPDA,
+          kycAuthority: kycIssuer.publicKey,
+          attestation: kycPDA(subject),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([kycIssuer])
+        .rpc();
+    }
+
+    async function createGated(freelancerKey: PublicKey) {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms({ requireKyc: true }))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancerKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancerKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancerKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      return pda;
+    }
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(kycIssuer.publicKey, LAMPORTS_PER_SOL);
+      await provider.connection.confirmTransaction(sig);
+      await setKycAuthority(kycIssuer.publicKey);
+      await issue(client.publicKey, Math.floor(Date.now() / 1000) + oneYear);
+    });
+
+    after(async () => {
+      await setKycAuthority(provider.wallet.publicKey);
+    });
+
+    it("rejects a gated contract when an attestation is missing", async () => {
+      try {
+        await createGated(Keypair.generate().publicKey);
+        expect.fail("should have required the freelancer's attestation");
+      } catch (err) {
+        expect(err.toString()).to.include("KycMissing");
+      }
+    });
+
+    it("creates a gated contract when both parties are attested", async () => {
+      const attested = Keypair.generate().publicKey;
+      await issue(attested, Math.floor(Date.now() / 1000) + oneYear);
+      const pda = await createGated(attested);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.terms.requireKyc).to.equal(true);
+    });
+
+    it("rejects an expired attestation", async () => {
+      const lapsed = Keypair.generate().publicKey;
+      await issue(lapsed, Math.floor(Date.now() / 1000) - 60);
+      try {
+        await createGated(lapsed);
+        expect.fail("should have rejected the expired attestation");
+      } catch (err) {
+        expect(err.toString()).to.include("KycExpired");
+      }
+    });
+  });
+
+  describe("display currency metadata", () => {
+    const EUR = Array.from(Buffer.from("EUR"));
+
+    it("stores the display currency and snapshot rate", async () => {
+      const { pda } = await createFundedContract([
+        milestone(100, "Priced in euros", { displayCurrency: EUR, displayRate: new BN(920_000) }),
+        milestone(50, "No display currency"),
+      ]);
+      const contract = await program.account.contract.fetch(pda);
+      expect(Buffer.from(contract.milestones[0].displayCurrency).toString()).to.equal("EUR");
+      expect(contract.milestones[0].displayRate.toNumber()).to.equal(920_000);
+      expect(contract.milestones[1].displayCurrency).to.be.null;
+    });
+
+    it("rejects malformed display metadata", async () => {
+      const invalid = [
+        { displayCurrency: Array.from(Buffer.from("eur")), displayRate: new BN(920_000) },
+        { displayCurrency: EUR, displayRate: new BN(0) },
+        { displayCurrency: null, displayRate: new BN(920_000) },
+      ];
+      for (const overrides of invalid) {
+        try {
+          await createFundedContract([milestone(100, "Bad display", overrides)]);
+          expect.fail("should have rejected the display metadata");
+        } catch (err) {
+          expect(err.toString()).to.include("InvalidDisplayCurrency");
+        }
+      }
+    });
+  });
+
+  describe("approval after completion", () => {
+    it("reports that the contract is already completed", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      // Completion closed the vault, so stand in another live token account
+      try {
+        await approve(pda, escrowVault, 0);
+        expect.fail("should have rejected the stale approval");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractAlreadyCompleted");
+      }
+    });
+  });
+
+  describe("missed deadline flagging", () => {
+    const bystander = Keypair.generate();
+    const flag = (pda: PublicKey, index: number) =>
+      program.methods
+        .flagMissedDeadline(index)
+        .accounts({ contract: pda, caller: bystander.publicKey })
+        .signers([bystander])
+        .rpc();
+
+    it("halts a missed milestone until the freelancer explains", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      const { pda } = await createFundedContract(
+        [
+          milestone(60, "Overdue"),
+          milestone(40, "On time", { dueDate: new BN(now + 86_400) }),
+        ],
+        defaultTerms({ flagMissedDeadlines: true })
+      );
+      await setDueDate(pda, 0, now - 3600);
+
+      try {
+        await flag(pda, 1);
+        expect.fail("should not flag a milestone before its due date");
+      } catch (err) {
+        expect(err.toString()).to.include("DeadlineNotMissed");
+      }
+
+      await flag(pda, 0);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ flagged: {} });
+
+      try {
+        await submit(pda, 0);
+        expect.fail("should block submission until explained");
+      } catch (err) {
+        expect(err.toString()).to.include("MilestoneNotPending");
+      }
+
+      await program.methods
+        .explainMissedDeadline(0, Array(32).fill(4))
+        .accounts({ contract: pda, freelancer: freelancer.publicKey })
+        .signers([freelancer])
+        .rpc();
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ pending: {} });
+      expect(contract.milestones[0].deadlineExplanation).to.deep.equal(Array(32).fill(4));
+
+      await submit(pda, 0);
+    });
+
+    it("is off unless the contract opts in", async () => {
+      const { pda } = await createFundedContract([milestone(100, "Overdue")]);
+      await setDueDate(pda, 0, Math.floor(Date.now() / 1000) - 3600);
+      try {
+        await flag(pda, 0);
+        expect.fail("should require the option");
+      } catch (err) {
+        expect(err.toString()).to.include("DeadlineFlaggingDisabled");
+      }
+    });
+  });
+
+  describe("SOL escrow", () => {
+    const swapProgram = anchor.workspace.SwapMock as Program<SwapMock>;
+    const [poolAuthority] = PublicKey.findProgramAddressSync([Buffer.from("pool")], swapProgram.programId);
+    let poolTokenAccount: PublicKey;
+
+    const solVaultPDA = (pda: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("sol_vault"), pda.toBuffer()], program.programId)[0];
+
+    async function createSolContract(
+      lamports: number[],
+      terms = defaultTerms({ swapProgram: swapProgram.programId })
+    ): Promise<PublicKey> {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const milestones = lamports.map((l, i) => milestone(l, `Milestone ${i}`));
+      const total = lamports.reduce((sum, l) => sum + l, 0);
+      await program.methods
+        .createContract(id, new BN(total), milestones, Array(32).fill(1), terms)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      await program.methods
+        .fundEscrowSol(new BN(total))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          solVault: solVaultPDA(pda),
+          systemProgram: SystemProgram.programId,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      return pda;
+    }
+
+    function approveSwap(pda: PublicKey, index: number, lamportsIn: number, minAmountOut: number) {
+      const swapData = swapProgram.coder.instruction.encode("swap", { lamportsIn: new BN(lamportsIn) });
+      return program.methods
+        .approveMilestoneSwap(index, new BN(minAmountOut), swapData)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          solVault: solVaultPDA(pda),
+          freelancerTokenAccount: freelancerTokenAccount,
+          swapProgram: swapProgram.programId,
+          config: configPDA,
+        })
+        .remainingAccounts([
+          { pubkey: solVaultPDA(pda), isSigner: false, isWritable: true },
+          { pubkey: poolAuthority, isSigner: false, isWritable: true },
+          { pubkey: poolTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: freelancerTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ])
+        .signers([client, freelancer])
+        .rpc();
+    }
+
+    before(async () => {
+      poolTokenAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        poolAuthority,
+        Keypair.generate(),
+        TOKEN_PROGRAM_ID
+      );
+      await mintTo(provider.connection, client, tokenMint, poolTokenAccount, client, 1_000_000);
+    });
+
+    it("swaps each release from SOL into the payment token", async () => {
+      // The mock pays one token unit per 1_000 lamports
+      const pda = await createSolContract([60_000_000, 40_000_000]);
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(100_000_000);
+
+      await submit(pda, 0);
+      try {
+        await approveSwap(pda, 0, 60_000_000, 60_001);
+        expect.fail("should have enforced the minimum amount out");
+      } catch (err) {
+        expect(err.toString()).to.include("SlippageExceeded");
+      }
+      try {
+        await approveSwap(pda, 0, 50_000_000, 0);
+        expect.fail("should have required the full milestone to be swapped");
+      } catch (err) {
+        expect(err.toString()).to.include("SwapAmountMismatch");
+      }
+
+      const before = await balance(freelancerTokenAccount);
+      await approveSwap(pda, 0, 60_000_000, 60_000);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 60_000);
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(40_000_000);
+
+      await submit(pda, 1);
+      await approveSwap(pda, 1, 40_000_000, 40_000);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(0);
+    });
+
+    it("refunds the SOL vault on cancellation", async () => {
+      const pda = await createSolContract([30_000_000]);
+      const cancel = (signers: Keypair[]) =>
+        program.methods
+          .cancelContractSol()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            solVault: solVaultPDA(pda),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers(signers)
+          .rpc();
+
+      // The freelancer has accepted, so the client cannot cancel alone
+      try {
+        await cancel([client]);
+        expect.fail("should have required the freelancer's signature");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(30_000_000);
+
+      const before = await provider.connection.getBalance(client.publicKey);
+      await cancel([client, freelancer]);
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(0);
+      // The provider wallet pays the transaction fee, so the client gets the whole refund
+      expect(await provider.connection.getBalance(client.publicKey)).to.equal(before + 30_000_000);
+    });
+
+    it("lets anyone refund an expired SOL escrow to the client", async () => {
+      const deadline = Math.floor(Date.now() / 1000) + 1;
+      const pda = await createSolContract([30_000_000], defaultTerms({ isNative: true, deadline: new BN(deadline) }));
+      await new Promise((resolve) => setTimeout(resolve, 2500));
+
+      const bystander = Keypair.generate();
+      const before = await provider.connection.getBalance(client.publicKey);
+      await program.methods
+        .cancelExpiredSol()
+        .accounts({
+          contract: pda,
+          caller: bystander.publicKey,
+          client: client.publicKey,
+          solVault: solVaultPDA(pda),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bystander])
+        .rpc();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ cancelled: {} });
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(0);
+      expect(await provider.connection.getBalance(client.publicKey)).to.equal(before + 30_000_000);
+    });
+
+    it("lets the freelancer walk away from a SOL escrow", async () => {
+      const pda = await createSolContract([60_000_000, 40_000_000], defaultTerms({ isNative: true }));
+      await submit(pda, 0);
+      await program.methods
+        .approveMilestoneNative(0)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          solVault: solVaultPDA(pda),
+          systemProgram: SystemProgram.programId,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+
+      // The approved milestone was already paid out; the rest goes back to the client
+      const before = await provider.connection.getBalance(client.publicKey);
+      await program.methods
+        .freelancerWithdrawSol()
+        .accounts({
+          contract: pda,
+          freelancer: freelancer.publicKey,
+          client: client.publicKey,
+          solVault: solVaultPDA(pda),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([freelancer])
+        .rpc();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ cancelled: {} });
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(0);
+      expect(await provider.connection.getBalance(client.publicKey)).to.equal(before + 40_000_000);
+    });
+
+    it("pays native SOL releases straight to the freelancer", async () => {
+      const pda = await createSolContract([60_000_000, 40_000_000], defaultTerms({ isNative: true }));
+      const approveNative = (index: number) =>
+        program.methods
+          .approveMilestoneNative(index)
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            solVault: solVaultPDA(pda),
+            systemProgram: SystemProgram.programId,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+
+      await submit(pda, 0);
+      const before = await provider.connection.getBalance(freelancer.publicKey);
+      await approveNative(0);
+      expect(await provider.connection.getBalance(freelancer.publicKey)).to.equal(before + 60_000_000);
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(40_000_000);
+
+      await submit(pda, 1);
+      await approveNative(1);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(await provider.connection.getBalance(solVaultPDA(pda))).to.equal(0);
+    });
+
+    it("keeps SOL escrows off the token approval and dispute paths", async () => {
+      const pda = await createSolContract([30_000_000], defaultTerms({ isNative: true }));
+      const { escrowVault } = await program.account.contract.fetch(pda);
+      await submit(pda, 0);
+
+      try {
+        await approve(pda, escrowVault, 0);
+        expect.fail("should have required approve_milestone_native");
+      } catch (err) {
+        expect(err.toString()).to.include("SolEscrowOnly");
+      }
+      try {
+        await raiseDispute(pda, 0);
+        expect.fail("should have refused to dispute a SOL escrow");
+      } catch (err) {
+        expect(err.toString()).to.include("SolEscrowOnly");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(0);
+    });
+
+    it("rejects terms combining native payouts with a swap", async () => {
+      try {
+        await createSolContract([10_000_000], defaultTerms({ isNative: true, swapProgram: swapProgram.programId }));
+        expect.fail("should have rejected the terms");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidSwapTerms");
+      }
+    });
+  });
+
+  describe("check_invariants", () => {
+    // Needs a build with the test-mutators feature for corrupt_contract
+    const check = (pda: PublicKey, vault: PublicKey | null) =>
+      program.methods.checkInvariants().accounts({ contract: pda, escrowVault: vault }).rpc();
+
+    it("passes for a healthy contract", async () => {
+      const { pda, vault } = await createFundedContract([40, 60]);
+      await check(pda, vault);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await check(pda, vault);
+    });
+
+    const corruptions: [string, any][] = [
+      ["InvariantReleasedExceedsTotal", { releasedAmount: [new BN(200)] }],
+      ["InvariantMilestoneSum", { milestoneAmount: { index: 0, amount: new BN(50) } }],
+      ["InvariantVaultBalance", { fundedAmount: [new BN(150)] }],
+      ["InvariantStatus", { status: [{ completed: {} }] }],
+    ];
+    for (const [error, corruption] of corruptions) {
+      it(`detects ${error}`, async () => {
+        const { pda, vault } = await createFundedContract([40, 60]);
+        await corruptContract(pda, corruption);
+        try {
+          await check(pda, vault);
+          expect.fail("should have detected the corruption");
+        } catch (err) {
+          expect(err.toString()).to.include(error);
+        }
+      });
+    }
+
+    it("reports InvariantVaultBalance when the expected balance overflows", async () => {
+      const { pda, vault } = await createFundedContract([40, 60]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      const u64Max = new BN("18446744073709551615");
+      await corruptContract(pda, { fundedAmount: [u64Max] });
+      await corruptContract(pda, { releasedAmount: [new BN(0)] });
+      try {
+        await check(pda, vault);
+        expect.fail("should have detected the corruption");
+      } catch (err) {
+        expect(err.toString()).to.include("InvariantVaultBalance");
+      }
+    });
+
+    it("detects InvariantBump", async () => {
+      const { pda, vault } = await createFundedContract([40, 60]);
+      const { bump } = await program.account.contract.fetch(pda);
+      await corruptContract(pda, { bump: [(bump + 1) % 256] });
+      try {
+        await check(pda, vault);
+        expect.fail("should have detected the corruption");
+      } catch (err) {
+        expect(err.toString()).to.include("InvariantBump");
+      }
+    });
+  });
+
+  describe("parallel disputes", () => {
+    it("resolves two simultaneous disputes independently", async () => {
+      const { pda, vault } = await createFundedContract(
+        [30, 70, 100],
+        defaultTerms({ disputeResolutionWindow: new BN(86_400) })
+      );
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await submit(pda, 2);
+      await raiseDispute(pda, 0);
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+      await raiseDispute(pda, 1, freelancer);
+
+      let contract = await program.account.contract.fetch(pda);
+      const [first, second] = contract.milestones;
+      expect(first.disputeDeadline.toNumber()).to.equal(first.disputedAt.toNumber() + 86_400);
+      expect(second.disputeDeadline.toNumber()).to.equal(second.disputedAt.toNumber() + 86_400);
+      expect(second.disputedAt.toNumber()).to.be.greaterThan(first.disputedAt.toNumber());
+      expect(contract.status).to.deep.equal({ disputed: {} });
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await resolveDispute(pda, vault, 1, { split: [50] });
+      await withdraw(pda, vault);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ disputed: {} });
+      expect(contract.milestones[1].status).to.deep.equal({ resolved: {} });
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 35);
+      // Milestone 0 is still contested
+      expect(contract.status).to.deep.equal({ disputed: {} });
+
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.releasedAmount.toNumber()).to.equal(100);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      await approve(pda, vault, 2);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+  });
+
+  describe("clawback window", () => {
+    function settleHeld(method: "claimRelease" | "clawbackRelease", pda: PublicKey, vault: PublicKey, authority: Keypair) {
+      const signers = method === "clawbackRelease" ? [authority, client] : [authority];
+      return program.methods[method](0)
+        .accounts({
+          contract: pda,
+          authority: authority.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers(signers)
+        .rpc();
+    }
+
+    it("lets the client claw back a release within the window", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ clawbackWindow: new BN(60) }));
+      await submit(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await approve(pda, vault, 0);
+
+      // The payout is held, not sent
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.heldTotal.toNumber()).to.equal(100);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      try {
+        await settleHeld("claimRelease", pda, vault, freelancer);
+        expect.fail("should not be claimable inside the window");
+      } catch (err) {
+        expect(err.toString()).to.include("ClawbackWindowOpen");
+      }
+
+      const clientBefore = await balance(clientTokenAccount);
+      await settleHeld("clawbackRelease", pda, vault, arbitrator);
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 100);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ clawedBack: {} });
+      expect(contract.refundedAmount.toNumber()).to.equal(100);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("makes the release final once the window closes", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ clawbackWindow: new BN(2) }));
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      try {
+        await settleHeld("clawbackRelease", pda, vault, arbitrator);
+        expect.fail("should have refused a late clawback");
+      } catch (err) {
+        expect(err.toString()).to.include("ClawbackWindowClosed");
+      }
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await settleHeld("claimRelease", pda, vault, freelancer);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("holds a payout decided for the freelancer through the window", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ clawbackWindow: new BN(60) }));
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(0);
+      expect(contract.heldTotal.toNumber()).to.equal(100);
+
+      const clientBefore = await balance(clientTokenAccount);
+      await settleHeld("clawbackRelease", pda, vault, arbitrator);
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 100);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ clawedBack: {} });
+    });
+  });
+
+  describe("escrow vault PDA", () => {
+    it("creates the vault with the contract at its derived address", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+
+      const [vault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.escrowVault.toBase58()).to.equal(vault.toBase58());
+      const vaultAccount = await getAccount(provider.connection, vault);
+      expect(vaultAccount.owner.toBase58()).to.equal(pda.toBase58());
+      expect(vaultAccount.mint.toBase58()).to.equal(tokenMint.toBase58());
+      expect(vaultAccount.amount).to.equal(BigInt(0));
+
+      const fund = (vault: PublicKey) =>
+        program.methods
+          .fundEscrow(new BN(100))
+          .accounts({
+            contract: pda,
+            funder: client.publicKey,
+            funderTokenAccount: clientTokenAccount,
+            escrowVault: vault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+
+      // Any other account owned by the contract is refused
+      const stray = await createAccount(provider.connection, client, tokenMint, pda, Keypair.generate(), TOKEN_PROGRAM_ID);
+      try {
+        await fund(stray);
+        expect.fail("should have required the contract's vault");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidVault");
+      }
+
+      await fund(vault);
+      expect(await balance(vault)).to.equal(100);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(100);
+    });
+  });
+
+  describe("relayed signed approvals", () => {
+    async function approveSigned(
+      pda: PublicKey,
+      vault: PublicKey,
+      index: number,
+      signer: Keypair,
+      refundAccount = clientTokenAccount,
+      message?: Buffer
+    ) {
+      return program.methods
+        .approveMilestoneSigned(index)
+        .accounts({
+          contract: pda,
+          relayer: provider.wallet.publicKey,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: refundAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          config: configPDA,
+        })
+        .preInstructions([
+          Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: signer.secretKey,
+            message: message ?? (await approvalMessage(pda, index)),
+          }),
+        ])
+        .rpc();
+    }
+
+    it("Releases a milestone from the client's relayed signature", async () => {
+      const { pda, vault } = await createFundedContract([100, 100]);
+      await submit(pda, 0);
+
+      const before = await balance(freelancerTokenAccount);
+      await approveSigned(pda, vault, 0, client);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.releasedAmount.toNumber()).to.equal(100);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(100);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 100);
+    });
+
+    it("Rejects an approval not signed by the client", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+
+      try {
+        await approveSigned(pda, vault, 0, freelancer);
+        expect.fail("should have rejected the freelancer's signature");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidApprovalSignature");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+    });
+
+    it("Rejects a relayer routing refunds to its own token account", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      const relayerAccount = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        provider.wallet.publicKey,
+        Keypair.generate(),
+        TOKEN_PROGRAM_ID
+      );
+
+      try {
+        await approveSigned(pda, vault, 0, client, relayerAccount);
+        expect.fail("should have required the client's own token account");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+      expect(await balance(relayerAccount)).to.equal(0);
+    });
+
+    it("Rejects an approval replayed against a later submission", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      const staleMessage = await approvalMessage(pda, 0);
+
+      // The client changes their mind and the freelancer resubmits
+      await program.methods
+        .rejectMilestone(0, Array(32).fill(5))
+        .accounts({ contract: pda, client: client.publicKey })
+        .signers([client])
+        .rpc();
+      await new Promise((resolve) => setTimeout(resolve, 1500));
+      await submit(pda, 0);
+
+      try {
+        await approveSigned(pda, vault, 0, client, undefined, staleMessage);
+        expect.fail("should have refused the signature over the earlier submission");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidApprovalSignature");
+      }
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+
+      await approveSigned(pda, vault, 0, client);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+    });
+  });
+
+  describe("sequential milestone amounts", () => {
+    it("Accepts non-decreasing amounts on a sequential contract", async () => {
+      const { pda } = await createFundedContract([100, 100, 250], defaultTerms({ sequential: true }));
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.terms.sequential).to.equal(true);
+      expect(contract.milestones.map((m) => m.amount.toNumber())).to.deep.equal([100, 100, 250]);
+    });
+
+    it("Rejects a decreasing amount on a sequential contract", async () => {
+      try {
+        await createFundedContract([200, 100], defaultTerms({ sequential: true }));
+        expect.fail("should have rejected the decreasing sequence");
+      } catch (err) {
+        expect(err.toString()).to.include("NonMonotonicMilestones");
+      }
+
+      // The same amounts are fine without the flag
+      const { pda } = await createFundedContract([200, 100]);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.terms.sequential).to.equal(false);
+    });
+  });
+
+  describe("arbitrator SLA", () => {
+    const resolver = Keypair.generate();
+
+    before(async () => {
+      await program.methods
+        .registerArbitrator(resolver.publicKey)
+        .accounts({
+          config: configPDA,
+          admin: provider.wallet.publicKey,
+          profile: arbitratorPDA(resolver.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .setArbitratorSla(new BN(2))
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setArbitratorSla(new BN(0))
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    async function resolveScopeDispute(wait: number) {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ scopeResolver: resolver.publicKey }));
+      await submit(pda, 0);
+      await raiseDispute(pda, 0, client, { scope: {} });
+      await new Promise((r) => setTimeout(r, wait));
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} }, Array(32).fill(8), resolver);
+    }
+
+    it("Keeps full reputation for a resolution within the SLA", async () => {
+      await resolveScopeDispute(0);
+
+      const profile = await program.account.arbitratorProfile.fetch(arbitratorPDA(resolver.publicKey));
+      expect(profile.resolvedCount).to.equal(1);
+      expect(profile.slaMetCount).to.equal(1);
+      expect(profile.reputationBps).to.equal(10_000);
+    });
+
+    it("Downweights the arbitrator for a resolution past the SLA", async () => {
+      await resolveScopeDispute(4000);
+
+      const profile = await program.account.arbitratorProfile.fetch(arbitratorPDA(resolver.publicKey));
+      expect(profile.resolvedCount).to.equal(2);
+      expect(profile.slaMetCount).to.equal(1);
+      expect(profile.totalResolutionSecs.toNumber()).to.be.at.least(3);
+      expect(profile.reputationBps).to.equal(9_000);
+    });
+  });
+
+  describe("sponsored escrow", () => {
+    const sponsor = Keypair.generate();
+    let sponsorTokenAccount: PublicKey;
+
+    before(async () => {
+      sponsorTokenAccount = await createAccount(provider.connection, client, tokenMint, sponsor.publicKey);
+      await mintTo(provider.connection, client, tokenMint, sponsorTokenAccount, client, 1_000);
+    });
+
+    it("Funds from the sponsor and refunds them on cancel", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(300), [milestone(300, "Grant")], Array(32).fill(1), defaultTerms({ sponsor: sponsor.publicKey }))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+
+      // The client cannot fund a sponsored contract
+      try {
+        await program.methods
+          .fundEscrow(new BN(300))
+          .accounts({
+            contract: pda,
+            funder: client.publicKey,
+            funderTokenAccount: clientTokenAccount,
+            escrowVault: vault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required the sponsor");
+      } catch (err) {
+        expect(err.toString()).to.include("SponsorOnly");
+      }
+
+      // Nor can the sponsor pay from an account in another mint
+      const otherMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const otherAccount = await createAccount(provider.connection, client, otherMint, sponsor.publicKey);
+      try {
+        await program.methods
+          .fundEscrowSponsored(new BN(300))
+          .accounts({
+            contract: pda,
+            sponsor: sponsor.publicKey,
+            sponsorTokenAccount: otherAccount,
+            escrowVault: vault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([sponsor])
+          .rpc();
+        expect.fail("should have required an account in the contract's mint");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFunderAccount");
+      }
+
+      await program.methods
+        .fundEscrowSponsored(new BN(300))
+        .accounts({
+          contract: pda,
+          sponsor: sponsor.publicKey,
+          sponsorTokenAccount: sponsorTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([sponsor])
+        .rpc();
+      expect(await balance(sponsorTokenAccount)).to.equal(700);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(300);
+
+      const cancel = (refundAccount: PublicKey) =>
+        program.methods
+          .cancelContract()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: refundAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client, freelancer])
+          .rpc();
+
+      // The refund cannot be diverted to the client
+      try {
+        await cancel(clientTokenAccount);
+        expect.fail("should have refunded the sponsor");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidRefundAccount");
+      }
+
+      await cancel(sponsorTokenAccount);
+      expect(await balance(sponsorTokenAccount)).to.equal(1_000);
+      expect(await balance(vault)).to.equal(0);
+    });
+  });
+
+  describe("split compute budget", () => {
+    // Well under the default 200k per-instruction limit, leaving room for
+    // congested-network priority fee instructions and completion callbacks
+    const SPLIT_CU_BUDGET = 100_000;
+
+    it("Resolves a split on a full-size contract within the CU budget", async () => {
+      const { pda, vault } = await createFundedContract(Array(10).fill(100));
+      await submit(pda, 9);
+      await raiseDispute(pda, 9);
+
+      const sig = await resolveDispute(pda, vault, 9, { split: [60] });
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const consumed = tx.meta.computeUnitsConsumed;
+      console.log("Split resolution compute units:", consumed);
+      expect(consumed).to.be.below(SPLIT_CU_BUDGET);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[9].status).to.deep.equal({ resolved: {} });
+      expect(contract.milestones[9].clientShare.toNumber()).to.equal(40);
+    });
+  });
+
+  describe("complete_and_renew", () => {
+    async function completeAndRenew(pda: PublicKey, vault: PublicKey, index: number, bonusVault: PublicKey = null) {
+      const renewalId = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [renewal] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), renewalId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const renewalVault = vaultPDA(renewalId);
+      await program.methods
+        .completeAndRenew(index, renewalId, [milestone(150, "Next month")], Array(32).fill(4))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          renewal,
+          renewalVault,
+          tokenMint: tokenMint,
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      return { renewal, renewalVault };
+    }
+
+    it("Completes a contract and escrows its renewal atomically", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+
+      const { renewal, renewalVault } = await completeAndRenew(pda, vault, 0);
+
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+      expect(await provider.connection.getAccountInfo(pda)).to.be.null;
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+
+      let contract = await program.account.contract.fetch(renewal);
+      // Escrowed up front, but the freelancer still has to accept the renewal
+      expect(contract.status).to.deep.equal({ pending: {} });
+      expect(contract.client.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(contract.freelancer.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(contract.totalAmount.toNumber()).to.equal(150);
+      expect(contract.fundedAmount.toNumber()).to.equal(150);
+      expect(contract.escrowVault.toBase58()).to.equal(renewalVault.toBase58());
+      expect(await balance(renewalVault)).to.equal(150);
+
+      await acceptContract(renewal);
+      contract = await program.account.contract.fetch(renewal);
+      expect(contract.status).to.deep.equal({ active: {} });
+    });
+
+    it("Leaves everything untouched if the approval does not complete the contract", async () => {
+      const { pda, vault } = await createFundedContract([100, 100]);
+      await submit(pda, 0);
+
+      try {
+        await completeAndRenew(pda, vault, 0);
+        expect.fail("should have required completion");
+      } catch (err) {
+        expect(err.toString()).to.include("RenewalRequiresCompletion");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+      expect(await balance(vault)).to.equal(200);
+    });
+
+    it("gives the renewal a deadline of the same length from its own start", async () => {
+      const deadline = Math.floor(Date.now() / 1000) + 30 * 24 * 3600;
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ deadline: new BN(deadline) }));
+      const term = deadline - (await program.account.contract.fetch(pda)).createdAt.toNumber();
+      await submit(pda, 0);
+
+      const { renewal } = await completeAndRenew(pda, vault, 0);
+
+      const contract = await program.account.contract.fetch(renewal);
+      expect(contract.terms.deadline.toNumber()).to.equal(contract.createdAt.toNumber() + term);
+    });
+
+    it("refuses to renew while the bonus vault still holds unpaid tokens", async () => {
+      const bonusMint = await createMint(provider.connection, client, client.publicKey, null, 9);
+      const clientBonus = await createAccount(provider.connection, client, bonusMint, client.publicKey);
+      await mintTo(provider.connection, client, bonusMint, clientBonus, client, 700);
+
+      const { pda, vault } = await createFundedContract(
+        [milestone(100, "Kept"), milestone(100, "Revoked", { bonusAmount: new BN(700) })],
+        defaultTerms({ bonusMint })
+      );
+      const bonusVault = await createAccount(
+        provider.connection,
+        client,
+        bonusMint,
+        pda,
+        Keypair.generate(),
+        TOKEN_PROGRAM_ID
+      );
+      await program.methods
+        .fundBonus(new BN(700))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          clientBonusTokenAccount: clientBonus,
+          bonusVault,
+          bonusMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      // Revoking takes the bonus out of the total but leaves its tokens in the vault
+      await program.methods
+        .revokeMilestone(1)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+      await submit(pda, 0);
+
+      try {
+        await completeAndRenew(pda, vault, 0, bonusVault);
+        expect.fail("should have refused to strand the bonus");
+      } catch (err) {
+        expect(err.toString()).to.include("BonusUnreclaimed");
+      }
+
+      expect(await balance(bonusVault)).to.equal(700);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+    });
+  });
+
+  describe("estimate_net_payout", () => {
+    async function estimate(pda: PublicKey) {
+      return program.methods.estimateNetPayout().accounts({ contract: pda }).view();
+    }
+
+    it("Matches an unconditioned release", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      const [e] = await estimate(pda);
+      expect(e.fee.toNumber()).to.equal(0);
+      expect(e.retainage.toNumber()).to.equal(0);
+      expect(e.payout.toNumber()).to.equal(100);
+
+      await submit(pda, 0);
+      const before = await balance(freelancerTokenAccount);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + e.payout.toNumber());
+    });
+
+    it("Matches releases net of fee and retainage", async () => {
+      const config = await program.account.config.fetch(configPDA);
+      const treasury = await createAccount(provider.connection, client, tokenMint, config.feeTreasury, Keypair.generate());
+      const { pda, vault } = await createFundedContract([1000, 400], defaultTerms({ feeBps: 250, retainageBps: 1000 }));
+
+      const estimates = await estimate(pda);
+      // 1000: fee 25, then 10% of 975 retained
+      expect(estimates[0].fee.toNumber()).to.equal(25);
+      expect(estimates[0].retainage.toNumber()).to.equal(97);
+      expect(estimates[0].payout.toNumber()).to.equal(878);
+
+      const start = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await approveWithFee(pda, vault, 0, treasury);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(start + estimates[0].payout.toNumber());
+
+      // The final release credits the last payout plus all retainage
+      await submit(pda, 1);
+      await approveWithFee(pda, vault, 1, treasury);
+      await withdraw(pda, vault);
+      const net = estimates.reduce((sum, e) => sum + e.payout.toNumber() + e.retainage.toNumber(), 0);
+      expect(await balance(freelancerTokenAccount)).to.equal(start + net);
+      expect(await balance(treasury)).to.equal(estimates.reduce((sum, e) => sum + e.fee.toNumber(), 0));
+    });
+  });
+
+  describe("forfeit decisions", () => {
+    function forfeit(pda: PublicKey, vault: PublicKey, index: number, restitution: PublicKey | null) {
+      return program.methods
+        .resolveDispute(index, { forfeit: {} }, Array(32).fill(6), Array(32).fill(8))
+        .accounts({
+          contract: pda,
+          arbitrator: arbitrator.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          restitutionTokenAccount: restitution,
+          arbitratorTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+          arbitratorProfile: arbitratorPDA(arbitrator.publicKey),
+        })
+        .signers([arbitrator])
+        .rpc();
+    }
+
+    it("Sends a forfeited milestone to the restitution account", async () => {
+      const config = await program.account.config.fetch(configPDA);
+      const restitution = await createAccount(
+        provider.connection,
+        client,
+        tokenMint,
+        config.restitutionTreasury,
+        Keypair.generate()
+      );
+      const { pda, vault } = await createFundedContract([100, 200]);
+      await submit(pda, 1);
+      await raiseDispute(pda, 1);
+
+      try {
+        await forfeit(pda, vault, 1, null);
+        expect.fail("should have required the restitution account");
+      } catch (err) {
+        expect(err.toString()).to.include("RestitutionAccountMissing");
+      }
+
+      const clientBefore = await balance(clientTokenAccount);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await forfeit(pda, vault, 1, restitution);
+
+      expect(await balance(restitution)).to.equal(200);
+      expect(await balance(vault)).to.equal(100);
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[1].status).to.deep.equal({ forfeited: {} });
+      expect(contract.releasedAmount.toNumber()).to.equal(200);
+    });
+
+    it("Rejects a restitution account not owned by the treasury", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await forfeit(pda, vault, 0, clientTokenAccount);
+        expect.fail("should have rejected the client's account");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidRestitutionAccount");
+      }
+    });
+  });
+
+  describe("resubmission window", () => {
+    function expire(pda: PublicKey, vault: PublicKey, index: number) {
+      return program.methods
+        .expireRejectedMilestone(index)
+        .accounts({
+          contract: pda,
+          payer: provider.wallet.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    it("Lets the freelancer resubmit a rejection within the window", async () => {
+      const { pda, vault } = await createFundedContract([100, 100], defaultTerms({ resubmitWindow: new BN(60) }));
+      await submit(pda, 0);
+      await reject(pda, 0);
+
+      // Nothing to expire while the freelancer can still resubmit
+      try {
+        await expire(pda, vault, 0);
+        expect.fail("should wait for the window");
+      } catch (err) {
+        expect(err.toString()).to.include("ResubmitWindowOpen");
+      }
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].feedbackHash).to.deep.equal(Array(32).fill(5));
+
+      await submit(pda, 0);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+      expect(contract.milestones[0].rejectedAt).to.be.null;
+    });
+
+    it("Refunds the client once the window passes without a resubmission", async () => {
+      const { pda, vault } = await createFundedContract([100, 100], defaultTerms({ resubmitWindow: new BN(1) }));
+      await submit(pda, 0);
+      await reject(pda, 0);
+      await new Promise((r) => setTimeout(r, 2500));
+
+      try {
+        await submit(pda, 0);
+        expect.fail("should have closed resubmission");
+      } catch (err) {
+        expect(err.toString()).to.include("ResubmitWindowClosed");
+      }
+
+      const clientBefore = await balance(clientTokenAccount);
+      await expire(pda, vault, 0);
+
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 100);
+      expect(await balance(vault)).to.equal(100);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ refunded: {} });
+      expect(contract.refundedAmount.toNumber()).to.equal(100);
+      expect(contract.status).to.deep.equal({ active: {} });
+    });
+
+    it("Resubmits a milestone a dispute rejected and approves the revision", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorClient: {} });
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ rejected: {} });
+
+      await program.methods
+        .submitMilestone(0, [Array(32).fill(9)], null)
+        .accounts({ contract: pda, freelancer: freelancer.publicKey })
+        .signers([freelancer])
+        .rpc();
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+      expect(contract.milestones[0].proofHashes).to.deep.equal([Array(32).fill(9)]);
+      expect(contract.milestones[0].revisionCount).to.equal(1);
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("Stops resubmissions at max_revisions", async () => {
+      const { pda } = await createFundedContract([milestone(100, "Logo", { maxRevisions: 1 })]);
+      await submit(pda, 0);
+      await reject(pda, 0);
+      await submit(pda, 0);
+      await reject(pda, 0);
+
+      try {
+        await submit(pda, 0);
+        expect.fail("should have run out of revisions");
+      } catch (err) {
+        expect(err.toString()).to.include("RevisionLimitReached");
+      }
+    });
+  });
+
+  describe("governance resolver of last resort", () => {
+    const governance = Keypair.generate();
+
+    before(async () => {
+      await program.methods
+        .setGovernance(governance.publicKey, new BN(2))
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setGovernance(provider.wallet.publicKey, new BN(30 * 86_400))
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    });
+
+    function governanceResolve(pda: PublicKey, vault: PublicKey, index: number, decision: any) {
+      return program.methods
+        .governanceResolve(index, decision)
+        .accounts({
+          contract: pda,
+          config: configPDA,
+          governance: governance.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          restitutionTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([governance])
+        .rpc();
+    }
+
+    it("Rejects a governance resolution before the dispute is stale", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await governanceResolve(pda, vault, 0, { favorFreelancer: {} });
+        expect.fail("should have waited for staleness");
+      } catch (err) {
+        expect(err.toString()).to.include("DisputeNotStale");
+      }
+    });
+
+    it("Rejects a split above 100% up front", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await governanceResolve(pda, vault, 0, { split: [101] });
+        expect.fail("should have rejected the split");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidSplit");
+      }
+    });
+
+    it("Resolves a stale dispute via governance", async () => {
+      const { pda, vault } = await createFundedContract([100, 100]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await new Promise((r) => setTimeout(r, 3000));
+
+      const before = await balance(freelancerTokenAccount);
+      await governanceResolve(pda, vault, 0, { split: [70] });
+      await withdraw(pda, vault);
+
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 70);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ resolved: {} });
+      expect(contract.milestones[0].clientShare.toNumber()).to.equal(30);
+    });
+  });
+
+  describe("unfunded escrow", () => {
+    it("Blocks milestone work until the escrow is funded", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+
+      try {
+        await submit(pda, 0);
+        expect.fail("should have required funding");
+      } catch (err) {
+        expect(err.toString()).to.include("EscrowNotFunded");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ pending: {} });
+    });
+  });
+
+  describe("milestone amounts in basis points", () => {
+    async function createInBps(total: number, bps: number[]): Promise<PublicKey> {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(
+          id,
+          new BN(total),
+          bps.map((b, i) => milestone(b, `Phase ${i}`)),
+          Array(32).fill(1),
+          defaultTerms({ amountsInBps: true })
+        )
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      return pda;
+    }
+
+    it("Normalizes uneven shares to exactly the total", async () => {
+      const pda = await createInBps(1001, [3333, 3333, 3334]);
+
+      const contract = await program.account.contract.fetch(pda);
+      const amounts = contract.milestones.map((m) => m.amount.toNumber());
+      // Each share is floored; the last milestone absorbs the remainder
+      expect(amounts).to.deep.equal([333, 333, 335]);
+      expect(amounts.reduce((a, b) => a + b, 0)).to.equal(1001);
+      expect(contract.totalAmount.toNumber()).to.equal(1001);
+    });
+
+    it("Rejects shares that do not sum to 100%", async () => {
+      try {
+        await createInBps(1000, [5000, 4000]);
+        expect.fail("should have rejected the shares");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidMilestoneBps");
+      }
+    });
+  });
+
+  describe("escrow vault ownership", () => {
+    async function createUnfunded(): Promise<PublicKey> {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      return pda;
+    }
+
+    function fund(pda: PublicKey, vault: PublicKey, funderTokenAccount = clientTokenAccount) {
+      return program.methods
+        .fundEscrow(new BN(100))
+        .accounts({
+          contract: pda,
+          funder: client.publicKey,
+          funderTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+    }
+
+    it("Rejects a vault the contract PDA does not own", async () => {
+      const pda = await createUnfunded();
+      const clientOwned = await createAccount(provider.connection, client, tokenMint, client.publicKey, Keypair.generate());
+
+      try {
+        await fund(pda, clientOwned);
+        expect.fail("should have rejected the client-owned vault");
+      } catch (err) {
+        // Only the vault created with the contract is accepted
+        expect(err.toString()).to.match(/InvalidVault\b/);
+      }
+    });
+
+    it("Rejects a vault holding another mint", async () => {
+      const pda = await createUnfunded();
+      const otherMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const wrongMint = await createAccount(provider.connection, client, otherMint, pda, Keypair.generate());
+
+      try {
+        await fund(pda, wrongMint);
+        expect.fail("should have rejected the vault's mint");
+      } catch (err) {
+        expect(err.toString()).to.match(/InvalidVault\b/);
+      }
+    });
+
+    it("Rejects a funder account holding another mint", async () => {
+      const pda = await createUnfunded();
+      const otherMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      const wrongMint = await createAccount(provider.connection, client, otherMint, client.publicKey, Keypair.generate());
+
+      try {
+        await fund(pda, vaultPDA((await program.account.contract.fetch(pda)).id), wrongMint);
+        expect.fail("should have rejected the funder account's mint");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFunderAccount");
+      }
+    });
+
+    it("Rejects funding the vault from itself", async () => {
+      const pda = await createUnfunded();
+      const vault = vaultPDA((await program.account.contract.fetch(pda)).id);
+
+      try {
+        await fund(pda, vault, vault);
+        expect.fail("should have rejected the self-transfer");
+      } catch (err) {
+        expect(err.toString()).to.include("SameAccount");
+      }
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("dispute history", () => {
+    it("Logs each resolved dispute on the contract", async () => {
+      const { pda, vault } = await createFundedContract([100, 100], defaultTerms());
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await raiseDispute(pda, 0, client, { technical: {} });
+      await resolveDispute(pda, vault, 0, { favorClient: {} }, Array(32).fill(1), arbitrator);
+      await raiseDispute(pda, 1, freelancer, { technical: {} });
+      await resolveDispute(pda, vault, 1, { split: [40] }, Array(32).fill(2), arbitrator);
+
+      const log = (await program.account.contract.fetch(pda)).disputeLog;
+      expect(log.length).to.equal(2);
+      expect(log[0].milestoneIndex).to.equal(0);
+      expect(log[0].raisedBy.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(log[0].decision).to.have.property("favorClient");
+      expect(log[0].reasonHash).to.deep.equal(Array(32).fill(7));
+      expect(log[1].milestoneIndex).to.equal(1);
+      expect(log[1].raisedBy.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(log[1].decision.split[0]).to.equal(40);
+      expect(log[1].resolvedAt.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("split bounds", () => {
+    it("Rejects a split above 100 percent", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms());
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await resolveDispute(pda, vault, 0, { split: [101] });
+        expect.fail("should have rejected the split");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidSplit");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("disputed");
+      expect(await balance(vault)).to.equal(100);
+    });
+
+    it("Conserves every milestone across split percentages", async () => {
+      // Odd and prime amounts against percentages that leave a remainder
+      const cases: [number, number][] = [
+        [1, 50], [3, 33], [7, 1], [99, 99], [101, 67],
+        [997, 13], [1_001, 50], [12_345, 71], [65_537, 0], [999_999, 100],
+      ];
+      const { pda, vault } = await createFundedContract(cases.map(([amount]) => amount));
+      for (let i = 0; i < cases.length; i++) {
+        await submit(pda, i);
+      }
+      for (let i = 0; i < cases.length; i++) {
+        await raiseDispute(pda, i);
+      }
+
+      for (let i = 0; i < cases.length; i++) {
+        const [amount, pct] = cases[i];
+        const clientBefore = await balance(clientTokenAccount);
+        await resolveDispute(pda, vault, i, { split: [pct] });
+
+        // The freelancer's share is credited for withdrawal, the client's refunded
+        const toFreelancer = (await program.account.contract.fetch(pda)).approvedUnclaimed.toNumber();
+        const toClient = (await balance(clientTokenAccount)) - clientBefore;
+        if (toFreelancer > 0) {
+          await withdraw(pda, vault);
+        }
+        expect(toFreelancer).to.equal(Math.floor((amount * pct) / 100));
+        expect(toFreelancer + toClient).to.equal(amount);
+      }
+
+      // Nothing is left behind: the last split completed the contract and its
+      // withdrawal closed the vault
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+  });
+
+  describe("funding delegate", () => {
+    const delegate = Keypair.generate();
+    const stranger = Keypair.generate();
+    let delegateTokenAccount: PublicKey;
+    let strangerTokenAccount: PublicKey;
+
+    before(async () => {
+      delegateTokenAccount = await createAccount(provider.connection, client, tokenMint, delegate.publicKey);
+      strangerTokenAccount = await createAccount(provider.connection, client, tokenMint, stranger.publicKey);
+      await mintTo(provider.connection, client, tokenMint, delegateTokenAccount, client, 1_000);
+      await mintTo(provider.connection, client, tokenMint, strangerTokenAccount, client, 1_000);
+    });
+
+    it("Funds from the delegate and refunds the client on cancel", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(
+          id,
+          new BN(300),
+          [milestone(300, "Custodied")],
+          Array(32).fill(1),
+          defaultTerms({ fundingDelegate: delegate.publicKey })
+        )
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+
+      const fund = (funder: Keypair, funderTokenAccount: PublicKey) =>
+        program.methods
+          .fundEscrow(new BN(300))
+          .accounts({
+            contract: pda,
+            funder: funder.publicKey,
+            funderTokenAccount,
+            escrowVault: vault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([funder])
+          .rpc();
+
+      try {
+        await fund(stranger, strangerTokenAccount);
+        expect.fail("should have rejected the stranger");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      await fund(delegate, delegateTokenAccount);
+      expect(await balance(delegateTokenAccount)).to.equal(700);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(300);
+      expect(contract.client.toBase58()).to.equal(client.publicKey.toBase58());
+
+      const clientBefore = await balance(clientTokenAccount);
+      await program.methods
+        .cancelContract()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 300);
+      expect(await balance(delegateTokenAccount)).to.equal(700);
+    });
+  });
+
+  describe("dispute index bounds", () => {
+    it("Rejects raising a dispute on a missing milestone", async () => {
+      const { pda } = await createFundedContract([100], defaultTerms());
+
+      try {
+        await raiseDispute(pda, 99);
+        expect.fail("should have rejected the index");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidMilestoneIndex");
+      }
+    });
+
+    it("Rejects resolving a dispute on a missing milestone", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms());
+
+      try {
+        await resolveDispute(pda, vault, 99, { favorClient: {} });
+        expect.fail("should have rejected the index");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidMilestoneIndex");
+      }
+    });
+  });
+
+  describe("update_fee_recipient", () => {
+    const newRecipient = Keypair.generate();
+    let originalRecipient: PublicKey;
+
+    before(async () => {
+      originalRecipient = (await program.account.config.fetch(configPDA)).feeTreasury;
+    });
+
+    after(async () => {
+      await program.methods
+        .updateFeeRecipient(originalRecipient)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey, contract: null })
+        .rpc();
+    });
+
+    it("Rejects a non-admin", async () => {
+      try {
+        await program.methods
+          .updateFeeRecipient(client.publicKey)
+          .accounts({ config: configPDA, admin: client.publicKey, contract: null })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected a non-admin");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+
+    it("Pays later releases of an existing contract to the new recipient", async () => {
+      const oldTreasury = await createAccount(provider.connection, client, tokenMint, originalRecipient, Keypair.generate());
+      const newTreasury = await createAccount(provider.connection, client, tokenMint, newRecipient.publicKey);
+      const { pda, vault } = await createFundedContract([1000, 1000], defaultTerms({ feeBps: 100 }));
+      await submit(pda, 0);
+      await approveWithFee(pda, vault, 0, oldTreasury);
+      expect(await balance(oldTreasury)).to.equal(10);
+
+      await program.methods
+        .updateFeeRecipient(newRecipient.publicKey)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey, contract: pda })
+        .rpc();
+      expect((await program.account.config.fetch(configPDA)).feeTreasury.toBase58()).to.equal(
+        newRecipient.publicKey.toBase58()
+      );
+
+      await submit(pda, 1);
+      try {
+        await approveWithFee(pda, vault, 1, oldTreasury);
+        expect.fail("should have required the new recipient");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFeeAccount");
+      }
+
+      await approveWithFee(pda, vault, 1, newTreasury);
+      expect(await balance(newTreasury)).to.equal(10);
+      expect(await balance(oldTreasury)).to.equal(10);
+    });
+  });
+
+  describe("checked amount sums", () => {
+    it("Rejects milestones whose amounts overflow instead of wrapping to the total", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const max = new BN("18446744073709551615");
+
+      // u64::MAX + 2 wraps to 1, which would otherwise match the total
+      try {
+        await program.methods
+          .createContract(id, new BN(1), [milestone(max, "Huge"), milestone(2, "Small")], Array(32).fill(1), defaultTerms())
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            arbitrator: arbitrator.publicKey,
+            tokenMint: tokenMint,
+            escrowVault: vaultPDA(id),
+            config: configPDA,
+            clientBlock: blockedPDA(client.publicKey),
+            freelancerBlock: blockedPDA(freelancer.publicKey),
+            clientKyc: kycPDA(client.publicKey),
+            freelancerKyc: kycPDA(freelancer.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected the overflowing sum");
+      } catch (err) {
+        expect(err.toString()).to.include("Overflow");
+      }
+    });
+  });
+
+  describe("deliverable format", () => {
+    const specHash = Array(32).fill(3);
+    const tag = (name: string) => [...Buffer.from(name.padEnd(8, "\0"))];
+
+    function submitWithFormat(pda: PublicKey, format: any) {
+      return program.methods
+        .submitMilestone(0, [Array(32).fill(1)], format)
+        .accounts({ contract: pda, freelancer: freelancer.publicKey })
+        .signers([freelancer])
+        .rpc();
+    }
+
+    it("Accepts a submission in the agreed format", async () => {
+      const { pda } = await createFundedContract(
+        [milestone(100, "Report", { expectedFormat: { specHash, tag: tag("pdf") } })],
+        defaultTerms()
+      );
+
+      await submitWithFormat(pda, { specHash, tag: tag("pdf") });
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("submitted");
+    });
+
+    it("Rejects a submission in another format", async () => {
+      const { pda } = await createFundedContract(
+        [milestone(100, "Report", { expectedFormat: { specHash, tag: tag("pdf") } })],
+        defaultTerms()
+      );
+
+      for (const format of [{ specHash, tag: tag("docx") }, null]) {
+        try {
+          await submitWithFormat(pda, format);
+          expect.fail("should have rejected the format");
+        } catch (err) {
+          expect(err.toString()).to.include("FormatMismatch");
+        }
+      }
+    });
+  });
+
+  describe("resolver authorization", () => {
+    it("Rejects a resolution signed by a stranger", async () => {
+      const stranger = Keypair.generate();
+      const { pda, vault } = await createFundedContract([100], defaultTerms());
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await resolveDispute(pda, vault, 0, { favorClient: {} }, Array(32).fill(8), stranger);
+        expect.fail("should have rejected the stranger");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("disputed");
+      expect(await balance(vault)).to.equal(100);
+    });
+  });
+
+  describe("finalize_contract", () => {
+    function finalize(pda: PublicKey, signer: Keypair) {
+      return program.methods
+        .finalizeContract()
+        .accounts({ contract: pda, client: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    }
+
+    it("Completes on the last release without sign-off", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms());
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      try {
+        await finalize(pda, client);
+        expect.fail("should have nothing to finalize");
+      } catch (err) {
+        expect(err.toString()).to.include("NotPendingFinalization");
+      }
+    });
+
+    it("Waits for the client's sign-off when required", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ requireSignOff: true }));
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      await withdraw(pda, vault);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ pendingFinalization: {} });
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+
+      try {
+        await finalize(pda, freelancer);
+        expect.fail("only the client may sign off");
+      } catch (err) {
+        expect(err.toString()).to.match(/ConstraintHasOne|has one/i);
+      }
+
+      const events = [];
+      const listener = program.addEventListener("ContractFinalized", (event) => {
+        if (event.contractId.eq(contract.id)) events.push(event);
+      });
+      await finalize(pda, client);
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+      await program.removeEventListener(listener);
+
+      expect(events.length).to.equal(1);
+      expect(events[0].client.toBase58()).to.equal(client.publicKey.toBase58());
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+  });
+
+  describe("milestone due dates", () => {
+    const now = () => Math.floor(Date.now() / 1000);
+
+    it("Rejects a due date that has already passed", async () => {
+      try {
+        await createFundedContract([milestone(100, "Yesterday", { dueDate: new BN(now() - 86_400) })]);
+        expect.fail("should have rejected the due date");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidDueDate");
+      }
+    });
+
+    it("Accepts an on-time submission without flagging it", async () => {
+      const { pda } = await createFundedContract([milestone(100, "Tomorrow", { dueDate: new BN(now() + 86_400) })]);
+      await submit(pda, 0);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("submitted");
+      expect(contract.milestones[0].late).to.equal(false);
+    });
+
+    it("Accepts a late submission and flags it", async () => {
+      const { pda } = await createFundedContract([milestone(100, "Overdue", { dueDate: new BN(now() + 86_400) })]);
+      await setDueDate(pda, 0, now() - 60);
+      await submit(pda, 0);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("submitted");
+      expect(contract.milestones[0].late).to.equal(true);
+    });
+  });
+
+  describe("claim_timeout_release", () => {
+    const bystander = Keypair.generate();
+
+    function claim(pda: PublicKey, vault: PublicKey, index: number) {
+      return program.methods
+        .claimTimeoutRelease(index)
+        .accounts({
+          contract: pda,
+          caller: bystander.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([bystander])
+        .rpc();
+    }
+
+    it("Waits for the client's review window", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ reviewWindow: new BN(3600) }));
+      await submit(pda, 0);
+
+      try {
+        await claim(pda, vault, 0);
+        expect.fail("should have waited for the review window");
+      } catch (err) {
+        expect(err.toString()).to.include("ReviewWindowNotElapsed");
+      }
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("submitted");
+    });
+
+    it("Releases to the freelancer once the window lapses", async () => {
+      const { pda, vault } = await createFundedContract([100, 50], defaultTerms({ reviewWindow: new BN(1) }));
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await new Promise((resolve) => setTimeout(resolve, 2500));
+
+      await claim(pda, vault, 0);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("approved");
+      expect(contract.releasedAmount.toNumber()).to.equal(100);
+    });
+
+    it("Is off without a review window", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+
+      try {
+        await claim(pda, vault, 0);
+        expect.fail("should have required a review window");
+      } catch (err) {
+        expect(err.toString()).to.include("NoReviewWindow");
+      }
+    });
+  });
+
+  describe("lifecycle events", () => {
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+
+    // Decode the events a confirmed transaction emitted
+    async function eventsOf(sig: string) {
+      await provider.connection.confirmTransaction(sig, "confirmed");
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return Array.from(parser.parseLogs(tx.meta.logMessages));
+    }
+
+    function find(events: anchor.Event[], name: string) {
+      const event = events.find((e) => e.name === name);
+      expect(event, `${name} not emitted`).to.not.be.undefined;
+      return event.data as any;
+    }
+
+    it("emits an event for each transition from creation to completion", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const milestones = [milestone(100, "Design"), milestone(50, "Build")];
+
+      let events = await eventsOf(
+        await program.methods
+          .createContract(id, new BN(150), milestones, Array(32).fill(9), defaultTerms())
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            arbitrator: arbitrator.publicKey,
+            tokenMint: tokenMint,
+            escrowVault: vaultPDA(id),
+            config: configPDA,
+            clientBlock: blockedPDA(client.publicKey),
+            freelancerBlock: blockedPDA(freelancer.publicKey),
+            clientKyc: kycPDA(client.publicKey),
+            freelancerKyc: kycPDA(freelancer.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([client])
+          .rpc()
+      );
+      const created = find(events, "ContractCreated");
+      expect(created.contractId.eq(id)).to.be.true;
+      expect(created.client.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(created.freelancer.toBase58()).to.equal(freelancer.publicKey.toBase58());
+      expect(created.totalAmount.toNumber()).to.equal(150);
+      await acceptContract(pda);
+
+      const vault = vaultPDA(id);
+      events = await eventsOf(
+        await program.methods
+          .fundEscrow(new BN(150))
+          .accounts({
+            contract: pda,
+            funder: client.publicKey,
+            funderTokenAccount: clientTokenAccount,
+            escrowVault: vault,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc()
+      );
+      const funded = find(events, "EscrowFunded");
+      expect(funded.funder.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(funded.amount.toNumber()).to.equal(150);
+      expect(funded.fundedAmount.toNumber()).to.equal(150);
+
+      events = await eventsOf(
+        await program.methods
+          .submitMilestone(0, [Array(32).fill(1)], null)
+          .accounts({ contract: pda, freelancer: freelancer.publicKey })
+          .signers([freelancer])
+          .rpc()
+      );
+      const submitted = find(events, "MilestoneSubmitted");
+      expect(submitted.milestoneIndex).to.equal(0);
+      expect(submitted.late).to.be.false;
+      expect(submitted.submittedAt.toNumber()).to.be.greaterThan(0);
+
+      events = await eventsOf(await approve(pda, vault, 0));
+      const approved = find(events, "MilestoneApproved");
+      expect(approved.milestoneIndex).to.equal(0);
+      expect(approved.amount.toNumber()).to.equal(100);
+      expect(approved.payout.toNumber()).to.equal(100);
+
+      await submit(pda, 1);
+      events = await eventsOf(
+        await program.methods
+          .raiseDispute(1, Array(32).fill(7), { technical: {} })
+          .accounts({
+            contract: pda,
+            authority: client.publicKey,
+            raiserTokenAccount: null,
+            escrowVault: null,
+            tokenMint: null,
+            tokenProgram: null,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc()
+      );
+      const raised = find(events, "DisputeRaised");
+      expect(raised.milestoneIndex).to.equal(1);
+      expect(raised.raisedBy.toBase58()).to.equal(client.publicKey.toBase58());
+      expect(raised.disputeType).to.deep.equal({ technical: {} });
+
+      events = await eventsOf(await resolveDispute(pda, vault, 1, { favorFreelancer: {} }));
+      const resolved = find(events, "DisputeResolved");
+      expect(resolved.decision).to.deep.equal({ favorFreelancer: {} });
+      expect(resolved.arbitrationProof).to.deep.equal(Array(32).fill(6));
+      expect(resolved.resolver.toBase58()).to.equal(arbitrator.publicKey.toBase58());
+      const completed = find(events, "ContractCompleted");
+      expect(completed.contractId.eq(id)).to.be.true;
+      expect(completed.releasedAmount.toNumber()).to.equal(150);
+    });
+
+    it("emits ContractCancelled with the refund", async () => {
+      const { pda, vault } = await createFundedContract([80]);
+
+      const events = await eventsOf(
+        await program.methods
+          .cancelContract()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client, freelancer])
+          .rpc()
+      );
+      const cancelled = find(events, "ContractCancelled");
+      expect(cancelled.refunded.toNumber()).to.equal(80);
+      expect(cancelled.refundRecipient.toBase58()).to.equal(client.publicKey.toBase58());
+    });
+  });
+
+  describe("amend_contract", () => {
+    function amend(pda: PublicKey, milestones: ReturnType<typeof milestone>[], total: number) {
+      return program.methods
+        .amendContract(milestones, new BN(total))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          config: configPDA,
+        })
+        .signers([client, freelancer])
+        .rpc();
+    }
+
+    it("replaces the milestones of an unfunded contract", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Everything")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+
+      try {
+        await amend(pda, [milestone(100, "Design"), milestone(100, "Build")], 250);
+        expect.fail("should have checked the milestone sum");
+      } catch (err) {
+        expect(err.toString()).to.include("AmountMismatch");
+      }
+
+      await amend(pda, [milestone(100, "Design"), milestone(150, "Build")], 250);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.totalAmount.toNumber()).to.equal(250);
+      expect(contract.milestones.map((m) => m.description)).to.deep.equal(["Design", "Build"]);
+    });
+
+    it("refuses to amend once the escrow is funded", async () => {
+      const { pda } = await createFundedContract([100]);
+      try {
+        await amend(pda, [milestone(60, "Design"), milestone(40, "Build")], 100);
+        expect.fail("should have refused a funded contract");
+      } catch (err) {
+        expect(err.toString()).to.include("CannotAmendFunded");
+      }
+    });
+  });
+
+  describe("Token-2022 mints", () => {
+    // 1% transfer fee, charged on every transfer in or out of the vault
+    const feeBps = 100;
+    let feeMint: PublicKey;
+    let clientFeeAccount: PublicKey;
+    let freelancerFeeAccount: PublicKey;
+    let feeToken: FundingToken;
+
+    async function balance2022(account: PublicKey): Promise<number> {
+      return Number((await getAccount(provider.connection, account, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+    }
+
+    before(async () => {
+      const mintKeypair = Keypair.generate();
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      const lamports = await provider.connection.getMinimumBalanceForRentExemption(mintLen);
+      const tx = new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: client.publicKey,
+          newAccountPubkey: mintKeypair.publicKey,
+          space: mintLen,
+          lamports,
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(
+          mintKeypair.publicKey,
+          client.publicKey,
+          client.publicKey,
+          feeBps,
+          BigInt(1_000_000_000),
+          TOKEN_2022_PROGRAM_ID
+        ),
+        createInitializeMintInstruction(mintKeypair.publicKey, 6, client.publicKey, null, TOKEN_2022_PROGRAM_ID)
+      );
+      await anchor.web3.sendAndConfirmTransaction(provider.connection, tx, [client, mintKeypair]);
+      feeMint = mintKeypair.publicKey;
+
+      clientFeeAccount = await createAccount(
+        provider.connection, client, feeMint, client.publicKey, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID
+      );
+      freelancerFeeAccount = await createAccount(
+        provider.connection, client, feeMint, freelancer.publicKey, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(
+        provider.connection, client, feeMint, clientFeeAccount, client, 10_000_000, [], undefined, TOKEN_2022_PROGRAM_ID
+      );
+      feeToken = { account: clientFeeAccount, mint: feeMint, program: TOKEN_2022_PROGRAM_ID };
+    });
+
+    it("credits funding net of the transfer fee and closes the vault once paid out", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(1_000_000), [milestone(1_000_000, "Build")], Array(32).fill(9), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: feeMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+
+      // 1% of 1_000_000 is withheld, so only 990_000 counts as funded
+      await deposit("fundEscrow", pda, vault, 1_000_000, feeToken);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(990_000);
+
+      // 10_102 less its rounded-up 1% fee of 102 covers the shortfall exactly
+      await deposit("topUpEscrow", pda, vault, 10_102, feeToken);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(1_000_000);
+
+      await submit(pda, 0);
+      await program.methods
+        .approveMilestone(0)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientFeeAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: feeMint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      await program.methods
+        .withdrawFunds()
+        .accounts({
+          contract: pda,
+          freelancer: freelancer.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerFeeAccount,
+          tokenMint: feeMint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([freelancer])
+        .rpc();
+
+      expect(await balance2022(freelancerFeeAccount)).to.equal(990_000);
+      // Withheld fees were harvested to the mint, so the vault could close
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+
+    it("grosses the payout up so the freelancer nets the milestone amount", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(
+          id,
+          new BN(1_000_000),
+          [milestone(1_000_000, "Build")],
+          Array(32).fill(9),
+          defaultTerms({ grossUpTransferFees: true })
+        )
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: feeMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+
+      // Funding is capped at the total plus the 10_102 fee on paying it all out
+      try {
+        await deposit("fundEscrow", pda, vault, 1_020_307, feeToken);
+        expect.fail("should have rejected funding beyond the gross-up buffer");
+      } catch (err) {
+        expect(err.toString()).to.include("OverFunded");
+      }
+      // 1_020_306 less its 1% fee delivers exactly that cap
+      await deposit("fundEscrow", pda, vault, 1_020_306, feeToken);
+      expect(await balance2022(vault)).to.equal(1_010_102);
+
+      await submit(pda, 0);
+      const clientBefore = await balance2022(clientFeeAccount);
+      const freelancerBefore = await balance2022(freelancerFeeAccount);
+      await program.methods
+        .approveMilestone(0)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientFeeAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: feeMint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+
+      // Completion keeps all 1_010_102 back, the gross amount that nets
+      // 1_000_000, leaving nothing to sweep to the client
+      expect(await balance2022(vault)).to.equal(1_010_102);
+      expect(await balance2022(clientFeeAccount)).to.equal(clientBefore);
+
+      await program.methods
+        .withdrawFunds()
+        .accounts({
+          contract: pda,
+          freelancer: freelancer.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerFeeAccount,
+          tokenMint: feeMint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([freelancer])
+        .rpc();
+
+      expect(await balance2022(freelancerFeeAccount)).to.equal(freelancerBefore + 1_000_000);
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+
+    it("refuses to reconcile a contract that grosses up transfer fees", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(
+          id,
+          new BN(1_000_000),
+          [milestone(1_000_000, "Build")],
+          Array(32).fill(9),
+          defaultTerms({ grossUpTransferFees: true })
+        )
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: feeMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const vault = vaultPDA(id);
+      await deposit("fundEscrow", pda, vault, 1_020_306, feeToken);
+
+      try {
+        await program.methods
+          .reconcileAccounting()
+          .accounts({ contract: pda, config: configPDA, authority: arbitrator.publicKey, escrowVault: vault })
+          .signers([arbitrator])
+          .rpc();
+        expect.fail("should have refused a gross-up contract");
+      } catch (err) {
+        expect(err.toString()).to.include("GrossUpNotReconcilable");
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.fundedAmount.toNumber()).to.equal(1_010_102);
+    });
+  });
+
+  describe("dispute bonds", () => {
+    const bond = 50;
+
+    async function raiseBonded(pda: PublicKey, vault: PublicKey, raiser: Keypair, raiserTokenAccount: PublicKey) {
+      await program.methods
+        .raiseDispute(0, Array(32).fill(7), { technical: {} })
+        .accounts({
+          contract: pda,
+          authority: raiser.publicKey,
+          raiserTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([raiser])
+        .rpc();
+    }
+
+    it("starts new milestones without a bond, whatever the caller sends", async () => {
+      // Tracked state is not part of a milestone input, so none of it reaches the contract
+      const { pda, vault } = await createFundedContract([
+        milestone(300, "Smuggled", { status: { disputed: {} }, disputeBond: new BN(500), disputeResolver: arbitrator.publicKey }),
+        milestone(200, "Other"),
+      ]);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ pending: {} });
+      expect(contract.milestones[0].disputeBond.toNumber()).to.equal(0);
+      expect(contract.milestones[0].disputeResolver).to.be.null;
+
+      // With no dispute open there is nothing to decide, so no bond leaves the vault
+      try {
+        await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+        expect.fail("should have found no dispute");
+      } catch (err) {
+        expect(err.toString()).to.include("MilestoneNotDisputed");
+      }
+      expect(await balance(vault)).to.equal(500);
+    });
+
+    it("holds the bond in the vault while the dispute is open", async () => {
+      const { pda, vault } = await createFundedContract([300], defaultTerms({ disputeBond: new BN(bond) }));
+      await submit(pda, 0);
+      const clientBefore = await balance(clientTokenAccount);
+
+      await raiseBonded(pda, vault, client, clientTokenAccount);
+
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore - bond);
+      expect(await balance(vault)).to.equal(300 + bond);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].disputeBond.toNumber()).to.equal(bond);
+      expect(contract.milestones[0].disputeRaisedBy.toBase58()).to.equal(client.publicKey.toBase58());
+    });
+
+    it("forwards a losing disputer's bond to the counterparty", async () => {
+      const { pda, vault } = await createFundedContract([300, 200], defaultTerms({ disputeBond: new BN(bond) }));
+      await submit(pda, 0);
+      await raiseBonded(pda, vault, client, clientTokenAccount);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      // The bond is forwarded at once; the milestone is credited for withdrawal
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + bond);
+      expect(await balance(vault)).to.equal(500);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(300);
+      expect(contract.milestones[0].disputeBond.toNumber()).to.equal(0);
+      expect(contract.milestones[0].disputeRaisedBy).to.be.null;
+    });
+
+    it("returns the bond to a disputer who prevails", async () => {
+      const { pda, vault } = await createFundedContract([300], defaultTerms({ disputeBond: new BN(bond) }));
+      await submit(pda, 0);
+      const clientBefore = await balance(clientTokenAccount);
+      await raiseBonded(pda, vault, client, clientTokenAccount);
+
+      await resolveDispute(pda, vault, 0, { favorClient: {} });
+
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore);
+      expect(await balance(vault)).to.equal(300);
+    });
+
+    it("forfeits a losing freelancer's bond to the client", async () => {
+      const { pda, vault } = await createFundedContract([300, 200], defaultTerms({ disputeBond: new BN(bond) }));
+      await transfer(provider.connection, client, clientTokenAccount, freelancerTokenAccount, client, bond);
+      await submit(pda, 0);
+      await raiseBonded(pda, vault, freelancer, freelancerTokenAccount);
+      const clientBefore = await balance(clientTokenAccount);
+
+      await resolveDispute(pda, vault, 0, { split: [20] });
+
+      // The client takes 80% of the milestone and the bond
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 240 + bond);
+    });
+
+    it("requires the bond accounts when the contract sets a bond", async () => {
+      const { pda } = await createFundedContract([300], defaultTerms({ disputeBond: new BN(bond) }));
+      await submit(pda, 0);
+      try {
+        await raiseDispute(pda, 0);
+        expect.fail("should have required the bond accounts");
+      } catch (err) {
+        expect(err.toString()).to.include("DisputeBondAccountsMissing");
+      }
+    });
+
+    it("blocks cancellation while a bond is held", async () => {
+      const { pda, vault } = await createFundedContract(
+        [300],
+        defaultTerms({ disputeBond: new BN(bond), resolutionFinalityWindow: new BN(3600) })
+      );
+      await submit(pda, 0);
+      await raiseBonded(pda, vault, client, clientTokenAccount);
+      // The decision waits out its finality window with the bond still in the vault
+      await resolveDispute(pda, vault, 0, { favorClient: {} });
+      try {
+        await program.methods
+          .cancelContract()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vault,
+            clientTokenAccount: clientTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client, freelancer])
+          .rpc();
+        expect.fail("should have kept the bond in escrow");
+      } catch (err) {
+        expect(err.toString()).to.include("DisputeBondHeld");
+      }
+    });
+  });
+
+  describe("claim_rejected_refund", () => {
+    async function claimRefund(pda: PublicKey, vault: PublicKey, index: number) {
+      await program.methods
+        .claimRejectedRefund(index)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client])
+        .rpc();
+    }
+
+    it("refunds a milestone after a FavorClient decision", async () => {
+      const { pda, vault } = await createFundedContract([100, 200]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorClient: {} });
+      const clientBefore = await balance(clientTokenAccount);
+
+      await claimRefund(pda, vault, 0);
+
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 100);
+      expect(await balance(vault)).to.equal(200);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ refunded: {} });
+      expect(contract.releasedAmount.toNumber()).to.equal(100);
+      expect(contract.refundedAmount.toNumber()).to.equal(100);
+      expect(contract.status).to.deep.equal({ active: {} });
+    });
+
+    it("completes the contract when the refund settles the last milestone", async () => {
+      const { pda, vault } = await createFundedContract([100, 200]);
+      await submit(pda, 1);
+      await approve(pda, vault, 1);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorClient: {} });
+
+      await claimRefund(pda, vault, 0);
+
+      // The vault stays open until the freelancer withdraws the approved milestone
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(await balance(vault)).to.equal(200);
+      await withdraw(pda, vault);
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+
+    it("stops the freelancer contesting an upheld rejection again", async () => {
+      const { pda, vault } = await createFundedContract(
+        [100],
+        defaultTerms({ rejectionContestWindow: new BN(60) })
+      );
+      await submit(pda, 0);
+      await reject(pda, 0);
+      await raiseDispute(pda, 0, freelancer);
+      await resolveDispute(pda, vault, 0, { favorClient: {} });
+
+      try {
+        await raiseDispute(pda, 0, freelancer);
+        expect.fail("should not reopen an upheld rejection");
+      } catch (err) {
+        expect(err.toString()).to.include("MilestoneNotSubmitted");
+      }
+      await claimRefund(pda, vault, 0);
+    });
+
+    it("leaves rejections the client made alone to expire_rejected_milestone", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await reject(pda, 0);
+      try {
+        await claimRefund(pda, vault, 0);
+        expect.fail("should have refused a rejection no dispute upheld");
+      } catch (err) {
+        expect(err.toString()).to.include("MilestoneNotRejected");
+      }
+    });
+  });
+
+  describe("contract dispute status", () => {
+    it("marks the contract disputed until the dispute is resolved", async () => {
+      const { pda, vault } = await createFundedContract([100, 200, 300]);
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await raiseDispute(pda, 0);
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ disputed: {} });
+
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      await approve(pda, vault, 1);
+      await submit(pda, 2);
+    });
+
+    it("blocks approvals and submissions while a dispute is open", async () => {
+      const { pda, vault } = await createFundedContract([100, 200, 300]);
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await raiseDispute(pda, 0);
+
+      try {
+        await approve(pda, vault, 1);
+        expect.fail("should not approve during a dispute");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractDisputed");
+      }
+      try {
+        await submit(pda, 2);
+        expect.fail("should not submit during a dispute");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractDisputed");
+      }
+    });
+
+    it("completes rather than reactivating when the decision settles the last milestone", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("reactivates during the finality window and disputes again on appeal", async () => {
+      const { pda, vault } = await createFundedContract(
+        [100, 200],
+        defaultTerms({ resolutionFinalityWindow: new BN(3600) })
+      );
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      await program.methods
+        .appealResolution(0)
+        .accounts({
+          contract: pda,
+          authority: client.publicKey,
+          appellantTokenAccount: null,
+          escrowVault: null,
+          tokenMint: null,
+          tokenProgram: null,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ disputed: {} });
+    });
+  });
+
+  describe("arbitrator panels", () => {
+    const panel = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    const panelTerms = () =>
+      defaultTerms({ arbitrators: panel.map((a) => a.publicKey), arbitrationThreshold: 2 });
+
+    it("executes a 2-of-3 decision once two votes match", async () => {
+      const { pda, vault } = await createFundedContract([100, 200], panelTerms());
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} }, Array(32).fill(8), panel[0]);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ disputed: {} });
+      expect(contract.milestones[0].disputeResolver).to.be.null;
+
+      // A conflicting vote is kept apart and does not count towards the first
+      await resolveDispute(pda, vault, 0, { favorClient: {} }, Array(32).fill(8), panel[1]);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ disputed: {} });
+      expect(contract.milestones[0].arbitrationVotes).to.deep.equal([
+        { seat: 0, decision: { favorFreelancer: {} } },
+        { seat: 1, decision: { favorClient: {} } },
+      ]);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} }, Array(32).fill(8), panel[2]);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(100);
+    });
+
+    it("lets an arbitrator change their vote", async () => {
+      const { pda, vault } = await createFundedContract([100, 200], panelTerms());
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      await resolveDispute(pda, vault, 0, { favorClient: {} }, Array(32).fill(8), panel[0]);
+      await resolveDispute(pda, vault, 0, { split: [50] }, Array(32).fill(8), panel[0]);
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].arbitrationVotes).to.deep.equal([{ seat: 0, decision: { split: [50] } }]);
+
+      await resolveDispute(pda, vault, 0, { split: [50] }, Array(32).fill(8), panel[1]);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ resolved: {} });
+    });
+
+    it("leaves technical disputes to the panel alone", async () => {
+      const { pda, vault } = await createFundedContract([100], panelTerms());
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      try {
+        await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+        expect.fail("the sole arbitrator should not decide a panel dispute");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+
+    it("rejects a threshold the panel cannot meet", async () => {
+      try {
+        await createFundedContract(
+          [100],
+          defaultTerms({ arbitrators: panel.slice(0, 2).map((a) => a.publicKey), arbitrationThreshold: 3 })
+        );
+        expect.fail("should have rejected the threshold");
+      } catch (err) {
+        expect(err.toString()).to.include("ThresholdNotMet");
+      }
+    });
+  });
+
+  describe("ordered milestones", () => {
+    it("rejects submitting ahead of an unapproved milestone", async () => {
+      const { pda } = await createFundedContract([100, 200, 300], defaultTerms({ ordered: true }));
+      try {
+        await submit(pda, 1);
+        expect.fail("should not skip milestone 0");
+      } catch (err) {
+        expect(err.toString()).to.include("PreviousMilestoneIncomplete");
+      }
+
+      // Submitted is not enough; the previous milestone must be approved
+      await submit(pda, 0);
+      try {
+        await submit(pda, 1);
+        expect.fail("should wait for milestone 0 to be approved");
+      } catch (err) {
+        expect(err.toString()).to.include("PreviousMilestoneIncomplete");
+      }
+    });
+
+    it("opens each milestone once the one before it is approved or resolved", async () => {
+      const { pda, vault } = await createFundedContract([100, 200, 300], defaultTerms({ ordered: true }));
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await submit(pda, 1);
+      await raiseDispute(pda, 1);
+      await resolveDispute(pda, vault, 1, { split: [50] });
+      await submit(pda, 2);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[2].status).to.deep.equal({ submitted: {} });
+    });
+
+    it("leaves unordered contracts free to submit in any order", async () => {
+      const { pda } = await createFundedContract([100, 200]);
+      await submit(pda, 1);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[1].status).to.deep.equal({ submitted: {} });
+    });
+  });
+
+  describe("withdraw_funds", () => {
+    it("credits approvals and pays them out on withdrawal", async () => {
+      const { pda, vault } = await createFundedContract([100, 50, 25]);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await submit(pda, 1);
+      await approve(pda, vault, 1);
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(150);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+      expect(await balance(vault)).to.equal(175);
+
+      await withdraw(pda, vault);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(0);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 150);
+      expect(await balance(vault)).to.equal(25);
+    });
+
+    it("refuses a second withdrawal of the same approvals", async () => {
+      const { pda, vault } = await createFundedContract([100, 50]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await withdraw(pda, vault);
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      try {
+        await withdraw(pda, vault);
+        expect.fail("should have had nothing left to withdraw");
+      } catch (err) {
+        expect(err.toString()).to.include("NothingToWithdraw");
+      }
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+    });
+
+    it("only lets the freelancer withdraw", async () => {
+      const { pda, vault } = await createFundedContract([100, 50]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      try {
+        await program.methods
+          .withdrawFunds()
+          .accounts({
+            contract: pda,
+            freelancer: client.publicKey,
+            client: client.publicKey,
+            escrowVault: vault,
+            freelancerTokenAccount: freelancerTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPDA,
+          })
+          .signers([client])
+          .rpc();
+        expect.fail("should have required the freelancer");
+      } catch (err) {
+        expect(err.toString()).to.include("ConstraintHasOne");
+      }
+    });
+
+    it("keeps unclaimed approvals for withdrawal when the contract completes", async () => {
+      const { pda, vault } = await createFundedContract([100, 50]);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await submit(pda, 1);
+      await approve(pda, vault, 1);
+
+      // Completing pushes nothing, so the client cannot redirect the freelancer's pay
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(150);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+
+      await withdraw(pda, vault);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(0);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 150);
+      expect(await provider.connection.getAccountInfo(vault)).to.be.null;
+    });
+
+    it("approves without the freelancer's token account", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      await submit(pda, 0);
+      await program.methods
+        .approveMilestone(0)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(100);
+    });
+
+    it("keeps unclaimed approvals out of a cancellation refund", async () => {
+      const { pda, vault } = await createFundedContract([100, 50]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+
+      const clientBefore = await balance(clientTokenAccount);
+      await program.methods
+        .cancelContract()
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([client, freelancer])
+        .rpc();
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 50);
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+      expect(await balance(vault)).to.equal(0);
+    });
+  });
+
+  describe("cancel_expired", () => {
+    const bystander = Keypair.generate();
+    const now = () => Math.floor(Date.now() / 1000);
+
+    function cancelExpired(pda: PublicKey, vault: PublicKey) {
+      return program.methods
+        .cancelExpired()
+        .accounts({
+          contract: pda,
+          caller: bystander.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bystander])
+        .rpc();
+    }
+
+    it("refuses to cancel before the deadline", async () => {
+      const { pda, vault } = await createFundedContract([100], defaultTerms({ deadline: new BN(now() + 3600) }));
+      try {
+        await cancelExpired(pda, vault);
+        expect.fail("should have waited for the deadline");
+      } catch (err) {
+        expect(err.toString()).to.include("DeadlineNotReached");
+      }
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ active: {} });
+    });
+
+    it("refuses to cancel a contract without a deadline", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      try {
+        await cancelExpired(pda, vault);
+        expect.fail("should have had no deadline to pass");
+      } catch (err) {
+        expect(err.toString()).to.include("DeadlineNotReached");
+      }
+    });
+
+    it("lets anyone refund the client once the deadline passes", async () => {
+      const { pda, vault } = await createFundedContract([60, 40], defaultTerms({ deadline: new BN(now() + 1) }));
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await new Promise((resolve) => setTimeout(resolve, 2500));
+
+      const clientBefore = await balance(clientTokenAccount);
+      await cancelExpired(pda, vault);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ cancelled: {} });
+      // Only the unreleased milestone is refunded; the approved one stays for the freelancer
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 40);
+      expect(await balance(vault)).to.equal(60);
+
+      try {
+        await cancelExpired(pda, vault);
+        expect.fail("should only cancel an active contract");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotActive");
+      }
+    });
+  });
+
+  describe("freelancer_withdraw", () => {
+    function walkAway(pda: PublicKey, vault: PublicKey, signer = freelancer) {
+      return program.methods
+        .freelancerWithdraw()
+        .accounts({
+          contract: pda,
+          freelancer: signer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    it("refunds the client the full unreleased balance", async () => {
+      const { pda, vault } = await createFundedContract([100, 200, 300]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await submit(pda, 1);
+
+      const clientBefore = await balance(clientTokenAccount);
+      await walkAway(pda, vault);
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ cancelled: {} });
+      // The submitted but unapproved milestone is forfeited along with the pending one
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 500);
+
+      // The approved milestone is still the freelancer's
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+      expect(await balance(vault)).to.equal(0);
+    });
+
+    it("only lets the freelancer walk away from an active contract", async () => {
+      const { pda, vault } = await createFundedContract([100]);
+      try {
+        await walkAway(pda, vault, client);
+        expect.fail("should have required the freelancer");
+      } catch (err) {
+        expect(err.toString()).to.match(/ConstraintHasOne|has one/i);
+      }
+
+      await walkAway(pda, vault);
+      try {
+        await walkAway(pda, vault);
+        expect.fail("should only cancel an active contract");
+      } catch (err) {
+        expect(err.toString()).to.include("ContractNotActive");
+      }
+    });
+  });
+
+  describe("approve_milestones", () => {
+    function approveBatch(pda: PublicKey, vault: PublicKey, indices: number[]) {
+      return program.methods
+        .approveMilestones(Buffer.from(indices))
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          escrowVault: vault,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+    }
+
+    it("approves three submitted milestones in one call", async () => {
+      const { pda, vault } = await createFundedContract([100, 200, 300, 400]);
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await submit(pda, 2);
+
+      await approveBatch(pda, vault, [0, 1, 2]);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones.slice(0, 3).map((m) => m.status)).to.deep.equal([
+        { approved: {} },
+        { approved: {} },
+        { approved: {} },
+      ]);
+      expect(contract.releasedAmount.toNumber()).to.equal(600);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      const before = await balance(freelancerTokenAccount);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 600);
+    });
+
+    it("reverts the whole batch when one index is not submitted", async () => {
+      const { pda, vault } = await createFundedContract([100, 200, 300]);
+      await submit(pda, 0);
+      await submit(pda, 1);
+
+      for (const indices of [[0, 1, 2], [0, 1, 1], [0, 7]]) {
+        try {
+          await approveBatch(pda, vault, indices);
+          expect.fail("should have reverted the batch");
+        } catch (err) {
+          expect(err.toString()).to.match(/MilestoneNotSubmitted|InvalidMilestoneIndex/);
+        }
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ submitted: {} });
+      expect(contract.milestones[1].status).to.deep.equal({ submitted: {} });
+      expect(contract.releasedAmount.toNumber()).to.equal(0);
+    });
+
+    it("completes the contract once when the batch releases the rest", async () => {
+      const { pda, vault } = await createFundedContract([100, 200]);
+      await submit(pda, 0);
+      await submit(pda, 1);
+
+      const before = await balance(freelancerTokenAccount);
+      await approveBatch(pda, vault, [1, 0]);
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.status).to.deep.equal({ completed: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(300);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(before + 300);
+    });
+  });
+
+  describe("milestone vesting", () => {
+    function claimVested(pda: PublicKey, vault: PublicKey) {
+      return program.methods
+        .claimVested(0)
+        .accounts({
+          contract: pda,
+          authority: freelancer.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([freelancer])
+        .rpc();
+    }
+
+    it("releases an approved payout linearly over the vesting period", async () => {
+      const { pda, vault } = await createFundedContract([milestone(1000, "Vesting", { vestingSecs: new BN(6) })]);
+      await submit(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await approve(pda, vault, 0);
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.heldTotal.toNumber()).to.equal(1000);
+      expect(contract.milestones[0].approvedAt).to.not.equal(null);
+
+      // Halfway through the window about half has vested
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await claimVested(pda, vault);
+      const halfway = (await balance(freelancerTokenAccount)) - freelancerBefore;
+      expect(halfway).to.be.closeTo(500, 170);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].vestedClaimed.toNumber()).to.equal(halfway);
+      expect(contract.status).to.deep.equal({ active: {} });
+
+      // The rest once fully vested, completing the contract
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      await claimVested(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 1000);
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.heldTotal.toNumber()).to.equal(0);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("vests the freelancer's share of a dispute decision", async () => {
+      const { pda, vault } = await createFundedContract([milestone(1000, "Vesting", { vestingSecs: new BN(60) })]);
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { split: [60] });
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(0);
+      expect(contract.heldTotal.toNumber()).to.equal(600);
+      expect(contract.milestones[0].heldPayout.toNumber()).to.equal(600);
+      expect(contract.milestones[0].approvedAt).to.not.equal(null);
+      expect(contract.status).to.deep.equal({ active: {} });
+    });
+
+    it("only vests milestones approved with a vesting period", async () => {
+      const { pda, vault } = await createFundedContract([milestone(100, "Vesting", { vestingSecs: new BN(60) }), 100]);
+      try {
+        await claimVested(pda, vault);
+        expect.fail("should not vest before approval");
+      } catch (err) {
+        expect(err.toString()).to.include("NoHeldPayout");
+      }
+
+      try {
+        await createFundedContract([milestone(100, "Instant", { vestingSecs: new BN(0) })]);
+        expect.fail("should have rejected an empty vesting period");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidVesting");
+      }
+    });
+  });
+
+  describe("arbitrator fee", () => {
+    async function arbitratorAccount(): Promise<PublicKey> {
+      return createAccount(provider.connection, client, tokenMint, arbitrator.publicKey, Keypair.generate());
+    }
+
+    it("pays the resolver before splitting the remainder", async () => {
+      const { pda, vault } = await createFundedContract([1000], defaultTerms({ arbitratorFeeBps: 1000 }));
+      const arbitratorTokenAccount = await arbitratorAccount();
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      const clientBefore = await balance(clientTokenAccount);
+      await resolveDispute(pda, vault, 0, { split: [50] }, Array(32).fill(8), arbitrator, arbitratorTokenAccount);
+      await withdraw(pda, vault);
+
+      // 10% to the arbitrator, then 450 each
+      expect(await balance(arbitratorTokenAccount)).to.equal(100);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 450);
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore + 450);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].amount.toNumber()).to.equal(900);
+      expect(contract.totalAmount.toNumber()).to.equal(900);
+      expect(contract.status).to.deep.equal({ completed: {} });
+    });
+
+    it("charges the fee when the client wins too", async () => {
+      const { pda, vault } = await createFundedContract([1000, 500], defaultTerms({ arbitratorFeeBps: 250 }));
+      const arbitratorTokenAccount = await arbitratorAccount();
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+
+      try {
+        await resolveDispute(pda, vault, 0, { favorClient: {} });
+        expect.fail("should have required the arbitrator's token account");
+      } catch (err) {
+        expect(err.toString()).to.include("ArbitratorAccountMissing");
+      }
+
+      await resolveDispute(pda, vault, 0, { favorClient: {} }, Array(32).fill(8), arbitrator, arbitratorTokenAccount);
+      expect(await balance(arbitratorTokenAccount)).to.equal(25);
+      expect(await balance(vault)).to.equal(1475);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ rejected: {} });
+      expect(contract.milestones[0].amount.toNumber()).to.equal(975);
+      expect(contract.fundedAmount.toNumber()).to.equal(1475);
+    });
+
+    it("charges the fee once when an appeal reopens the dispute", async () => {
+      const { pda, vault } = await createFundedContract(
+        [1000, 500],
+        defaultTerms({ arbitratorFeeBps: 1000, resolutionFinalityWindow: new BN(60) })
+      );
+      const arbitratorTokenAccount = await arbitratorAccount();
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} }, Array(32).fill(8), arbitrator, arbitratorTokenAccount);
+      expect(await balance(arbitratorTokenAccount)).to.equal(100);
+
+      await program.methods
+        .appealResolution(0)
+        .accounts({
+          contract: pda,
+          authority: client.publicKey,
+          appellantTokenAccount: null,
+          escrowVault: null,
+          tokenMint: null,
+          tokenProgram: null,
+          config: configPDA,
+        })
+        .signers([client])
+        .rpc();
+      await resolveDispute(pda, vault, 0, { favorClient: {} }, Array(32).fill(8), arbitrator, arbitratorTokenAccount);
+
+      expect(await balance(arbitratorTokenAccount)).to.equal(100);
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].amount.toNumber()).to.equal(900);
+      expect(contract.totalAmount.toNumber()).to.equal(1400);
+      expect(contract.milestones[0].pendingDecision).to.deep.equal({ favorClient: {} });
+    });
+
+    it("rejects a fee above the milestone amount", async () => {
+      try {
+        await createFundedContract([100], defaultTerms({ arbitratorFeeBps: 10_001 }));
+        expect.fail("should have rejected the fee");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidArbitratorFee");
+      }
+    });
+  });
+
+  describe("senior arbitration", () => {
+    const senior = Keypair.generate();
+    const appealTerms = (finalityWindow: number) =>
+      defaultTerms({
+        resolutionFinalityWindow: new BN(finalityWindow),
+        seniorArbitrator: senior.publicKey,
+        appealBond: new BN(50),
+      });
+
+    function appeal(pda: PublicKey, vault: PublicKey, appellant: Keypair, appellantTokenAccount: PublicKey) {
+      return program.methods
+        .appealResolution(0)
+        .accounts({
+          contract: pda,
+          authority: appellant.publicKey,
+          appellantTokenAccount,
+          escrowVault: vault,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([appellant])
+        .rpc();
+    }
+
+    function resolveAppeal(pda: PublicKey, vault: PublicKey, decision: any, resolver: Keypair = senior) {
+      return program.methods
+        .resolveAppeal(0, decision, Array(32).fill(9))
+        .accounts({
+          contract: pda,
+          seniorArbitrator: resolver.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          restitutionTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .signers([resolver])
+        .rpc();
+    }
+
+    it("finalizes the resolution when no one appeals", async () => {
+      const { pda, vault } = await createFundedContract([100, 100], appealTerms(2));
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await program.methods
+        .settleResolution(0)
+        .accounts({
+          contract: pda,
+          payer: provider.wallet.publicKey,
+          client: client.publicKey,
+          escrowVault: vault,
+          freelancerTokenAccount: freelancerTokenAccount,
+          clientTokenAccount: clientTokenAccount,
+          bonusVault: null,
+          freelancerBonusTokenAccount: null,
+          bonusMint: null,
+          feeTreasuryTokenAccount: null,
+          restitutionTokenAccount: null,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPDA,
+        })
+        .rpc();
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ approved: {} });
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(100);
+      await withdraw(pda, vault);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore + 100);
+    });
+
+    it("moves an appeal to the senior arbitrator, who decides it", async () => {
+      const { pda, vault } = await createFundedContract([100, 100], appealTerms(60));
+      await submit(pda, 0);
+      await raiseDispute(pda, 0);
+      await resolveDispute(pda, vault, 0, { favorFreelancer: {} });
+
+      const clientBefore = await balance(clientTokenAccount);
+      const freelancerBefore = await balance(freelancerTokenAccount);
+      await appeal(pda, vault, client, clientTokenAccount);
+
+      let contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ appealed: {} });
+      expect(contract.milestones[0].appealBond.toNumber()).to.equal(50);
+      expect(contract.status).to.deep.equal({ disputed: {} });
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore - 50);
+
+      // Only the senior arbitrator may decide the appeal
+      for (const attempt of [
+        () => resolveDispute(pda, vault, 0, { favorClient: {} }),
+        () => resolveAppeal(pda, vault, { favorClient: {} }, arbitrator),
+      ]) {
+        try {
+          await attempt();
+          expect.fail("should have required the senior arbitrator");
+        } catch (err) {
+          expect(err.toString()).to.match(/MilestoneNotDisputed|Unauthorized/);
+        }
+      }
+
+      await resolveAppeal(pda, vault, { favorClient: {} });
+
+      // The appeal succeeded: its bond comes back and nothing was paid out
+      contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.deep.equal({ rejected: {} });
+      expect(contract.milestones[0].appealBond.toNumber()).to.equal(0);
+      expect(contract.status).to.deep.equal({ active: {} });
+      expect(await balance(clientTokenAccount)).to.equal(clientBefore);
+      expect(await balance(freelancerTokenAccount)).to.equal(freelancerBefore);
+    });
+
+    it("requires the appeal bond to exceed the dispute bond", async () => {
+      try {
+        await createFundedContract(
+          [100],
+          defaultTerms({ seniorArbitrator: senior.publicKey, appealBond: new BN(10), disputeBond: new BN(10) })
+        );
+        expect.fail("should have rejected the appeal terms");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidAppealTerms");
+      }
+    });
+  });
+
+  describe("withdraw_fees", () => {
+    let treasury: PublicKey;
+    let destination: PublicKey;
+
+    before(async () => {
+      // Fees paid to the config address accumulate in accounts it owns
+      treasury = await createAccount(provider.connection, client, tokenMint, configPDA, Keypair.generate());
+      destination = await createAccount(provider.connection, client, tokenMint, provider.wallet.publicKey, Keypair.generate());
+      await mintTo(provider.connection, client, tokenMint, treasury, client, 100);
+    });
+
+    function withdrawFees(amount: number, admin: PublicKey, signers: Keypair[] = []) {
+      return program.methods
+        .withdrawFees(new BN(amount))
+        .accounts({
+          config: configPDA,
+          admin,
+          treasuryTokenAccount: treasury,
+          destination,
+          tokenMint: tokenMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers(signers)
+        .rpc();
+    }
+
+    it("rejects withdrawals by anyone but the admin", async () => {
+      try {
+        await withdrawFees(100, client.publicKey, [client]);
+        expect.fail("should have rejected a non-admin");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      expect(await balance(treasury)).to.equal(100);
+    });
+
+    it("lets the admin withdraw accumulated fees", async () => {
+      await withdrawFees(60, provider.wallet.publicKey);
+      expect(await balance(treasury)).to.equal(40);
+      expect(await balance(destination)).to.equal(60);
+
+      try {
+        await withdrawFees(41, provider.wallet.publicKey);
+        expect.fail("should have refused to overdraw the treasury");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFeeWithdrawal");
+      }
+    });
+  });
+
+  describe("set_paused", () => {
+    function setPaused(paused: boolean) {
+      return program.methods
+        .setPaused(paused)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    }
+
+    it("halts funding but still lets the parties cancel", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "Only")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+
+      await setPaused(true);
+      try {
+        try {
+          await program.methods
+            .fundEscrow(new BN(100))
+            .accounts({
+              contract: pda,
+              funder: client.publicKey,
+              funderTokenAccount: clientTokenAccount,
+              escrowVault: vaultPDA(id),
+              tokenMint: tokenMint,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              config: configPDA,
+            })
+            .signers([client])
+            .rpc();
+          expect.fail("should have been halted by the pause");
+        } catch (err) {
+          expect(err.toString()).to.include("ProgramPaused");
+        }
+
+        await program.methods
+          .cancelContract()
+          .accounts({
+            contract: pda,
+            client: client.publicKey,
+            freelancer: freelancer.publicKey,
+            escrowVault: vaultPDA(id),
+            clientTokenAccount: clientTokenAccount,
+            tokenMint: tokenMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([client, freelancer])
+          .rpc();
+        const contract = await program.account.contract.fetch(pda);
+        expect(contract.status).to.deep.equal({ cancelled: {} });
+      } finally {
+        await setPaused(false);
+      }
+    });
+
+    it("halts the permissionless timeout release and the relayed approval", async () => {
+      const { pda, vault } = await createFundedContract([100, 100], defaultTerms({ reviewWindow: new BN(1) }));
+      await submit(pda, 0);
+      await submit(pda, 1);
+      await new Promise((resolve) => setTimeout(resolve, 2500));
+      const bystander = Keypair.generate();
+      const payoutAccounts = {
+        contract: pda,
+        client: client.publicKey,
+        escrowVault: vault,
+        clientTokenAccount: clientTokenAccount,
+        bonusVault: null,
+        freelancerBonusTokenAccount: null,
+        bonusMint: null,
+        feeTreasuryTokenAccount: null,
+        tokenMint: tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        config: configPDA,
+      };
+
+      await setPaused(true);
+      try {
+        try {
+          await program.methods
+            .claimTimeoutRelease(0)
+            .accounts({ ...payoutAccounts, caller: bystander.publicKey })
+            .signers([bystander])
+            .rpc();
+          expect.fail("should have been halted by the pause");
+        } catch (err) {
+          expect(err.toString()).to.include("ProgramPaused");
+        }
+
+        try {
+          await program.methods
+            .approveMilestoneSigned(1)
+            .accounts({
+              ...payoutAccounts,
+              relayer: provider.wallet.publicKey,
+              freelancer: freelancer.publicKey,
+              instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .preInstructions([
+              Ed25519Program.createInstructionWithPrivateKey({
+                privateKey: client.secretKey,
+                message: await approvalMessage(pda, 1),
+              }),
+            ])
+            .rpc();
+          expect.fail("should have been halted by the pause");
+        } catch (err) {
+          expect(err.toString()).to.include("ProgramPaused");
+        }
+      } finally {
+        await setPaused(false);
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones[0].status).to.have.property("submitted");
+      expect(contract.milestones[1].status).to.have.property("submitted");
+      expect(await balance(vault)).to.equal(200);
+    });
+
+    it("halts withdrawals, tips and held claims", async () => {
+      const { pda, vault } = await createFundedContract([100, 100]);
+      const held = await createFundedContract([milestone(100, "Vesting", { vestingSecs: new BN(1) })]);
+      await submit(pda, 0);
+      await approve(pda, vault, 0);
+      await submit(held.pda, 0);
+      await approve(held.pda, held.vault, 0);
+      await new Promise((resolve) => setTimeout(resolve, 1500));
+
+      const halted: [string, () => Promise<unknown>][] = [
+        ["withdraw_funds", () => withdraw(pda, vault)],
+        [
+          "add_tip",
+          () =>
+            program.methods
+              .addTip(0, new BN(10))
+              .accounts({
+                contract: pda,
+                client: client.publicKey,
+                clientTokenAccount: clientTokenAccount,
+                freelancerTokenAccount: freelancerTokenAccount,
+                feeTreasuryTokenAccount: null,
+                tokenMint: tokenMint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                config: configPDA,
+              })
+              .signers([client])
+              .rpc(),
+        ],
+        [
+          "claim_vested",
+          () =>
+            program.methods
+              .claimVested(0)
+              .accounts({
+                contract: held.pda,
+                authority: freelancer.publicKey,
+                client: client.publicKey,
+                escrowVault: held.vault,
+                freelancerTokenAccount: freelancerTokenAccount,
+                clientTokenAccount: clientTokenAccount,
+                tokenMint: tokenMint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                config: configPDA,
+              })
+              .signers([freelancer])
+              .rpc(),
+        ],
+      ];
+
+      await setPaused(true);
+      try {
+        for (const [name, call] of halted) {
+          try {
+            await call();
+            expect.fail(`${name} should have been halted by the pause`);
+          } catch (err) {
+            expect(err.toString()).to.include("ProgramPaused");
+          }
+        }
+      } finally {
+        await setPaused(false);
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.approvedUnclaimed.toNumber()).to.equal(100);
+      expect(contract.milestones[0].tipAmount.toNumber()).to.equal(0);
+      const heldContract = await program.account.contract.fetch(held.pda);
+      expect(heldContract.heldTotal.toNumber()).to.equal(100);
+
+      await withdraw(pda, vault);
+      expect((await program.account.contract.fetch(pda)).approvedUnclaimed.toNumber()).to.equal(0);
+    });
+
+    it("only lets the admin pause", async () => {
+      try {
+        await program.methods
+          .setPaused(true)
+          .accounts({ config: configPDA, admin: client.publicKey })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected a non-admin");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+      const config = await program.account.config.fetch(configPDA);
+      expect(config.paused).to.equal(false);
+    });
+  });
+
+  describe("allowed mints", () => {
+    function setAllowed(method: "addAllowedMint" | "removeAllowedMint", mint: PublicKey) {
+      return program.methods[method](mint)
+        .accounts({ config: configPDA, admin: provider.wallet.publicKey })
+        .rpc();
+    }
+
+    it("only accepts whitelisted mints once the whitelist has entries", async () => {
+      // An empty whitelist allows any mint
+      await createFundedContract([100]);
+
+      const otherMint = await createMint(provider.connection, client, client.publicKey, null, 6);
+      await setAllowed("addAllowedMint", otherMint);
+      try {
+        try {
+          await createFundedContract([100]);
+          expect.fail("should have rejected a mint off the whitelist");
+        } catch (err) {
+          expect(err.toString()).to.include("MintNotAllowed");
+        }
+
+        await setAllowed("addAllowedMint", tokenMint);
+        const config = await program.account.config.fetch(configPDA);
+        expect(config.allowedMints.map((m) => m.toBase58())).to.deep.equal([
+          otherMint.toBase58(),
+          tokenMint.toBase58(),
+        ]);
+        await createFundedContract([100]);
+      } finally {
+        for (const mint of [otherMint, tokenMint]) {
+          await setAllowed("removeAllowedMint", mint).catch(() => {});
+        }
+      }
+      const config = await program.account.config.fetch(configPDA);
+      expect(config.allowedMints).to.be.empty;
+    });
+
+    it("only lets the admin change the whitelist", async () => {
+      try {
+        await program.methods
+          .addAllowedMint(tokenMint)
+          .accounts({ config: configPDA, admin: client.publicKey })
+          .signers([client])
+          .rpc();
+        expect.fail("should have rejected a non-admin");
+      } catch (err) {
+        expect(err.toString()).to.include("Unauthorized");
+      }
+    });
+  });
+
+  describe("add_milestones", () => {
+    function addMilestones(pda: PublicKey, milestones: ReturnType<typeof milestone>[]) {
+      return program.methods
+        .addMilestones(milestones)
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          config: configPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client, freelancer])
+        .rpc();
+    }
+
+    it("grows an unfunded contract past the initial milestone limit", async () => {
+      const id = new BN(Date.now() + Math.floor(Math.random() * 1_000_000));
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("contract"), id.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createContract(id, new BN(100), [milestone(100, "M0")], Array(32).fill(1), defaultTerms())
+        .accounts({
+          contract: pda,
+          client: client.publicKey,
+          freelancer: freelancer.publicKey,
+          arbitrator: arbitrator.publicKey,
+          tokenMint: tokenMint,
+          escrowVault: vaultPDA(id),
+          config: configPDA,
+          clientBlock: blockedPDA(client.publicKey),
+          freelancerBlock: blockedPDA(freelancer.publicKey),
+          clientKyc: kycPDA(client.publicKey),
+          freelancerKyc: kycPDA(freelancer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([client])
+        .rpc();
+      await acceptContract(pda);
+      const sizeBefore = (await provider.connection.getAccountInfo(pda)).data.length;
+
+      // Four per call keeps each transaction under the size limit
+      for (let batch = 0; batch < 6; batch++) {
+        const milestones = [1, 2, 3, 4].map((j) => milestone(10 * (batch * 4 + j), `M${batch * 4 + j}`));
+        await addMilestones(pda, milestones);
+      }
+
+      const contract = await program.account.contract.fetch(pda);
+      expect(contract.milestones.length).to.equal(25);
+      expect(contract.milestones[24].description).to.equal("M24");
+      const sum = contract.milestones.reduce((total, m) => total.add(m.amount), new BN(0));
+      expect(sum.toString()).to.equal(contract.totalAmount.toString());
+      expect(contract.totalAmount.toNumber()).to.equal(100 + 10 * ((24 * 25) / 2));
+
+      // The client paid to keep the grown account rent exempt
+      const info = await provider.connection.getAccountInfo(pda);
+      expect(info.data.length).to.be.greaterThan(sizeBefore);
+      const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      expect(info.lamports).to.be.at.least(rentExempt);
+    });
+
+    it("refuses to add milestones once the escrow is funded", async () => {
+      const { pda } = await createFundedContract([100]);
+      try {
+        await addMilestones(pda, [milestone(50, "Extra")]);
+        expect.fail("should have refused a funded contract");
+      } catch (err) {
+        expect(err.toString()).to.include("CannotAmendFunded");
+      }
+    });
+  });
+});
+
+// Helper to build a pending milestone for create_contract
+function milestone(amount: number | BN, description: string, overrides: Partial<Record<string, any>> = {}) {
+  return {
+    amount: new BN(amount),
+    description,
+    dueDate: null,
+    penaltyPerDayBps: 0,
+    bonusAmount: new BN(0),
+    minReviewSeconds: new BN(0),
+    displayCurrency: null,
+    displayRate: new BN(0),
+    expectedFormat: null,
+    maxRevisions: 0,
+    vestingSecs: null,
+    ...overrides,
+  };
+}
+
+// Default contract terms; override individual fields per test
+function defaultTerms(overrides: Partial<Record<string, any>> = {}) {
+  return {
+    retainageBps: 0,
+    feeBps: 0,
+    scopeResolver: null,
+    completionCallbackProgram: null,
+    bonusMint: null,
+    rejectionContestWindow: new BN(0),
+    feeOnTips: false,
+    resolutionFinalityWindow: new BN(0),
+    cancelApprovers: [],
+    cancelThreshold: 0,
+    requireKyc: false,
+    flagMissedDeadlines: false,
+    swapProgram: null,
+    disputeResolutionWindow: new BN(0),
+    clawbackWindow: new BN(0),
+    sequential: false,
+    sponsor: null,
+    resubmitWindow: new BN(0),
+    amountsInBps: false,
+    fundingDelegate: null,
+    requireSignOff: false,
+    reviewWindow: new BN(0),
+    isNative: false,
+    disputeBond: new BN(0),
+    arbitrators: [],
+    arbitrationThreshold: 0,
+    ordered: false,
+    deadline: new BN(0),
+    arbitratorFeeBps: 0,
+    seniorArbitrator: null,
+    appealBond: new BN(0),
+    grossUpTransferFees: false,
+    ...overrides,
+  };
+}
 
 // Helper to create deterministic description hash
 function hashDescription(description: string): number[] {